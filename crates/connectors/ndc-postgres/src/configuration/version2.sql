@@ -4,18 +4,21 @@
 -- The data model of these tables is quite involved and carries with it decades
 -- of legacy. Supporting notes on this are kept in 'introspection-notes.md'.
 --
--- TODO: This uses unqualified table (and view) and constraint names.
+-- TODO: This uses unqualified constraint names.
 --       We will need to qualify them at some point. This makes the aliases seem
 --       redundant, but they will change in the future.
---       If similar named tables exist in different schemas it is arbitrary
---       which one we pick currently! (c.f. Citus schemas 'columnar' and
---       'columnar_internal' which both have a 'chunk' table)
+--
+-- Tables with the same name may exist in different schemas (c.f. Citus schemas
+-- 'columnar' and 'columnar_internal' which both have a 'chunk' table). These
+-- are all included, and are told apart by prefixing the schema name to the
+-- name of the collection (unless the schema is one of the unqualified schemas
+-- and no other unqualified schema has a table of the same name).
 
 -- When debugging in 'psql', uncomment the lines below to be able to run the
 -- query with arguments set.
 
 -- DEALLOCATE ALL; -- Or use 'DEALLOCATE configuration' between reloads
--- PREPARE configuration(varchar[], varchar[], jsonb) AS
+-- PREPARE configuration(varchar[], varchar[], jsonb, varchar[], boolean, bigint) AS
 
 WITH
   -- The overall structure of this query is a CTE (i.e. 'WITH .. SELECT')
@@ -59,13 +62,15 @@ WITH
       cl.relnamespace AS schema_id,
       cl.oid AS relation_id,
       cl.relname relation_name,
-      cl.relkind relation_kind
+      cl.relkind relation_kind,
+      cl.relam access_method_id,
+      cl.relrowsecurity row_security_enabled
     FROM
       pg_class cl
   ),
   queryable_relations AS
   (
-    SELECT DISTINCT ON (relation_name) relations.*
+    SELECT relations.*
     FROM relations
     WHERE relation_kind IN
       -- Lots of different types of relations exist, but we're only interested in
@@ -83,10 +88,49 @@ WITH
         -- I = partitioned index
       )
 
-    -- Since we will _not_ be grouping by a key we need this to be ordered
-    -- to get deterministic results.
-    -- (Specificically, we do not yet take schemas into account)
-    ORDER BY relation_name, schema_id, relation_kind
+    -- Relation names are unique within a schema, so there is no need to pick
+    -- between relations of the same name here.
+  ),
+
+  -- The names of the collections of the queryable relations. Relations in the
+  -- unqualified schemas are named by their relation name alone, unless another
+  -- unqualified schema has a relation of the same name, in which case all of
+  -- them are prefixed by their schema name so that none of them is lost.
+  collection_names AS
+  (
+    SELECT
+      rel.relation_id,
+      CASE
+        WHEN
+          s.schema_name = ANY ($2)
+          AND count(*) OVER (
+            PARTITION BY rel.relation_name, s.schema_name = ANY ($2)
+          ) = 1
+        THEN rel.relation_name
+        ELSE s.schema_name || '_' || rel.relation_name
+      END
+      AS collection_name
+    FROM
+      queryable_relations
+      AS rel
+    INNER JOIN schemas
+      AS s
+      USING (schema_id)
+  ),
+
+  -- The collections which are introspected: the first 'maxTables' ($6) in the
+  -- order of their names, or all of them when it is null. Comparing the names
+  -- byte by byte keeps the same ones as ordering them in the configuration.
+  introspected_collections AS
+  (
+    SELECT
+      names.*
+    FROM
+      collection_names
+      AS names
+    ORDER BY
+      names.collection_name COLLATE "C"
+    LIMIT $6
   ),
 
   -- Columns are recorded in `pg_attribute`. An 'attribute' is the generic term
@@ -102,17 +146,109 @@ WITH
       att.attnum AS column_number,
       att.atttypid AS type_id,
       CASE WHEN att.attnotnull THEN 'nonNullable' ELSE 'nullable' END
-      AS nullable
-      -- Columns that will likely be of interest soon:
-      -- attidentity
-      -- attgenerated
-      -- atthasdef
+      AS nullable,
+      -- The collation of the column, when it is not the default one of its
+      -- type, as it decides how the column's values compare and sort.
+      coll.collname AS collation_name,
+      -- The declared length of 'varchar(n)' and 'char(n)' columns. The type
+      -- modifier of an array column is that of its elements, so this is also
+      -- the length of the elements of 'varchar(n)[]' columns. The number of
+      -- dimensions of an array column ('attndims') is not enforced by postgres,
+      -- so we expose every array as one-dimensional.
+      --
+      -- A column of a domain type has no type modifier of its own, so we use
+      -- the domain's.
+      CASE
+        WHEN base_typ.typname IN ('varchar', 'bpchar')
+        THEN nullif(greatest(att.atttypmod, elem_typ.typtypmod), -1) - 4
+      END
+      AS max_length,
+      -- Generated columns have their expression recorded as a default, but
+      -- it is not one inserts can rely on, as they cannot give them values.
+      att.atthasdef AND att.attgenerated = '' AS has_default,
+      CASE att.attidentity
+        WHEN 'a' THEN 'identityAlways'
+        WHEN 'd' THEN 'identityByDefault'
+        ELSE 'notIdentity'
+      END
+      AS is_identity,
+      att.attgenerated <> '' AS is_generated
     FROM
       pg_catalog.pg_attribute AS att
+    LEFT OUTER JOIN
+      pg_catalog.pg_type AS typ
+      ON (typ.oid = att.atttypid)
+    LEFT OUTER JOIN
+      pg_catalog.pg_collation AS coll
+      ON (coll.oid = att.attcollation AND att.attcollation <> typ.typcollation)
+    -- The element type of array columns, and the type itself otherwise.
+    LEFT OUTER JOIN
+      pg_catalog.pg_type AS elem_typ
+      ON (
+        elem_typ.oid =
+          CASE WHEN typ.typcategory = 'A' THEN typ.typelem ELSE typ.oid END
+      )
+    -- The type underlying the element type, if it is a domain.
+    LEFT OUTER JOIN
+      pg_catalog.pg_type AS base_typ
+      ON (
+        base_typ.oid =
+          CASE
+            WHEN elem_typ.typtype = 'd' THEN elem_typ.typbasetype
+            ELSE elem_typ.oid
+          END
+      )
     WHERE
       -- We only include columns that are actually part of the table currently.
       NOT att.attisdropped -- This table also records historic columns.
-      AND att.attnum > 0   -- attnum <= 0 are special system-defined columns.
+      AND (
+        att.attnum > 0     -- attnum <= 0 are special system-defined columns.
+        -- Some system columns are included when they are asked for, so that
+        -- for instance 'xmin' can be used as a row version.
+        OR (
+          att.attname = ANY ($4)
+          AND att.attname IN ('ctid', 'xmin', 'tableoid')
+        )
+      )
+  ),
+
+  -- The sequences that generate the values of serial and identity columns
+  -- depend on their column, automatically for serial columns ('a') and
+  -- internally for identity columns ('i'). The last value a sequence handed
+  -- out estimates the largest value of its column, without scanning the table.
+  -- It is neither transactionally consistent nor exact, as values handed out by
+  -- transactions which rolled back are never used, and it changes with every
+  -- insert, so it is only introspected when asked for.
+  --
+  -- 'pg_sequences' leaves out the last value of sequences we may not read.
+  column_sequence_values AS
+  (
+    SELECT
+      dep.refobjid AS relation_id,
+      att.attname AS column_name,
+      seq.last_value
+    FROM
+      pg_catalog.pg_depend AS dep
+    INNER JOIN
+      pg_catalog.pg_class AS seq_class
+      ON (seq_class.oid = dep.objid AND seq_class.relkind = 'S')
+    INNER JOIN
+      pg_catalog.pg_namespace AS seq_ns
+      ON (seq_ns.oid = seq_class.relnamespace)
+    INNER JOIN
+      pg_catalog.pg_sequences AS seq
+      ON (
+        seq.schemaname = seq_ns.nspname
+        AND seq.sequencename = seq_class.relname
+      )
+    INNER JOIN
+      pg_catalog.pg_attribute AS att
+      ON (att.attrelid = dep.refobjid AND att.attnum = dep.refobjsubid)
+    WHERE
+      $5
+      AND dep.classid = 'pg_catalog.pg_class'::regclass
+      AND dep.refclassid = 'pg_catalog.pg_class'::regclass
+      AND dep.deptype IN ('a', 'i')
   ),
 
   -- Comments on database objects are recorded in `pg_description`. See
@@ -344,7 +480,7 @@ WITH
   --
   -- In PostgreSQL, operators and aggregation functions each relate to a `pg_proc`
   -- procedure. On CockroachDB, however, they are independent.
-  comparison_operators AS
+  catalog_comparison_operators AS
   (
     SELECT
       op.oprname AS operator_name,
@@ -370,6 +506,31 @@ WITH
     ORDER BY op.oprname
   ),
 
+  -- PostgreSQL rewrites 'SIMILAR TO' to a regular expression match, so it is
+  -- not recorded in 'pg_operator'. It applies to the same types as 'LIKE'.
+  comparison_operators AS
+  (
+    SELECT * FROM catalog_comparison_operators
+    UNION
+    SELECT
+      similar_to.operator_name,
+      op.argument1_type,
+      op.argument2_type
+    FROM
+      catalog_comparison_operators
+      AS op
+    CROSS JOIN
+      (VALUES ('SIMILAR TO'), ('NOT SIMILAR TO'))
+      AS similar_to(operator_name)
+    WHERE
+      op.operator_name = '~~'
+      AND NOT EXISTS
+        (
+          SELECT 1 FROM catalog_comparison_operators
+          WHERE operator_name = 'SIMILAR TO'
+        )
+  ),
+
   implicit_casts AS
   (
     SELECT
@@ -456,7 +617,8 @@ WITH
   (
     SELECT
       v ->> 'operatorName' AS operator_name,
-      v ->> 'exposedName' AS exposed_name
+      v ->> 'exposedName' AS exposed_name,
+      v ->> 'collation' AS collation
     FROM
       jsonb_array_elements($3) AS v
   ),
@@ -567,21 +729,210 @@ WITH
       constraints AS c
     WHERE
       c.constraint_type = 'f' -- For foreign-key constraints
+  ),
+
+  -- Foreign keys are recorded under the names of their constraints, which
+  -- are often generated, like 'Album_ArtistId_fkey'. A friendlier name can be
+  -- given with a comment on the constraint:
+  --
+  --   COMMENT ON CONSTRAINT "Album_ArtistId_fkey" ON "Album" IS 'artist'
+  --
+  -- Constraints without a comment, or whose comment names another foreign
+  -- key of the same table as well, keep the name of the constraint.
+  foreign_key_names AS
+  (
+    SELECT
+      constraint_id,
+      CASE
+        WHEN count(*) OVER (PARTITION BY relation_id, coalesce(comment, constraint_name)) = 1
+          THEN coalesce(comment, constraint_name)
+        ELSE constraint_name
+      END AS foreign_key_name
+    FROM
+    (
+      SELECT
+        fk.constraint_id,
+        fk.relation_id,
+        fk.constraint_name,
+        nullif(btrim(comm.description), '') AS comment
+      FROM
+        foreign_key_constraints AS fk
+      LEFT OUTER JOIN
+        pg_description AS comm
+        ON (
+          comm.classoid = 'pg_catalog.pg_constraint'::regclass
+          AND comm.objoid = fk.constraint_id
+          AND comm.objsubid = 0
+        )
+    ) AS fk
+  ),
+
+  -- Check constraints of the form 'column IN (<literal>, ...)' are commonly
+  -- used to emulate enums. We recognize these and record the values they
+  -- allow.
+  --
+  -- Postgres does not keep the original text of a check constraint, so we
+  -- work on the definition as it is reconstructed by 'pg_get_constraintdef',
+  -- where such a constraint reads like:
+  --
+  --   CHECK ((status = ANY (ARRAY['a'::text, 'b'::text])))
+  --
+  -- We only recognize constraints on a single column that consist of a list
+  -- of string literals. Anything more complex is skipped.
+  column_allowed_values AS
+  (
+    WITH
+      check_constraint_literals AS
+      (
+        SELECT
+          c.conname AS constraint_name,
+          c.conrelid AS relation_id,
+          c.conkey[1] AS column_number,
+          substring(
+            pg_get_constraintdef(c.oid)
+            FROM
+            '^CHECK \(\(\(?(?:"(?:[^"]|"")*"|[^ ()"]+)\)?(?:::[a-z ]+)? = ANY \(\(?ARRAY\[(''(?:[^'']|'''')*''::[a-z ]+(?:, ''(?:[^'']|'''')*''::[a-z ]+)*)\]\)?(?:::[a-z ]+\[\])?\)\)\)$'
+          )
+          AS literals
+        FROM
+          pg_catalog.pg_constraint AS c
+        WHERE
+          c.contype = 'c' -- For check constraints
+          AND cardinality(c.conkey) = 1
+      ),
+      check_constraint_values AS
+      (
+        -- Should a column have several such constraints, we pick one of them
+        -- deterministically.
+        SELECT DISTINCT ON (relation_id, column_number)
+          con.relation_id,
+          con.column_number,
+          array(
+            SELECT
+              replace(m.literal[1], '''''', '''')
+            FROM
+              regexp_matches(con.literals, '''((?:[^'']|'''')*)''::', 'g')
+              WITH ORDINALITY
+              AS m(literal, ordinality)
+            ORDER BY m.ordinality
+          )
+          AS allowed_values
+        FROM
+          check_constraint_literals
+          AS con
+        WHERE
+          con.literals IS NOT NULL
+        ORDER BY relation_id, column_number, constraint_name
+      )
+    SELECT
+      col.relation_id,
+      col.column_name,
+      con.allowed_values
+    FROM
+      check_constraint_values
+      AS con
+    INNER JOIN
+      columns
+      AS col
+      USING (relation_id, column_number)
+  ),
+
+  -- Indexes are recorded in 'pg_index', see
+  -- https://www.postgresql.org/docs/current/catalog-pg-index.html for its
+  -- schema.
+  --
+  -- We only record partial indexes, along with their predicate, as queries
+  -- can only make use of them when they imply that predicate.
+  partial_indexes AS
+  (
+    SELECT
+      i.indrelid AS relation_id,
+      index_relation.relname AS index_name,
+      pg_get_expr(i.indpred, i.indrelid) AS predicate
+    FROM
+      pg_catalog.pg_index
+      AS i
+    INNER JOIN
+      pg_catalog.pg_class
+      AS index_relation
+      ON (index_relation.oid = i.indexrelid)
+    WHERE
+      i.indpred IS NOT NULL
+  ),
+
+  -- Row-level security policies are recorded in 'pg_policy', see
+  -- https://www.postgresql.org/docs/current/catalog-pg-policy.html for its
+  -- schema. We only record them for information, as the database enforces
+  -- them by itself.
+  rls_policies AS
+  (
+    SELECT
+      pol.polrelid AS relation_id,
+      pol.polname AS policy_name,
+      CASE pol.polcmd
+        WHEN 'r' THEN 'select'
+        WHEN 'a' THEN 'insert'
+        WHEN 'w' THEN 'update'
+        WHEN 'd' THEN 'delete'
+        ELSE 'all'
+      END AS command
+    FROM
+      pg_catalog.pg_policy
+      AS pol
+  ),
+
+  -- Triggers are recorded in 'pg_trigger', see
+  -- https://www.postgresql.org/docs/current/catalog-pg-trigger.html for its
+  -- schema. Their timing and events are bits of 'tgtype', as defined in
+  -- 'src/include/catalog/pg_trigger.h'. We leave out the internal triggers
+  -- which enforce constraints, such as foreign keys, and only record the rest
+  -- for information.
+  triggers AS
+  (
+    SELECT
+      t.tgrelid AS relation_id,
+      t.tgname AS trigger_name,
+      CASE
+        WHEN t.tgtype & 2 <> 0 THEN 'before'
+        WHEN t.tgtype & 64 <> 0 THEN 'insteadOf'
+        ELSE 'after'
+      END AS timing,
+      (
+        SELECT
+          jsonb_agg(event.event_name ORDER BY event.position)
+        FROM
+          (
+            VALUES
+              (1, 4, 'insert'),
+              (2, 16, 'update'),
+              (3, 8, 'delete'),
+              (4, 32, 'truncate')
+          )
+          AS event(position, bit, event_name)
+        WHERE
+          t.tgtype & event.bit <> 0
+      ) AS events
+    FROM
+      pg_catalog.pg_trigger
+      AS t
+    WHERE
+      NOT t.tgisinternal
   )
 SELECT
   coalesce(tables.result, '{}'::jsonb) AS "Tables" ,
   coalesce(aggregate_functions.result, '{}'::jsonb) AS "AggregateFunctions",
-  coalesce(comparison_functions.result, '{}'::jsonb) as "ComparisonFunctions"
+  coalesce(comparison_functions.result, '{}'::jsonb) as "ComparisonFunctions",
+  coalesce(extensions.result, '{}'::jsonb) AS "Extensions",
+  coalesce(enum_types.result, '{}'::jsonb) AS "EnumTypes",
+  (SELECT count(*) FROM collection_names)
+    - (SELECT count(*) FROM introspected_collections)
+    AS "TablesLeftOut"
 FROM
   (
     -- Tables and views
     SELECT
       jsonb_object_agg(
-        CASE
-          WHEN s.schema_name = ANY ($2)
-          THEN rel.relation_name
-          ELSE s.schema_name || '_' || rel.relation_name
-        END,
+        names.collection_name,
         jsonb_build_object(
           'schemaName',
           s.schema_name,
@@ -589,12 +940,29 @@ FROM
           rel.relation_name,
           'description',
           comm.description,
+          'accessMethod',
+          am.amname,
+          'foreignServer',
+          fs.srvname,
           'columns',
           columns_info.result,
           'uniquenessConstraints',
           coalesce(uniqueness_constraints_info.result, '{}'::jsonb),
           'foreignRelations',
-          coalesce(foreign_key_constraints_info.result, '{}'::jsonb)
+          coalesce(foreign_key_constraints_info.result, '{}'::jsonb),
+          'indexes',
+          coalesce(indexes_info.result, '{}'::jsonb),
+          'rls',
+          CASE
+            WHEN rel.row_security_enabled
+            THEN
+              jsonb_build_object(
+                'policies',
+                coalesce(rls_info.result, '{}'::jsonb)
+              )
+          END,
+          'triggers',
+          coalesce(triggers_info.result, '{}'::jsonb)
         )
       )
       AS result
@@ -607,10 +975,36 @@ FROM
       AS comm
       USING (relation_id)
 
+    -- The table access method (storage engine), see
+    -- https://www.postgresql.org/docs/current/catalog-pg-am.html for its
+    -- schema. Views and foreign tables don't have one.
+    LEFT OUTER JOIN
+      pg_catalog.pg_am
+      AS am
+      ON (am.oid = rel.access_method_id)
+
+    -- The server of a foreign table, see
+    -- https://www.postgresql.org/docs/current/catalog-pg-foreign-table.html
+    -- and https://www.postgresql.org/docs/current/catalog-pg-foreign-server.html
+    -- for their schemas.
+    LEFT OUTER JOIN
+      pg_catalog.pg_foreign_table
+      AS ft
+      ON (ft.ftrelid = rel.relation_id)
+
+    LEFT OUTER JOIN
+      pg_catalog.pg_foreign_server
+      AS fs
+      ON (fs.oid = ft.ftserver)
+
     INNER JOIN schemas
       AS s
       USING (schema_id)
 
+    INNER JOIN introspected_collections
+      AS names
+      USING (relation_id)
+
     -- Columns
     INNER JOIN
     (
@@ -652,7 +1046,21 @@ FROM
             'nullable',
             c.nullable,
             'description',
-            comm.description
+            comm.description,
+            'allowedValues',
+            allowed.allowed_values,
+            'collation',
+            c.collation_name,
+            'maxLength',
+            c.max_length,
+            'sequenceLastValue',
+            seq.last_value,
+            'hasDefault',
+            c.has_default,
+            'isIdentity',
+            c.is_identity,
+            'isGenerated',
+            c.is_generated
             )
         )
         AS result
@@ -664,6 +1072,12 @@ FROM
       LEFT OUTER JOIN column_comments
         AS comm
         USING (relation_id, column_name)
+      LEFT OUTER JOIN column_allowed_values
+        AS allowed
+        USING (relation_id, column_name)
+      LEFT OUTER JOIN column_sequence_values
+        AS seq
+        USING (relation_id, column_name)
       GROUP BY relation_id
       HAVING
         -- All columns must have a supported type for us to list this table.
@@ -737,12 +1151,15 @@ FROM
             -- which we can then re-group and aggregate as json.
             SELECT
               relation_id,
-              constraint_name,
+              foreign_key_name AS constraint_name,
               unnest(key_columns) as key_column,
               referenced_relation_id,
               unnest(referenced_columns) as referenced_column
             FROM
              foreign_key_constraints
+            INNER JOIN
+             foreign_key_names
+             USING (constraint_id)
           )
           AS con
           GROUP BY
@@ -752,6 +1169,12 @@ FROM
       INNER JOIN relations
         AS foreign_relation
         ON foreign_relation.relation_id = con.referenced_relation_id
+      -- Foreign keys are only kept when the tables they reference are
+      -- introspected too, as the schema has no collection to relate them to
+      -- otherwise.
+      INNER JOIN introspected_collections
+        AS foreign_collection
+        ON foreign_collection.relation_id = con.referenced_relation_id
       INNER JOIN schemas
         AS foreign_schema
         ON foreign_relation.schema_id = foreign_schema.schema_id
@@ -760,6 +1183,68 @@ FROM
     AS foreign_key_constraints_info
     USING (relation_id)
 
+    -- Partial indexes
+    LEFT OUTER JOIN
+    (
+      SELECT
+        idx.relation_id,
+        jsonb_object_agg(
+          idx.index_name,
+          jsonb_build_object(
+            'predicate',
+            idx.predicate
+          )
+        )
+        AS result
+      FROM partial_indexes
+        AS idx
+      GROUP BY relation_id
+    )
+    AS indexes_info
+    USING (relation_id)
+
+    -- Row-level security policies
+    LEFT OUTER JOIN
+    (
+      SELECT
+        pol.relation_id,
+        jsonb_object_agg(
+          pol.policy_name,
+          jsonb_build_object(
+            'command',
+            pol.command
+          )
+        )
+        AS result
+      FROM rls_policies
+        AS pol
+      GROUP BY relation_id
+    )
+    AS rls_info
+    USING (relation_id)
+
+    -- Triggers
+    LEFT OUTER JOIN
+    (
+      SELECT
+        trig.relation_id,
+        jsonb_object_agg(
+          trig.trigger_name,
+          jsonb_build_object(
+            'timing',
+            trig.timing,
+            'events',
+            trig.events
+          )
+        )
+        AS result
+      FROM triggers
+        AS trig
+      GROUP BY relation_id
+    )
+    AS triggers_info
+    USING (relation_id)
+
   ) AS tables
 
   -- Aggregation functions
@@ -816,7 +1301,31 @@ FROM
   (
     -- Comparison Operators
     WITH
-      comparison_operators_mapped AS
+      -- The key/value operators of the 'hstore' extension are exposed under
+      -- fixed names when it is installed, as their operator names are
+      -- shared with unrelated operators on other types.
+      hstore_operators_mapped AS
+      (
+        SELECT
+          map.exposed_name,
+          op.operator_name,
+          op.argument1_type,
+          op.argument2_type
+        FROM
+          comparison_operators
+          AS op
+        INNER JOIN
+          (VALUES ('?', '_has_key'), ('@>', '_contains'))
+          AS map(operator_name, exposed_name)
+          USING (operator_name)
+        WHERE
+          op.argument1_type = 'hstore'
+          AND EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'hstore')
+      ),
+
+      -- The trigram similarity operator of the 'pg_trgm' extension, when it is
+      -- installed.
+      trigram_operators_mapped AS
       (
         SELECT
           map.exposed_name,
@@ -826,10 +1335,51 @@ FROM
         FROM
           comparison_operators_cast_extended
           AS op
+        INNER JOIN
+          (VALUES ('%', '_word_similar'))
+          AS map(operator_name, exposed_name)
+          USING (operator_name)
+        WHERE
+          op.argument1_type IN ('text', 'varchar', 'bpchar')
+          AND EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_trgm')
+      ),
+
+      comparison_operators_mapped AS
+      (
+        SELECT
+          map.exposed_name,
+          op.operator_name,
+          op.argument1_type,
+          op.argument2_type,
+          map.collation
+        FROM
+          comparison_operators_cast_extended
+          AS op
         INNER JOIN
           operator_mappings
           AS map
           USING (operator_name)
+        WHERE
+          -- An operator mapped with a collation only applies to the types
+          -- which support collations.
+          map.collation IS NULL
+          OR EXISTS
+            (
+              SELECT 1
+              FROM
+                scalar_types
+                AS t
+              INNER JOIN
+                pg_type
+                ON (pg_type.oid = t.type_id)
+              WHERE
+                t.type_name = op.argument1_type
+                AND pg_type.typcollation <> 0
+            )
+        UNION
+        SELECT *, NULL FROM hstore_operators_mapped
+        UNION
+        SELECT *, NULL FROM trigram_operators_mapped
       ),
 
       -- When an operator is overloaded for a type (either explicitly or
@@ -841,7 +1391,8 @@ FROM
           op.exposed_name,
           op.operator_name,
           op.argument1_type,
-          op.argument2_type
+          op.argument2_type,
+          op.collation
         FROM
           comparison_operators_mapped
           AS op
@@ -851,6 +1402,28 @@ FROM
           op.argument1_type = op.argument2_type DESC
       ),
 
+      -- 'IS DISTINCT FROM' is not an operator of its own, but it applies
+      -- wherever inequality does. Unlike '<>', it holds when only one side is
+      -- NULL, so comparing a nullable column with a value includes the rows
+      -- where the column is NULL.
+      comparison_operators_with_distinct AS
+      (
+        SELECT * FROM comparison_operators_filtered
+        UNION ALL
+        SELECT
+          '_neq_or_null' AS exposed_name,
+          'IS DISTINCT FROM' AS operator_name,
+          op.argument1_type,
+          op.argument2_type,
+          NULL AS collation
+        FROM
+          comparison_operators_filtered
+          AS op
+        WHERE
+          op.operator_name IN ('<>', '!=')
+          AND op.collation IS NULL
+      ),
+
       comparison_operators_by_first_arg AS
       (
         SELECT
@@ -861,10 +1434,14 @@ FROM
               'operatorName', op.operator_name,
               'argumentType', op.argument2_type
             )
+            || CASE
+              WHEN op.collation IS NULL THEN '{}'
+              ELSE jsonb_build_object('collation', op.collation)
+            END
           )
           AS result
         FROM
-          comparison_operators_filtered
+          comparison_operators_with_distinct
           AS op
         GROUP BY op.argument1_type
       )
@@ -876,7 +1453,67 @@ FROM
     FROM
       comparison_operators_by_first_arg
       AS op
-  ) AS comparison_functions;
+  ) AS comparison_functions
+
+  -- Installed extensions, which some operators and types depend on. These are
+  -- represented as a json object of the form:
+  --
+  --   {
+  --     <extension name>:
+  --       {
+  --         'schemaName': <schema name>
+  --       }
+  --   }
+  --
+  -- The version is left out, as it changes with minor upgrades of the database
+  -- without affecting what we expose.
+  CROSS JOIN
+  (
+    SELECT
+      jsonb_object_agg(
+        ext.extname,
+        jsonb_build_object(
+          'schemaName',
+          ns.nspname
+        )
+      ) AS result
+    FROM
+      pg_catalog.pg_extension
+      AS ext
+    INNER JOIN
+      pg_catalog.pg_namespace
+      AS ns
+      ON (ns.oid = ext.extnamespace)
+  ) AS extensions
+
+  -- The labels of enum types, see
+  -- https://www.postgresql.org/docs/current/catalog-pg-enum.html for its
+  -- schema. These are represented as a json object of the form:
+  --
+  --   {
+  --     <type name>: [<label>, ...]
+  --   }
+  --
+  -- with the labels in the order they sort in.
+  CROSS JOIN
+  (
+    SELECT
+      jsonb_object_agg(t.type_name, labels.labels) AS result
+    FROM
+      scalar_types
+      AS t
+    INNER JOIN
+    (
+      SELECT
+        e.enumtypid AS type_id,
+        jsonb_agg(e.enumlabel ORDER BY e.enumsortorder) AS labels
+      FROM
+        pg_catalog.pg_enum
+        AS e
+      GROUP BY e.enumtypid
+    ) AS labels
+    USING (type_id)
+  ) AS enum_types;
 
 -- Uncomment the following lines to just run the configuration query with reasonable default arguments
 --
@@ -899,4 +1536,7 @@ FROM
 --     {"operatorName": "!~", "exposedName": "_nregex"},
 --     {"operatorName": "~*", "exposedName": "_iregex"},
 --     {"operatorName": "!~*", "exposedName": "_niregex"}
---    ]'::jsonb);
+--    ]'::jsonb,
+--   '{}'::varchar[],
+--   false,
+--   NULL);
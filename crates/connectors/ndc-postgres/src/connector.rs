@@ -6,6 +6,7 @@
 //! `super::configuration`.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use tracing::{info_span, Instrument};
@@ -88,26 +89,22 @@ impl connector::Connector for Postgres {
     ) -> Result<Self::State, connector::InitializationError> {
         let runtime_configuration = configuration::as_runtime_configuration(configuration);
 
-        state::create_state(
-            &runtime_configuration.connection_uri,
-            &runtime_configuration.pool_settings,
-            metrics,
-        )
-        .instrument(info_span!("Initialise state"))
-        .await
-        .map(Arc::new)
-        .map_err(|err| connector::InitializationError::Other(err.into()))
-        .map_err(|err| {
-            tracing::error!(
-                meta.signal_type = "log",
-                event.domain = "ndc",
-                event.name = "Initialization error",
-                name = "Initialization error",
-                body = %err,
-                error = true,
-            );
-            err
-        })
+        state::create_state_for_region(&runtime_configuration, metrics)
+            .instrument(info_span!("Initialise state"))
+            .await
+            .map(Arc::new)
+            .map_err(|err| connector::InitializationError::Other(err.into()))
+            .map_err(|err| {
+                tracing::error!(
+                    meta.signal_type = "log",
+                    event.domain = "ndc",
+                    event.name = "Initialization error",
+                    name = "Initialization error",
+                    body = %err,
+                    error = true,
+                );
+                err
+            })
     }
 
     /// Update any metrics from the state
@@ -130,10 +127,19 @@ impl connector::Connector for Postgres {
     /// For example, this function should check that the connector
     /// is able to reach its data source over the network.
     async fn health_check(
-        _configuration: &Self::Configuration,
+        configuration: &Self::Configuration,
         state: &Self::State,
     ) -> Result<(), connector::HealthError> {
-        health::health_check(&state.pool).await.map_err(|err| {
+        let (max_replication_lag, validation_query) =
+            configuration::health_check_settings(configuration);
+        health::health_check(
+            &state.pool,
+            &state.metrics,
+            max_replication_lag.map(Duration::from_secs),
+            validation_query,
+        )
+        .await
+        .map_err(|err| {
             tracing::error!(
                 meta.signal_type = "log",
                 event.domain = "ndc",
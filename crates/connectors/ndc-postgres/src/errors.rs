@@ -0,0 +1,32 @@
+//! Report the errors of running requests to clients as verbosely as configured.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::configuration::version2::ErrorVerbosity;
+
+/// The message returned to clients for an error which occurred while running a request, whether
+/// the database reported it or not.
+///
+/// When sanitized, the error is logged with a correlation id instead, which the message gives so
+/// that a client's report can be matched up with the log.
+pub fn error_message(verbosity: ErrorVerbosity, err: &impl std::fmt::Display) -> String {
+    match verbosity {
+        ErrorVerbosity::Detailed => err.to_string(),
+        ErrorVerbosity::Sanitized => {
+            let correlation_id = correlation_id();
+            tracing::error!(
+                correlation_id = %correlation_id,
+                error = %err,
+                "Request error"
+            );
+            format!("the request could not be run (correlation id: {correlation_id})")
+        }
+    }
+}
+
+/// A random identifier for an error. The hashers of the standard library are seeded randomly,
+/// which saves depending on a random number generator.
+fn correlation_id() -> String {
+    format!("{:016x}", RandomState::new().build_hasher().finish())
+}
@@ -13,6 +13,7 @@ use query_engine_sql::sql;
 use query_engine_translation::translation;
 
 use super::configuration;
+use super::errors;
 use super::state;
 
 /// Explain a query by creating an execution plan
@@ -41,6 +42,7 @@ pub async fn explain<'a>(
             &state.database_info,
             &state.metrics,
             plan,
+            to_explain_format(configuration.explain_format),
         )
         .instrument(info_span!("Explain query"))
         .await
@@ -58,14 +60,37 @@ pub async fn explain<'a>(
                     query_engine_execution::query::QueryError::NotSupported(_) => {
                         state.metrics.error_metrics.record_unsupported_feature()
                     }
+                    query_engine_execution::query::QueryError::NativeQueryColumnTypeMismatch {
+                        ..
+                    } => state.metrics.error_metrics.record_connector_error(),
+                    query_engine_execution::query::QueryError::SqlTooLong(_) => {
+                        state.metrics.error_metrics.record_invalid_request()
+                    }
                 }
 
-                connector::ExplainError::Other(err.to_string().into())
+                if let query_engine_execution::query::QueryError::SqlTooLong(_) = err {
+                    connector::ExplainError::InvalidRequest(errors::error_message(
+                        configuration.error_verbosity,
+                        &err,
+                    ))
+                } else {
+                    connector::ExplainError::Other(
+                        errors::error_message(configuration.error_verbosity, &err).into(),
+                    )
+                }
             }
             query_engine_execution::query::Error::DB(err) => {
                 tracing::error!("{}", err);
                 state.metrics.error_metrics.record_database_error();
-                connector::ExplainError::Other(err.to_string().into())
+                connector::ExplainError::Other(
+                    errors::error_message(configuration.error_verbosity, &err).into(),
+                )
+            }
+            query_engine_execution::query::Error::PoolSaturated(err) => {
+                tracing::warn!("{}", err);
+                connector::ExplainError::Other(
+                    errors::error_message(configuration.error_verbosity, &err).into(),
+                )
             }
         })?;
 
@@ -82,6 +107,15 @@ pub async fn explain<'a>(
     .await
 }
 
+fn to_explain_format(
+    explain_format: Option<configuration::version2::ExplainFormat>,
+) -> sql::ast::ExplainFormat {
+    match explain_format {
+        None | Some(configuration::version2::ExplainFormat::Text) => sql::ast::ExplainFormat::Text,
+        Some(configuration::version2::ExplainFormat::Json) => sql::ast::ExplainFormat::Json,
+    }
+}
+
 fn plan_query(
     configuration: &configuration::RuntimeConfiguration,
     state: &state::State,
@@ -89,23 +123,27 @@ fn plan_query(
 ) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>, connector::ExplainError>
 {
     let timer = state.metrics.time_query_plan();
-    let result =
-        translation::query::translate(&configuration.metadata, query_request).map_err(|err| {
-            tracing::error!("{}", err);
-            match err {
-                translation::error::Error::CapabilityNotSupported(_) => {
-                    state.metrics.error_metrics.record_unsupported_capability();
-                    connector::ExplainError::UnsupportedOperation(err.to_string())
-                }
-                translation::error::Error::NotImplementedYet(_) => {
-                    state.metrics.error_metrics.record_unsupported_feature();
-                    connector::ExplainError::UnsupportedOperation(err.to_string())
-                }
-                _ => {
-                    state.metrics.error_metrics.record_invalid_request();
-                    connector::ExplainError::InvalidRequest(err.to_string())
-                }
+    let result = translation::query::translate(
+        &configuration.metadata,
+        configuration.query_options(state.database_info.is_cockroach()),
+        query_request,
+    )
+    .map_err(|err| {
+        tracing::error!("{}", err);
+        match err {
+            translation::error::Error::CapabilityNotSupported(_) => {
+                state.metrics.error_metrics.record_unsupported_capability();
+                connector::ExplainError::UnsupportedOperation(err.to_string())
+            }
+            translation::error::Error::NotImplementedYet(_) => {
+                state.metrics.error_metrics.record_unsupported_feature();
+                connector::ExplainError::UnsupportedOperation(err.to_string())
+            }
+            _ => {
+                state.metrics.error_metrics.record_invalid_request();
+                connector::ExplainError::InvalidRequest(err.to_string())
             }
-        });
+        }
+    });
     timer.complete_with(result)
 }
@@ -0,0 +1,193 @@
+//! Export the rows of a collection in bulk with `COPY`, for extracting data faster than through
+//! the `/query` endpoint. This is not part of the NDC specification.
+
+use bytes::Bytes;
+use tokio::sync::mpsc;
+use tracing::{info_span, Instrument};
+
+use ndc_sdk::connector;
+use ndc_sdk::models;
+use query_engine_sql::sql;
+use query_engine_translation::translation;
+
+use super::configuration;
+use super::errors;
+use super::query;
+use super::state;
+
+/// The format of exported rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// CSV, with a header line naming the fields.
+    Csv,
+    /// A JSON object per row, each on its own line.
+    JsonLines,
+}
+
+/// Export the rows selected by a query request, with the fields it selects, filtered and ordered
+/// as it asks. Aggregates are not exported, and variables are not supported.
+///
+/// The output is sent to the returned receiver in chunks as the database produces it; a failure
+/// once the export has started is sent in place of a chunk, after which the receiver is closed.
+pub async fn export(
+    configuration: &configuration::RuntimeConfiguration,
+    state: &state::State,
+    query_request: models::QueryRequest,
+    format: ExportFormat,
+) -> Result<mpsc::Receiver<Result<Bytes, connector::QueryError>>, connector::QueryError> {
+    async move {
+        tracing::info!(
+            query_request_json = serde_json::to_string(&query_request).unwrap(),
+            query_request = ?query_request
+        );
+
+        let plan = async { plan_export(configuration, state, query_request, format) }
+            .instrument(info_span!("Plan export"))
+            .await?;
+
+        let chunks = query_engine_execution::export::export(
+            &state.pool,
+            &state.database_info,
+            &state.metrics,
+            plan,
+        )
+        .instrument(info_span!("Export rows"))
+        .await
+        .map_err(|err| execution_error(configuration, state, err))?;
+
+        Ok(forward_chunks(configuration, state, chunks))
+    }
+    .instrument(info_span!("Export"))
+    .await
+}
+
+fn plan_export(
+    configuration: &configuration::RuntimeConfiguration,
+    state: &state::State,
+    query_request: models::QueryRequest,
+    format: ExportFormat,
+) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Export>, connector::QueryError>
+{
+    let role = configuration
+        .collection_roles
+        .get(&query_request.collection)
+        .cloned();
+    let plan = translation::query::translate_export(
+        &configuration.metadata,
+        translation::helpers::QueryOptions {
+            // Exports are meant to extract every row.
+            default_limit: None,
+            ..configuration.query_options(state.database_info.is_cockroach())
+        },
+        query_request,
+        match format {
+            ExportFormat::Csv => sql::ast::CopyFormat::Csv,
+            ExportFormat::JsonLines => sql::ast::CopyFormat::Lines,
+        },
+    )
+    .map_err(|err| {
+        tracing::error!("{}", err);
+        // log metrics
+        match err {
+            translation::error::Error::CapabilityNotSupported(_) => {
+                state.metrics.error_metrics.record_unsupported_capability();
+                connector::QueryError::UnsupportedOperation(err.to_string())
+            }
+            translation::error::Error::NotImplementedYet(_) => {
+                state.metrics.error_metrics.record_unsupported_feature();
+                connector::QueryError::UnsupportedOperation(err.to_string())
+            }
+            _ => {
+                state.metrics.error_metrics.record_invalid_request();
+                connector::QueryError::InvalidRequest(err.to_string())
+            }
+        }
+    })?;
+    if configuration.isolation_level.is_none() && role.is_none() {
+        return Ok(plan);
+    }
+    // As for queries, the role only applies within the transaction.
+    let mut pre = sql::helpers::transaction_begin(
+        configuration
+            .isolation_level
+            .map(query::to_transaction_isolation_level),
+    );
+    pre.extend(role.map(sql::helpers::set_local_role));
+    Ok(sql::execution_plan::ExecutionPlan {
+        pre,
+        post: sql::helpers::transaction_commit(),
+        ..plan
+    })
+}
+
+/// Forward the chunks of an export, reporting failures as query errors.
+fn forward_chunks(
+    configuration: &configuration::RuntimeConfiguration,
+    state: &state::State,
+    mut chunks: mpsc::Receiver<Result<Bytes, query_engine_execution::query::Error>>,
+) -> mpsc::Receiver<Result<Bytes, connector::QueryError>> {
+    let error_verbosity = configuration.error_verbosity;
+    let metrics = state.metrics.clone();
+    let (sender, receiver) = mpsc::channel(1);
+    tokio::spawn(async move {
+        while let Some(chunk) = chunks.recv().await {
+            let chunk = chunk.map_err(|err| match err {
+                query_engine_execution::query::Error::Query(err) => {
+                    tracing::error!("{}", err);
+                    metrics.error_metrics.record_invalid_request();
+                    connector::QueryError::Other(
+                        errors::error_message(error_verbosity, &err).into(),
+                    )
+                }
+                query_engine_execution::query::Error::DB(err) => {
+                    tracing::error!("{}", err);
+                    metrics.error_metrics.record_database_error();
+                    connector::QueryError::Other(
+                        errors::error_message(error_verbosity, &err).into(),
+                    )
+                }
+                query_engine_execution::query::Error::PoolSaturated(err) => {
+                    tracing::warn!("{}", err);
+                    connector::QueryError::Other(
+                        errors::error_message(error_verbosity, &err).into(),
+                    )
+                }
+            });
+            if sender.send(chunk).await.is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+/// Report a failure to start an export as a query error.
+fn execution_error(
+    configuration: &configuration::RuntimeConfiguration,
+    state: &state::State,
+    err: query_engine_execution::query::Error,
+) -> connector::QueryError {
+    match err {
+        query_engine_execution::query::Error::Query(err) => {
+            tracing::error!("{}", err);
+            state.metrics.error_metrics.record_invalid_request();
+            connector::QueryError::InvalidRequest(errors::error_message(
+                configuration.error_verbosity,
+                &err,
+            ))
+        }
+        query_engine_execution::query::Error::DB(err) => {
+            tracing::error!("{}", err);
+            state.metrics.error_metrics.record_database_error();
+            connector::QueryError::Other(
+                errors::error_message(configuration.error_verbosity, &err).into(),
+            )
+        }
+        query_engine_execution::query::Error::PoolSaturated(err) => {
+            tracing::warn!("{}", err);
+            connector::QueryError::Other(
+                errors::error_message(configuration.error_verbosity, &err).into(),
+            )
+        }
+    }
+}
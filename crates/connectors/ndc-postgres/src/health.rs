@@ -1,18 +1,113 @@
 //! Health check endpoint for the connector.
 
+use std::time::Duration;
+
 use ndc_sdk::connector;
+use sqlx::Row;
+
+use query_engine_execution::metrics;
+use query_engine_execution::pool::{ConnectionPool, Priority};
 
 /// Check the health of the connector.
 ///
 /// For example, this function should check that the connector
 /// is able to reach its data source over the network.
-pub async fn health_check(pool: &sqlx::PgPool) -> Result<(), connector::HealthError> {
-    let sqlx_query = sqlx::query("SELECT 1");
-
-    sqlx_query
-        .fetch_one(pool)
+///
+/// When the database is a read replica and `max_replication_lag` is set, the connector is also
+/// unhealthy while the replica lags further behind its primary than that, so that it can be
+/// taken out of rotation.
+///
+/// When a `validation_query` is set, the connector is unhealthy while it fails.
+///
+/// The connection is acquired with a high priority, so that a busy pool does not hold up the
+/// health check behind the queries waiting for a connection.
+pub async fn health_check(
+    pool: &ConnectionPool,
+    metrics: &metrics::Metrics,
+    max_replication_lag: Option<Duration>,
+    validation_query: Option<&str>,
+) -> Result<(), connector::HealthError> {
+    let mut connection = pool
+        .acquire_with_priority(metrics, Priority::High)
         .await
         .map_err(|err| connector::HealthError::Other(Box::new(err)))?;
+    let lag = async {
+        if let Some(query) = validation_query {
+            query_engine_execution::pool::validate_connection(connection.as_mut(), query).await?;
+        }
+        replication_lag(connection.as_mut()).await
+    }
+    .await
+    .map_err(|err| connector::HealthError::Other(Box::new(err)))?;
+
+    check_replication_lag(lag, max_replication_lag)
+}
+
+/// How far a read replica lags behind its primary, or `None` when the database is not a replica.
+///
+/// A replica which has replayed all the changes it has received is not lagging, however long ago
+/// the last transaction was.
+pub async fn replication_lag(
+    connection: &mut sqlx::PgConnection,
+) -> Result<Option<Duration>, sqlx::Error> {
+    let lag: Option<f64> = sqlx::query(
+        "SELECT CASE
+            WHEN NOT pg_is_in_recovery() THEN NULL
+            WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+            ELSE extract(epoch FROM now() - pg_last_xact_replay_timestamp())
+         END::float8",
+    )
+    .fetch_one(connection)
+    .await?
+    .get(0);
+
+    Ok(lag.map(|seconds| Duration::from_secs_f64(seconds.max(0.0))))
+}
+
+/// Report a replica lagging further behind than the maximum as unhealthy.
+fn check_replication_lag(
+    lag: Option<Duration>,
+    max_replication_lag: Option<Duration>,
+) -> Result<(), connector::HealthError> {
+    match (lag, max_replication_lag) {
+        (Some(lag), Some(max_replication_lag)) if lag > max_replication_lag => {
+            Err(connector::HealthError::Other(
+                format!(
+                    "the replica lags {:.1}s behind its primary, more than the maximum of {}s",
+                    lag.as_secs_f64(),
+                    max_replication_lag.as_secs()
+                )
+                .into(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lagging_replicas_are_unhealthy() {
+        let result = check_replication_lag(
+            Some(Duration::from_millis(12_500)),
+            Some(Duration::from_secs(10)),
+        );
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("12.5s"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn replicas_within_the_maximum_lag_are_healthy() {
+        let result =
+            check_replication_lag(Some(Duration::from_secs(3)), Some(Duration::from_secs(10)));
+        assert!(result.is_ok());
+    }
 
-    Ok(())
+    #[test]
+    fn lag_is_ignored_without_a_maximum_or_a_replica() {
+        assert!(check_replication_lag(Some(Duration::from_secs(60)), None).is_ok());
+        assert!(check_replication_lag(None, Some(Duration::from_secs(10))).is_ok());
+    }
 }
@@ -3,7 +3,9 @@
 pub mod capabilities;
 pub mod configuration;
 pub mod connector;
+pub mod errors;
 pub mod explain;
+pub mod export;
 pub mod health;
 pub mod mutation;
 pub mod query;
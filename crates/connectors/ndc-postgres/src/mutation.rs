@@ -13,6 +13,7 @@ use query_engine_sql::sql;
 use query_engine_translation::translation;
 
 use super::configuration;
+use super::errors;
 use super::state;
 
 /// Execute a mutation
@@ -37,7 +38,7 @@ pub async fn mutation<'a>(
             .instrument(info_span!("Plan mutation"))
             .await?;
 
-        let result = execute_mutation(state, plan)
+        let result = execute_mutation(configuration, state, plan)
             .instrument(info_span!("Execute mutation"))
             .await?;
 
@@ -65,6 +66,15 @@ fn plan_mutation(
         .map(|operation| {
             translation::mutation::translate(
                 &configuration.metadata,
+                translation::helpers::QueryOptions {
+                    mandatory_filters: configuration.mandatory_filters.clone(),
+                    case_insensitive_native_query_arguments: configuration
+                        .case_insensitive_native_query_arguments,
+                    interval_representation: configuration.interval_representation,
+                    big_number_representation: configuration.big_number_representation,
+                    enum_value_case: configuration.enum_value_case,
+                    ..Default::default()
+                },
                 operation,
                 request.collection_relationships.clone(),
             )
@@ -88,27 +98,49 @@ fn plan_mutation(
             })
         })
         .collect::<Result<Vec<_>, connector::MutationError>>()?;
-    timer.complete_with(Ok(sql::execution_plan::simple_mutations_execution_plan(
-        mutations,
-    )))
+    timer.complete_with(Ok(sql::execution_plan::ExecutionPlan {
+        max_sql_length: configuration.max_sql_length,
+        ..sql::execution_plan::simple_mutations_execution_plan(mutations)
+    }))
 }
 
 async fn execute_mutation(
+    configuration: &configuration::RuntimeConfiguration,
     state: &state::State,
     plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
 ) -> Result<JsonResponse<models::MutationResponse>, connector::MutationError> {
-    query_engine_execution::mutation::execute(
-        &state.pool,
-        &state.database_info,
-        &state.metrics,
-        plan,
-    )
-    .await
-    .map(JsonResponse::Serialized)
-    .map_err(|err| {
+    let execute = || {
+        query_engine_execution::mutation::execute(
+            &state.pool,
+            &state.database_info,
+            &state.metrics,
+            &plan,
+        )
+    };
+    let result = match execute().await {
+        // The credentials may have been rotated; retry once with the current ones. The
+        // connection is refused before the transaction begins, so nothing has been written.
+        Err(query_engine_execution::mutation::Error::DB(err))
+            if query_engine_execution::pool::is_authentication_error(&err) =>
+        {
+            tracing::warn!("reconnecting after an authentication failure: {}", err);
+            state
+                .reconnect(configuration)
+                .map_err(|err| connector::MutationError::Other(err.into()))?;
+            execute().await
+        }
+        result => result,
+    };
+    result.map(JsonResponse::Serialized).map_err(|err| {
         tracing::error!("{}", err);
         log_err_metrics(state, &err);
-        connector::MutationError::Other(err.to_string().into())
+        let message = errors::error_message(configuration.error_verbosity, &err);
+        match &err {
+            query_engine_execution::mutation::Error::Query(
+                query_engine_execution::mutation::QueryError::SqlTooLong(_),
+            ) => connector::MutationError::InvalidRequest(message),
+            _ => connector::MutationError::Other(message.into()),
+        }
     })
 }
 
@@ -118,10 +150,15 @@ fn log_err_metrics(state: &state::State, err: &query_engine_execution::mutation:
             query_engine_execution::mutation::QueryError::NotSupported(_) => {
                 state.metrics.error_metrics.record_unsupported_feature()
             }
+            query_engine_execution::mutation::QueryError::SqlTooLong(_) => {
+                state.metrics.error_metrics.record_invalid_request()
+            }
         },
         query_engine_execution::mutation::Error::DB(_) => {
             state.metrics.error_metrics.record_database_error();
         }
+        // Recorded when the request is rejected.
+        query_engine_execution::mutation::Error::PoolSaturated(_) => {}
         query_engine_execution::mutation::Error::Multiple(err1, err2) => {
             log_err_metrics(state, err1);
             log_err_metrics(state, err2);
@@ -3,6 +3,8 @@
 //! [Native Data Connector Specification](https://hasura.github.io/ndc-spec/specification/queries/index.html)
 //! for further details.
 
+use std::time::{Duration, Instant};
+
 use tracing::{info_span, Instrument};
 
 use ndc_sdk::connector;
@@ -12,6 +14,7 @@ use query_engine_sql::sql;
 use query_engine_translation::translation;
 
 use super::configuration;
+use super::errors;
 use super::state;
 
 /// Execute a query
@@ -32,13 +35,25 @@ pub async fn query<'a>(
             query_request = ?query_request
         );
 
+        let empty_response = empty_response_for_limit_zero(&query_request);
+
         let plan = async { plan_query(configuration, state, query_request) }
             .instrument(info_span!("Plan query"))
             .await?;
 
-        let result = execute_query(state, plan)
-            .instrument(info_span!("Execute query"))
-            .await?;
+        // We still plan the query so that invalid requests are reported as such, but there is no
+        // need to ask the database for rows we already know are not there.
+        let result = match empty_response {
+            Some(response) => JsonResponse::Value(response),
+            None => {
+                execute_query(configuration, state, plan)
+                    .instrument(info_span!(
+                        "Execute query",
+                        execution_time_ms = tracing::field::Empty
+                    ))
+                    .await?
+            }
+        };
 
         state.metrics.record_successful_query();
         Ok(result)
@@ -49,40 +64,157 @@ pub async fn query<'a>(
     timer.complete_with(result)
 }
 
+/// A query with a limit of 0 and no aggregates is used by some tools to discover the shape of a
+/// result. We can answer those without a round-trip to the database: the response is a single
+/// empty row set, or one per set of variables.
+fn empty_response_for_limit_zero(
+    query_request: &models::QueryRequest,
+) -> Option<models::QueryResponse> {
+    let query = &query_request.query;
+    let has_aggregates = query
+        .aggregates
+        .as_ref()
+        .map_or(false, |aggregates| !aggregates.is_empty());
+
+    if query.limit != Some(0) || has_aggregates {
+        return None;
+    }
+
+    let row_set = models::RowSet {
+        aggregates: None,
+        rows: query.fields.as_ref().map(|_| vec![]),
+    };
+    let row_sets = match &query_request.variables {
+        None => 1,
+        Some(variables) => variables.len(),
+    };
+
+    Some(models::QueryResponse(vec![row_set; row_sets]))
+}
+
 fn plan_query(
     configuration: &configuration::RuntimeConfiguration,
     state: &state::State,
     query_request: models::QueryRequest,
 ) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>, connector::QueryError> {
     let timer = state.metrics.time_query_plan();
-    let result =
-        translation::query::translate(&configuration.metadata, query_request).map_err(|err| {
-            tracing::error!("{}", err);
-            // log metrics
-            match err {
-                translation::error::Error::CapabilityNotSupported(_) => {
-                    state.metrics.error_metrics.record_unsupported_capability();
-                    connector::QueryError::UnsupportedOperation(err.to_string())
-                }
-                translation::error::Error::NotImplementedYet(_) => {
-                    state.metrics.error_metrics.record_unsupported_feature();
-                    connector::QueryError::UnsupportedOperation(err.to_string())
-                }
-                _ => {
-                    state.metrics.error_metrics.record_invalid_request();
-                    connector::QueryError::InvalidRequest(err.to_string())
-                }
+    let role = configuration
+        .collection_roles
+        .get(&query_request.collection)
+        .cloned();
+    let result = translation::query::translate(
+        &configuration.metadata,
+        configuration.query_options(state.database_info.is_cockroach()),
+        query_request,
+    )
+    .map_err(|err| {
+        tracing::error!("{}", err);
+        // log metrics
+        match err {
+            translation::error::Error::CapabilityNotSupported(_) => {
+                state.metrics.error_metrics.record_unsupported_capability();
+                connector::QueryError::UnsupportedOperation(err.to_string())
+            }
+            translation::error::Error::NotImplementedYet(_) => {
+                state.metrics.error_metrics.record_unsupported_feature();
+                connector::QueryError::UnsupportedOperation(err.to_string())
             }
-        });
+            _ => {
+                state.metrics.error_metrics.record_invalid_request();
+                connector::QueryError::InvalidRequest(err.to_string())
+            }
+        }
+    });
+    let result = result.and_then(|plan| {
+        in_transaction(plan, configuration.isolation_level, role).map_err(|err| {
+            tracing::error!("{}", err);
+            state.metrics.error_metrics.record_invalid_request();
+            err
+        })
+    });
     timer.complete_with(result)
 }
 
+/// Run the query in a transaction with the configured isolation level and as the collection's
+/// role, if there are any. CockroachDB does not allow a query inside a transaction to read its data
+/// as of a point in time, so a query with the 'as_of' argument cannot be run this way.
+fn in_transaction(
+    plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+    isolation_level: Option<configuration::version2::IsolationLevel>,
+    role: Option<String>,
+) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>, connector::QueryError> {
+    if isolation_level.is_none() && role.is_none() {
+        return Ok(plan);
+    }
+    if plan.query.query.as_of_system_time.is_some() {
+        return Err(connector::QueryError::InvalidRequest(format!(
+            "The '{}' argument cannot be used when queries run in a transaction, with a default isolation level or a collection role.",
+            translation::query::AS_OF_ARGUMENT
+        )));
+    }
+    // A role set with 'SET LOCAL' reverts when the transaction ends, so the connection is
+    // returned to the pool as it was.
+    let mut pre =
+        sql::helpers::transaction_begin(isolation_level.map(to_transaction_isolation_level));
+    pre.extend(role.map(sql::helpers::set_local_role));
+    Ok(sql::execution_plan::ExecutionPlan {
+        pre,
+        post: sql::helpers::transaction_commit(),
+        ..plan
+    })
+}
+
+pub(crate) fn to_transaction_isolation_level(
+    isolation_level: configuration::version2::IsolationLevel,
+) -> sql::ast::transaction::IsolationLevel {
+    match isolation_level {
+        configuration::version2::IsolationLevel::ReadCommitted => {
+            sql::ast::transaction::IsolationLevel::ReadCommitted
+        }
+        configuration::version2::IsolationLevel::RepeatableRead => {
+            sql::ast::transaction::IsolationLevel::RepeatableRead
+        }
+        configuration::version2::IsolationLevel::Serializable => {
+            sql::ast::transaction::IsolationLevel::Serializable
+        }
+    }
+}
+
 async fn execute_query(
+    configuration: &configuration::RuntimeConfiguration,
     state: &state::State,
     plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
 ) -> Result<JsonResponse<models::QueryResponse>, connector::QueryError> {
-    query_engine_execution::query::execute(&state.pool, &state.database_info, &state.metrics, plan)
-        .await
+    let execute = || {
+        retry_transaction_conflicts(configuration.serialization_failure_retries, || {
+            query_engine_execution::query::execute(
+                &state.pool,
+                &state.database_info,
+                &state.metrics,
+                &plan,
+            )
+        })
+    };
+    let started = Instant::now();
+    let result = match execute().await {
+        // The credentials may have been rotated; retry once with the current ones.
+        Err(query_engine_execution::query::Error::DB(err))
+            if query_engine_execution::pool::is_authentication_error(&err) =>
+        {
+            tracing::warn!("reconnecting after an authentication failure: {}", err);
+            state
+                .reconnect(configuration)
+                .map_err(|err| connector::QueryError::Other(err.into()))?;
+            execute().await
+        }
+        result => result,
+    };
+    // From acquiring a connection to receiving the result, including any retries.
+    tracing::Span::current().record(
+        "execution_time_ms",
+        started.elapsed().as_secs_f64() * 1000.0,
+    );
+    result
         .map(JsonResponse::Serialized)
         .map_err(|err| match err {
             query_engine_execution::query::Error::Query(err) => {
@@ -98,13 +230,204 @@ async fn execute_query(
                     query_engine_execution::query::QueryError::NotSupported(_) => {
                         state.metrics.error_metrics.record_unsupported_feature()
                     }
+                    query_engine_execution::query::QueryError::NativeQueryColumnTypeMismatch {
+                        ..
+                    } => state.metrics.error_metrics.record_connector_error(),
+                    query_engine_execution::query::QueryError::SqlTooLong(_) => {
+                        state.metrics.error_metrics.record_invalid_request()
+                    }
+                }
+                if let query_engine_execution::query::QueryError::SqlTooLong(_) = err {
+                    connector::QueryError::InvalidRequest(errors::error_message(
+                        configuration.error_verbosity,
+                        &err,
+                    ))
+                } else {
+                    connector::QueryError::Other(
+                        errors::error_message(configuration.error_verbosity, &err).into(),
+                    )
                 }
-                connector::QueryError::Other(err.to_string().into())
             }
             query_engine_execution::query::Error::DB(err) => {
                 tracing::error!("{}", err);
                 state.metrics.error_metrics.record_database_error();
-                connector::QueryError::Other(err.to_string().into())
+                connector::QueryError::Other(
+                    errors::error_message(configuration.error_verbosity, &err).into(),
+                )
+            }
+            query_engine_execution::query::Error::PoolSaturated(err) => {
+                tracing::warn!("{}", err);
+                connector::QueryError::Other(
+                    errors::error_message(configuration.error_verbosity, &err).into(),
+                )
+            }
+        })
+}
+
+/// Run a query, and run it again after a short wait whenever it fails because it conflicted with a
+/// concurrent transaction, up to the configured number of retries.
+///
+/// Only queries can be retried this way, as they do not modify the database.
+async fn retry_transaction_conflicts<T, F, Fut>(
+    retries: Option<configuration::version2::RetrySettings>,
+    mut run: F,
+) -> Result<T, query_engine_execution::query::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, query_engine_execution::query::Error>>,
+{
+    let (max_retries, mut backoff) = match retries {
+        None => (0, Duration::ZERO),
+        Some(retries) => (
+            retries.max_retries,
+            Duration::from_millis(retries.initial_backoff),
+        ),
+    };
+    let mut retried = 0;
+    loop {
+        match run().await {
+            Err(query_engine_execution::query::Error::DB(err))
+                if retried < max_retries
+                    && query_engine_execution::query::is_transaction_conflict(&err) =>
+            {
+                tracing::warn!("retrying after a transaction conflict: {}", err);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                retried += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    /// The error Postgres reports when a serializable transaction cannot be committed.
+    #[derive(Debug)]
+    struct SerializationFailure;
+
+    impl std::fmt::Display for SerializationFailure {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(sqlx::error::DatabaseError::message(self))
+        }
+    }
+
+    impl std::error::Error for SerializationFailure {}
+
+    impl sqlx::error::DatabaseError for SerializationFailure {
+        fn message(&self) -> &str {
+            "could not serialize access due to read/write dependencies among transactions"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed("40001"))
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    /// Run a query which fails with a serialization failure the given number of times, and then
+    /// succeeds. Returns whether it succeeded in the end, and how many times it ran.
+    async fn run_failing(
+        failures: u32,
+        retries: Option<configuration::version2::RetrySettings>,
+    ) -> (bool, u32) {
+        let mut attempts = 0;
+        let result = retry_transaction_conflicts(retries, || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt <= failures {
+                    Err(query_engine_execution::query::Error::DB(
+                        sqlx::Error::Database(Box::new(SerializationFailure)),
+                    ))
+                } else {
+                    Ok(())
+                }
             }
         })
+        .await;
+        (result.is_ok(), attempts)
+    }
+
+    const RETRIES: configuration::version2::RetrySettings =
+        configuration::version2::RetrySettings {
+            max_retries: 2,
+            initial_backoff: 1,
+        };
+
+    #[tokio::test]
+    async fn serialization_failures_are_retried() {
+        assert_eq!(run_failing(1, Some(RETRIES)).await, (true, 2));
+    }
+
+    #[tokio::test]
+    async fn serialization_failures_are_retried_a_limited_number_of_times() {
+        assert_eq!(run_failing(5, Some(RETRIES)).await, (false, 3));
+    }
+
+    #[tokio::test]
+    async fn serialization_failures_are_not_retried_by_default() {
+        assert_eq!(run_failing(1, None).await, (false, 1));
+    }
+
+    /// A plan for a query reading its data as of the given point in time, if any.
+    fn plan_as_of(
+        as_of_system_time: Option<&str>,
+    ) -> sql::execution_plan::ExecutionPlan<sql::execution_plan::Query> {
+        let mut select = sql::helpers::simple_select(vec![]);
+        select.as_of_system_time = as_of_system_time
+            .map(|time| sql::ast::Expression::Value(sql::ast::Value::String(time.to_string())));
+        sql::execution_plan::simple_query_execution_plan(None, "Album".to_string(), select)
+    }
+
+    #[test]
+    fn queries_as_of_a_point_in_time_run_outside_of_a_transaction() {
+        let plan = in_transaction(plan_as_of(Some("-10s")), None, None).unwrap();
+        assert!(plan.pre.is_empty());
+        assert!(plan.post.is_empty());
+    }
+
+    #[test]
+    fn queries_as_of_a_point_in_time_cannot_run_with_an_isolation_level() {
+        let result = in_transaction(
+            plan_as_of(Some("-10s")),
+            Some(configuration::version2::IsolationLevel::Serializable),
+            None,
+        );
+        assert!(
+            matches!(result, Err(connector::QueryError::InvalidRequest(ref message)) if message.contains("'as_of'")),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn queries_run_in_a_transaction_with_an_isolation_level() {
+        let plan = in_transaction(
+            plan_as_of(None),
+            Some(configuration::version2::IsolationLevel::Serializable),
+            None,
+        )
+        .unwrap();
+        assert_eq!(plan.pre.len(), 1);
+        assert_eq!(plan.post.len(), 1);
+    }
 }
@@ -3,13 +3,15 @@
 //! [Native Data Connector Specification](https://hasura.github.io/ndc-spec/specification/schema/index.html)
 //! for further details.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use ndc_sdk::connector;
 use ndc_sdk::models;
 use query_engine_metadata::metadata;
+use query_engine_translation::translation;
 
 use super::configuration;
+use super::configuration::version2::UnknownScalarStrategy;
 
 /// Get the connector's schema.
 ///
@@ -19,51 +21,101 @@ pub async fn get_schema(
     config: &configuration::RuntimeConfiguration,
 ) -> Result<models::SchemaResponse, connector::SchemaError> {
     let configuration::RuntimeConfiguration { metadata, .. } = config;
-    let scalar_types: BTreeMap<String, models::ScalarType> =
-        configuration::occurring_scalar_types(&metadata.tables, &metadata.native_queries)
-            .iter()
+    let occurring_scalar_types = configuration::occurring_scalar_types(
+        &metadata.tables,
+        &metadata.native_queries,
+        &metadata.composite_types,
+        &metadata.inline_rows,
+    );
+    let unknown_scalar_types: Vec<&metadata::ScalarType> = occurring_scalar_types
+        .iter()
+        .filter(|scalar_type| metadata.is_unknown_scalar_type(scalar_type))
+        .collect();
+    // Configurations which reject unknown scalar types are rejected when they are validated.
+    let exposed_names: ExposedNames = match config.unknown_scalar_strategy {
+        UnknownScalarStrategy::Error | UnknownScalarStrategy::TreatAsJson => BTreeMap::new(),
+        UnknownScalarStrategy::TreatAsString => unknown_scalar_types
+            .into_iter()
             .map(|scalar_type| {
                 (
-                    scalar_type.0.clone(),
-                    models::ScalarType {
-                        aggregate_functions: metadata
+                    scalar_type.clone(),
+                    metadata::ScalarType("text".to_string()),
+                )
+            })
+            .collect(),
+    };
+    let exposed_scalar_types: BTreeSet<metadata::ScalarType> = occurring_scalar_types
+        .iter()
+        .map(|scalar_type| exposed_name(&exposed_names, scalar_type))
+        .collect();
+    let scalar_types: BTreeMap<String, models::ScalarType> = exposed_scalar_types
+        .iter()
+        .map(|scalar_type| {
+            (
+                scalar_type.0.clone(),
+                models::ScalarType {
+                    aggregate_functions: aggregate_functions(
+                        scalar_type,
+                        metadata
                             .aggregate_functions
                             .0
                             .get(scalar_type)
-                            .unwrap_or(&BTreeMap::new())
-                            .iter()
-                            .map(|(function_name, function_definition)| {
-                                (
-                                    function_name.clone(),
-                                    models::AggregateFunctionDefinition {
-                                        result_type: models::Type::Named {
-                                            name: function_definition.return_type.0.clone(),
-                                        },
+                            .unwrap_or(&BTreeMap::new()),
+                        &exposed_scalar_types,
+                    )
+                    .into_iter()
+                    .map(|(function_name, return_type)| {
+                        (
+                            function_name,
+                            models::AggregateFunctionDefinition {
+                                result_type: models::Type::Named {
+                                    name: return_type.0,
+                                },
+                            },
+                        )
+                    })
+                    .collect(),
+                    comparison_operators: metadata
+                        .comparison_operators
+                        .0
+                        .get(scalar_type)
+                        .unwrap_or(&BTreeMap::new())
+                        .iter()
+                        .map(|(op_name, op_def)| {
+                            (
+                                op_name.clone(),
+                                models::ComparisonOperatorDefinition {
+                                    argument_type: models::Type::Named {
+                                        name: op_def.argument_type.0.clone(),
                                     },
-                                )
-                            })
-                            .collect(),
-                        comparison_operators: metadata
-                            .comparison_operators
-                            .0
-                            .get(scalar_type)
-                            .unwrap_or(&BTreeMap::new())
-                            .iter()
-                            .map(|(op_name, op_def)| {
-                                (
-                                    op_name.clone(),
-                                    models::ComparisonOperatorDefinition {
-                                        argument_type: models::Type::Named {
-                                            name: op_def.argument_type.0.clone(),
+                                },
+                            )
+                        })
+                        // Quantified comparisons, such as '_gt_all', take an array of the
+                        // arguments of the operators they quantify.
+                        .chain(
+                            metadata
+                                .comparison_operators
+                                .quantified(scalar_type)
+                                .into_iter()
+                                .map(|(op_name, (_, op_def))| {
+                                    (
+                                        op_name,
+                                        models::ComparisonOperatorDefinition {
+                                            argument_type: models::Type::Array {
+                                                element_type: Box::new(models::Type::Named {
+                                                    name: op_def.argument_type.0.clone(),
+                                                }),
+                                            },
                                         },
-                                    },
-                                )
-                            })
-                            .collect(),
-                    },
-                )
-            })
-            .collect();
+                                    )
+                                }),
+                        )
+                        .collect(),
+                },
+            )
+        })
+        .collect();
 
     let collections_by_identifier: BTreeMap<(&str, &str), &str> = metadata
         .tables
@@ -83,8 +135,61 @@ pub async fn get_schema(
         .iter()
         .map(|(collection_name, table)| models::CollectionInfo {
             name: collection_name.clone(),
-            description: table.description.clone(),
-            arguments: BTreeMap::new(),
+            description: collection_description(table),
+            arguments: table
+                .search_rank_fields
+                .0
+                .values()
+                .map(|field| {
+                    (
+                        field.argument.clone(),
+                        models::ArgumentInfo {
+                            description: Some(
+                                "The text search the search rank fields rank by".to_string(),
+                            ),
+                            argument_type: models::Type::Nullable {
+                                underlying_type: Box::new(models::Type::Named {
+                                    name: exposed_name(
+                                        &exposed_names,
+                                        &metadata::ScalarType("text".to_string()),
+                                    )
+                                    .0,
+                                }),
+                            },
+                        },
+                    )
+                })
+                .chain([(
+                    translation::query::AS_OF_ARGUMENT.to_string(),
+                    models::ArgumentInfo {
+                        description: Some(
+                            "The point in time to read the data as of, such as '-10s'. Only supported on CockroachDB, when queries do not run in a transaction".to_string(),
+                        ),
+                        argument_type: models::Type::Nullable {
+                            underlying_type: Box::new(models::Type::Named {
+                                name: exposed_name(
+                                    &exposed_names,
+                                    &metadata::ScalarType("text".to_string()),
+                                )
+                                .0,
+                            }),
+                        },
+                    },
+                )])
+                .chain([(
+                    translation::query::SAMPLE_ARGUMENT.to_string(),
+                    models::ArgumentInfo {
+                        description: Some(
+                            "Only read a sample of the rows, such as {\"method\": \"system\", \"percentage\": 10}. Views cannot be sampled".to_string(),
+                        ),
+                        argument_type: models::Type::Nullable {
+                            underlying_type: Box::new(models::Type::Named {
+                                name: TABLE_SAMPLE_TYPE.to_string(),
+                            }),
+                        },
+                    },
+                )])
+                .collect(),
             collection_type: collection_name.clone(),
             uniqueness_constraints: table
                 .uniqueness_constraints
@@ -157,7 +262,7 @@ pub async fn get_schema(
                         name.clone(),
                         models::ArgumentInfo {
                             description: column_info.description.clone(),
-                            argument_type: column_to_type(column_info),
+                            argument_type: column_to_type(&exposed_names, column_info),
                         },
                     )
                 })
@@ -168,28 +273,139 @@ pub async fn get_schema(
         })
         .collect();
 
+    let inline_rows: Vec<models::CollectionInfo> = metadata
+        .inline_rows
+        .0
+        .iter()
+        .map(|(name, info)| models::CollectionInfo {
+            name: name.clone(),
+            description: info.description.clone(),
+            arguments: BTreeMap::from([(
+                translation::helpers::INLINE_ROWS_ARGUMENT.to_string(),
+                models::ArgumentInfo {
+                    description: Some("The rows of the collection".to_string()),
+                    argument_type: models::Type::Array {
+                        element_type: Box::new(models::Type::Named { name: name.clone() }),
+                    },
+                },
+            )]),
+            collection_type: name.clone(),
+            uniqueness_constraints: BTreeMap::new(),
+            foreign_keys: BTreeMap::new(),
+        })
+        .collect();
+
     let mut collections = tables;
     collections.extend(native_queries);
+    collections.extend(inline_rows);
 
     let table_types =
         BTreeMap::from_iter(metadata.tables.0.iter().map(|(collection_name, table)| {
             let object_type = models::ObjectType {
                 description: table.description.clone(),
-                fields: BTreeMap::from_iter(table.columns.values().map(|column| {
+                fields: table
+                    .columns
+                    .iter()
+                    .map(|(field_name, column)| {
+                        (
+                            field_name.clone(),
+                            models::ObjectField {
+                                description: column_description(
+                                    column,
+                                    enum_values(metadata, config.enum_value_case, &column.r#type),
+                                ),
+                                r#type: column_to_type(&exposed_names, column),
+                            },
+                        )
+                    })
+                    .chain(table.computed_fields.0.iter().map(|(field_name, field)| {
+                        (
+                            field_name.clone(),
+                            models::ObjectField {
+                                description: field.description.clone(),
+                                r#type: computed_field_to_type(&exposed_names, field),
+                            },
+                        )
+                    }))
+                    .chain(table.ranking_fields.0.iter().map(|(field_name, field)| {
+                        (
+                            field_name.clone(),
+                            models::ObjectField {
+                                description: field.description.clone(),
+                                r#type: models::Type::Named {
+                                    name: exposed_name(
+                                        &exposed_names,
+                                        &metadata::ScalarType("int8".to_string()),
+                                    )
+                                    .0,
+                                },
+                            },
+                        )
+                    }))
+                    .chain(
+                        table
+                            .search_rank_fields
+                            .0
+                            .iter()
+                            .map(|(field_name, field)| {
+                                (
+                                    field_name.clone(),
+                                    models::ObjectField {
+                                        description: field.description.clone(),
+                                        r#type: models::Type::Nullable {
+                                            underlying_type: Box::new(models::Type::Named {
+                                                name: exposed_name(
+                                                    &exposed_names,
+                                                    &metadata::ScalarType("float4".to_string()),
+                                                )
+                                                .0,
+                                            }),
+                                        },
+                                    },
+                                )
+                            }),
+                    )
+                    .collect(),
+            };
+            (collection_name.clone(), object_type)
+        }));
+
+    let native_queries_types =
+        BTreeMap::from_iter(metadata.native_queries.0.iter().map(|(name, info)| {
+            let object_type = models::ObjectType {
+                description: info.description.clone(),
+                fields: BTreeMap::from_iter(info.columns.values().map(|column| {
                     (
                         column.name.clone(),
                         models::ObjectField {
                             description: column.description.clone(),
-                            r#type: column_to_type(column),
+                            r#type: column_to_type(&exposed_names, column),
                         },
                     )
                 })),
             };
-            (collection_name.clone(), object_type)
+            (name.clone(), object_type)
         }));
 
-    let native_queries_types =
-        BTreeMap::from_iter(metadata.native_queries.0.iter().map(|(name, info)| {
+    let composite_types =
+        BTreeMap::from_iter(metadata.composite_types.0.iter().map(|(name, info)| {
+            let object_type = models::ObjectType {
+                description: info.description.clone(),
+                fields: BTreeMap::from_iter(info.fields.values().map(|field| {
+                    (
+                        field.name.clone(),
+                        models::ObjectField {
+                            description: field.description.clone(),
+                            r#type: column_to_type(&exposed_names, field),
+                        },
+                    )
+                })),
+            };
+            (name.clone(), object_type)
+        }));
+
+    let inline_rows_types =
+        BTreeMap::from_iter(metadata.inline_rows.0.iter().map(|(name, info)| {
             let object_type = models::ObjectType {
                 description: info.description.clone(),
                 fields: BTreeMap::from_iter(info.columns.values().map(|column| {
@@ -197,7 +413,7 @@ pub async fn get_schema(
                         column.name.clone(),
                         models::ObjectField {
                             description: column.description.clone(),
-                            r#type: column_to_type(column),
+                            r#type: column_to_type(&exposed_names, column),
                         },
                     )
                 })),
@@ -207,6 +423,14 @@ pub async fn get_schema(
 
     let mut object_types = table_types;
     object_types.extend(native_queries_types);
+    object_types.extend(composite_types);
+    object_types.extend(inline_rows_types);
+    if !metadata.tables.0.is_empty() {
+        object_types.insert(
+            TABLE_SAMPLE_TYPE.to_string(),
+            table_sample_type(&exposed_names),
+        );
+    }
 
     let procedures: Vec<models::ProcedureInfo> = metadata
         .native_queries
@@ -224,7 +448,7 @@ pub async fn get_schema(
                         name.clone(),
                         models::ArgumentInfo {
                             description: column_info.description.clone(),
-                            argument_type: column_to_type(column_info),
+                            argument_type: column_to_type(&exposed_names, column_info),
                         },
                     )
                 })
@@ -242,22 +466,1034 @@ pub async fn get_schema(
     })
 }
 
-fn column_to_type(column: &metadata::ColumnInfo) -> models::Type {
+/// The type of the 'sample' argument of tables.
+const TABLE_SAMPLE_TYPE: &str = "table_sample";
+
+/// The sample of its rows a table reads: a 'system' sample of blocks of rows or a 'bernoulli' sample
+/// of rows, of a percentage of the table.
+fn table_sample_type(exposed_names: &ExposedNames) -> models::ObjectType {
+    models::ObjectType {
+        description: Some("A sample of the rows of a table".to_string()),
+        fields: BTreeMap::from([
+            (
+                "method".to_string(),
+                models::ObjectField {
+                    description: Some("Either 'system' or 'bernoulli'".to_string()),
+                    r#type: models::Type::Named {
+                        name: exposed_name(
+                            exposed_names,
+                            &metadata::ScalarType("text".to_string()),
+                        )
+                        .0,
+                    },
+                },
+            ),
+            (
+                "percentage".to_string(),
+                models::ObjectField {
+                    description: Some(
+                        "The percentage of the table to read, from 0 to 100".to_string(),
+                    ),
+                    r#type: models::Type::Named {
+                        name: exposed_name(
+                            exposed_names,
+                            &metadata::ScalarType("float8".to_string()),
+                        )
+                        .0,
+                    },
+                },
+            ),
+        ]),
+    }
+}
+
+/// The description of a table, which also lists its row-level security policies when row-level
+/// security is enabled on it, and its triggers.
+fn collection_description(table: &metadata::TableInfo) -> Option<String> {
+    let foreign_server = table
+        .foreign_server
+        .as_ref()
+        .map(|server| format!("Foreign table on the server '{server}'. It is read-only."));
+    let rls = table.rls.as_ref().map(|rls| {
+        let policies = if rls.policies.is_empty() {
+            "none".to_string()
+        } else {
+            rls.policies
+                .iter()
+                .map(|(policy_name, policy)| {
+                    format!("{policy_name} ({})", policy_command(policy.command))
+                })
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        format!("Row-level security policies: {policies}")
+    });
+    let insertable_columns = insertable_columns(table);
+    let insertable_columns = (insertable_columns.len() < table.columns.len()
+        || insertable_columns.iter().any(|(_, optional)| *optional))
+    .then(|| {
+        let columns = if insertable_columns.is_empty() {
+            "none".to_string()
+        } else {
+            insertable_columns
+                .iter()
+                .map(|(column_name, optional)| {
+                    if *optional {
+                        format!("{column_name} (optional)")
+                    } else {
+                        column_name.to_string()
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        format!("Insertable columns: {columns}")
+    });
+    let triggers = (!table.triggers.is_empty()).then(|| {
+        let triggers: Vec<String> = table
+            .triggers
+            .0
+            .iter()
+            .map(|(trigger_name, trigger)| {
+                let events: Vec<&str> = trigger.events.iter().copied().map(trigger_event).collect();
+                format!(
+                    "{trigger_name} ({} {})",
+                    trigger_timing(trigger.timing),
+                    events.join(" OR ")
+                )
+            })
+            .collect();
+        format!("Triggers: {}", triggers.join(", "))
+    });
+    let paragraphs: Vec<String> = [
+        table.description.clone(),
+        foreign_server,
+        rls,
+        insertable_columns,
+        triggers,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if paragraphs.is_empty() {
+        None
+    } else {
+        Some(paragraphs.join("\n\n"))
+    }
+}
+
+/// The columns of a table inserts may give values to, with whether they may be left out because
+/// the column has a default. Generated columns and 'GENERATED ALWAYS' identity columns cannot be
+/// given values.
+fn insertable_columns(table: &metadata::TableInfo) -> Vec<(&str, bool)> {
+    table
+        .columns
+        .iter()
+        .filter(|(_, column)| {
+            !column.is_generated && column.is_identity != metadata::IsIdentity::IdentityAlways
+        })
+        .map(|(column_name, column)| {
+            (
+                column_name.as_str(),
+                column.has_default || column.is_identity == metadata::IsIdentity::IdentityByDefault,
+            )
+        })
+        .collect()
+}
+
+/// The SQL command a row-level security policy applies to.
+fn policy_command(command: metadata::RlsPolicyCommand) -> &'static str {
+    match command {
+        metadata::RlsPolicyCommand::All => "ALL",
+        metadata::RlsPolicyCommand::Select => "SELECT",
+        metadata::RlsPolicyCommand::Insert => "INSERT",
+        metadata::RlsPolicyCommand::Update => "UPDATE",
+        metadata::RlsPolicyCommand::Delete => "DELETE",
+    }
+}
+
+/// When a trigger fires, as written in `CREATE TRIGGER`.
+fn trigger_timing(timing: metadata::TriggerTiming) -> &'static str {
+    match timing {
+        metadata::TriggerTiming::Before => "BEFORE",
+        metadata::TriggerTiming::After => "AFTER",
+        metadata::TriggerTiming::InsteadOf => "INSTEAD OF",
+    }
+}
+
+/// An event a trigger fires on, as written in `CREATE TRIGGER`.
+fn trigger_event(event: metadata::TriggerEvent) -> &'static str {
+    match event {
+        metadata::TriggerEvent::Insert => "INSERT",
+        metadata::TriggerEvent::Update => "UPDATE",
+        metadata::TriggerEvent::Delete => "DELETE",
+        metadata::TriggerEvent::Truncate => "TRUNCATE",
+    }
+}
+
+/// The description of a column, which also lists the values the column is restricted to, its
+/// collation, whether it is generated and the estimated maximum of its sequence, if any.
+fn column_description(
+    column: &metadata::ColumnInfo,
+    enum_values: Option<Vec<String>>,
+) -> Option<String> {
+    let allowed_values = column
+        .allowed_values
+        .clone()
+        .or(enum_values)
+        .map(|allowed_values| format!("Allowed values: {}", allowed_values.join(", ")));
+    let collation = column
+        .collation
+        .as_ref()
+        .map(|collation| format!("Collation: {collation}"));
+    let sequence_last_value = column.sequence_last_value.map(|last_value| {
+        format!(
+            "Estimated maximum: {last_value} (the last value of its sequence when introspected)"
+        )
+    });
+    let generated = column
+        .is_generated
+        .then(|| "Generated from the other columns of its row, and read-only.".to_string());
+    let deprecated = column.deprecated.as_ref().map(|reason| {
+        if reason.is_empty() {
+            "Deprecated.".to_string()
+        } else {
+            format!("Deprecated: {reason}")
+        }
+    });
+    let paragraphs: Vec<String> = [
+        deprecated,
+        column.description.clone(),
+        allowed_values,
+        collation,
+        generated,
+        sequence_last_value,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if paragraphs.is_empty() {
+        None
+    } else {
+        Some(paragraphs.join("\n\n"))
+    }
+}
+
+/// The labels of the enum type of a column, or of the elements of an array column, in the case
+/// they are exposed in.
+fn enum_values(
+    metadata: &metadata::Metadata,
+    enum_value_case: Option<translation::helpers::EnumValueCase>,
+    r#type: &metadata::Type,
+) -> Option<Vec<String>> {
+    let scalar_type = match r#type {
+        metadata::Type::ScalarType(scalar_type) => scalar_type,
+        metadata::Type::ArrayType(element_type) => match element_type.as_ref() {
+            metadata::Type::ScalarType(scalar_type) => scalar_type,
+            _ => return None,
+        },
+        metadata::Type::CompositeType(_) => return None,
+    };
+    let labels = metadata.enum_types.0.get(scalar_type)?;
+    Some(
+        labels
+            .iter()
+            .map(|label| match enum_value_case {
+                Some(enum_value_case) => enum_value_case.apply(label),
+                None => label.clone(),
+            })
+            .collect(),
+    )
+}
+
+/// The scalar types exposed under the name of another type, with the type they are exposed as.
+type ExposedNames = BTreeMap<metadata::ScalarType, metadata::ScalarType>;
+
+/// The scalar type a scalar type is exposed as.
+fn exposed_name(
+    exposed_names: &ExposedNames,
+    scalar_type: &metadata::ScalarType,
+) -> metadata::ScalarType {
+    exposed_names
+        .get(scalar_type)
+        .unwrap_or(scalar_type)
+        .clone()
+}
+
+fn column_to_type(exposed_names: &ExposedNames, column: &metadata::ColumnInfo) -> models::Type {
     match &column.nullable {
-        metadata::Nullable::NonNullable => type_to_type(&column.r#type),
+        metadata::Nullable::NonNullable => type_to_type(exposed_names, &column.r#type),
+        metadata::Nullable::Nullable => models::Type::Nullable {
+            underlying_type: Box::new(type_to_type(exposed_names, &column.r#type)),
+        },
+    }
+}
+
+fn computed_field_to_type(
+    exposed_names: &ExposedNames,
+    field: &metadata::ComputedField,
+) -> models::Type {
+    let scalar_type = models::Type::Named {
+        name: exposed_name(exposed_names, &field.r#type).0,
+    };
+    match &field.nullable {
+        metadata::Nullable::NonNullable => scalar_type,
         metadata::Nullable::Nullable => models::Type::Nullable {
-            underlying_type: Box::new(type_to_type(&column.r#type)),
+            underlying_type: Box::new(scalar_type),
         },
     }
 }
 
-fn type_to_type(typ: &metadata::Type) -> models::Type {
+fn type_to_type(exposed_names: &ExposedNames, typ: &metadata::Type) -> models::Type {
     match &typ {
         metadata::Type::ArrayType(typ) => models::Type::Array {
-            element_type: Box::new(type_to_type(typ)),
+            element_type: Box::new(type_to_type(exposed_names, typ)),
         },
         metadata::Type::ScalarType(scalar_type) => models::Type::Named {
-            name: scalar_type.0.clone(),
+            name: exposed_name(exposed_names, scalar_type).0,
         },
+        metadata::Type::CompositeType(type_name) => models::Type::Named {
+            name: type_name.clone(),
+        },
+    }
+}
+
+/// The aggregate functions exposed for a scalar type: those found during introspection, plus the
+/// standard `sum`, `avg`, `min` and `max` aggregates for numeric types, and `bool_and` and
+/// `bool_or` for booleans, when introspection did not provide them.
+///
+/// A standard aggregate is only added when its result type is itself a scalar type in the schema,
+/// so that the schema never refers to an unknown type.
+fn aggregate_functions(
+    scalar_type: &metadata::ScalarType,
+    introspected: &BTreeMap<String, metadata::AggregateFunction>,
+    scalar_types: &BTreeSet<metadata::ScalarType>,
+) -> BTreeMap<String, metadata::ScalarType> {
+    let mut aggregate_functions: BTreeMap<String, metadata::ScalarType> = introspected
+        .iter()
+        .map(|(function_name, function_definition)| {
+            (
+                function_name.clone(),
+                function_definition.return_type.clone(),
+            )
+        })
+        .collect();
+
+    for (function_name, return_type) in metadata::standard_aggregate_functions(scalar_type) {
+        let return_type = metadata::ScalarType(return_type.to_string());
+        if scalar_types.contains(&return_type) {
+            aggregate_functions
+                .entry(function_name.to_string())
+                .or_insert(return_type);
+        }
+    }
+
+    aggregate_functions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar_types(names: &[&str]) -> BTreeSet<metadata::ScalarType> {
+        names
+            .iter()
+            .map(|name| metadata::ScalarType(name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn standard_aggregates_on_int4_have_correct_result_types() {
+        let result = aggregate_functions(
+            &metadata::ScalarType("int4".to_string()),
+            &BTreeMap::new(),
+            &scalar_types(&["int4", "int8", "numeric"]),
+        );
+
+        assert_eq!(
+            result,
+            BTreeMap::from([
+                (
+                    "avg".to_string(),
+                    metadata::ScalarType("numeric".to_string())
+                ),
+                ("max".to_string(), metadata::ScalarType("int4".to_string())),
+                ("min".to_string(), metadata::ScalarType("int4".to_string())),
+                ("sum".to_string(), metadata::ScalarType("int8".to_string())),
+            ])
+        );
+    }
+
+    #[test]
+    fn standard_aggregates_do_not_narrow_sum_when_wider_type_is_missing() {
+        let result = aggregate_functions(
+            &metadata::ScalarType("int4".to_string()),
+            &BTreeMap::new(),
+            &scalar_types(&["int4"]),
+        );
+
+        assert_eq!(
+            result,
+            BTreeMap::from([
+                ("max".to_string(), metadata::ScalarType("int4".to_string())),
+                ("min".to_string(), metadata::ScalarType("int4".to_string())),
+            ])
+        );
+    }
+
+    #[test]
+    fn introspected_aggregates_take_precedence() {
+        let result = aggregate_functions(
+            &metadata::ScalarType("int4".to_string()),
+            &BTreeMap::from([(
+                "sum".to_string(),
+                metadata::AggregateFunction {
+                    return_type: metadata::ScalarType("numeric".to_string()),
+                },
+            )]),
+            &scalar_types(&["int4", "int8", "numeric"]),
+        );
+
+        assert_eq!(
+            result.get("sum"),
+            Some(&metadata::ScalarType("numeric".to_string()))
+        );
+    }
+
+    #[test]
+    fn standard_aggregates_on_bool_are_bool_and_and_bool_or() {
+        let result = aggregate_functions(
+            &metadata::ScalarType("bool".to_string()),
+            &BTreeMap::new(),
+            &scalar_types(&["bool"]),
+        );
+
+        assert_eq!(
+            result,
+            BTreeMap::from([
+                (
+                    "bool_and".to_string(),
+                    metadata::ScalarType("bool".to_string())
+                ),
+                (
+                    "bool_or".to_string(),
+                    metadata::ScalarType("bool".to_string())
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn no_standard_aggregates_for_non_numeric_types() {
+        let result = aggregate_functions(
+            &metadata::ScalarType("text".to_string()),
+            &BTreeMap::new(),
+            &scalar_types(&["text", "int8", "numeric"]),
+        );
+
+        assert!(result.is_empty());
+    }
+
+    #[tokio::test]
+    async fn same_named_tables_in_different_schemas_are_kept_apart() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "events": {
+                    "schemaName": "public",
+                    "tableName": "events",
+                    "columns": {
+                        "id": { "name": "id", "type": { "scalarType": "int4" } }
+                    }
+                },
+                "audit_events": {
+                    "schemaName": "audit",
+                    "tableName": "events",
+                    "columns": {
+                        "id": { "name": "id", "type": { "scalarType": "int4" } },
+                        "actor": { "name": "actor", "type": { "scalarType": "text" } }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        for collection in &schema.collections {
+            assert!(
+                schema
+                    .object_types
+                    .contains_key(&collection.collection_type),
+                "missing object type for collection {}",
+                collection.name
+            );
+        }
+        assert_eq!(
+            schema.object_types["events"]
+                .fields
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["id"]
+        );
+        assert_eq!(
+            schema.object_types["audit_events"]
+                .fields
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["actor", "id"]
+        );
+    }
+
+    #[tokio::test]
+    async fn allowed_values_are_listed_in_the_field_description() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "orders": {
+                    "schemaName": "public",
+                    "tableName": "orders",
+                    "columns": {
+                        "status": {
+                            "name": "status",
+                            "type": { "scalarType": "text" },
+                            "description": "The order status",
+                            "allowedValues": ["pending", "shipped", "delivered"]
+                        },
+                        "kind": {
+                            "name": "kind",
+                            "type": { "scalarType": "text" },
+                            "allowedValues": ["retail", "wholesale"]
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let fields = &schema.object_types["orders"].fields;
+        assert_eq!(
+            fields["status"].description.as_deref(),
+            Some("The order status\n\nAllowed values: pending, shipped, delivered")
+        );
+        assert_eq!(
+            fields["kind"].description.as_deref(),
+            Some("Allowed values: retail, wholesale")
+        );
+    }
+
+    #[tokio::test]
+    async fn collations_are_listed_in_the_field_description() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "products": {
+                    "schemaName": "public",
+                    "tableName": "products",
+                    "columns": {
+                        "code": {
+                            "name": "code",
+                            "type": { "scalarType": "text" },
+                            "collation": "C"
+                        },
+                        "size": {
+                            "name": "size",
+                            "type": { "scalarType": "text" },
+                            "description": "The product size",
+                            "allowedValues": ["S", "M", "L"],
+                            "collation": "C"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let fields = &schema.object_types["products"].fields;
+        assert_eq!(fields["code"].description.as_deref(), Some("Collation: C"));
+        assert_eq!(
+            fields["size"].description.as_deref(),
+            Some("The product size\n\nAllowed values: S, M, L\n\nCollation: C")
+        );
+    }
+
+    #[tokio::test]
+    async fn enum_labels_are_listed_in_the_field_description_in_the_exposed_case() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "orders": {
+                    "schemaName": "public",
+                    "tableName": "orders",
+                    "columns": {
+                        "status": {
+                            "name": "status",
+                            "type": { "scalarType": "order_status" }
+                        }
+                    }
+                }
+            },
+            "enumTypes": {
+                "order_status": ["pending", "shipped"]
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            enum_value_case: Some(translation::helpers::EnumValueCase::Upper),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let fields = &schema.object_types["orders"].fields;
+        assert_eq!(
+            fields["status"].description.as_deref(),
+            Some("Allowed values: PENDING, SHIPPED")
+        );
+    }
+
+    #[tokio::test]
+    async fn sequence_values_are_listed_in_the_field_description() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "tickets": {
+                    "schemaName": "public",
+                    "tableName": "tickets",
+                    "columns": {
+                        "id": {
+                            "name": "id",
+                            "type": { "scalarType": "int4" },
+                            "sequenceLastValue": 3
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            schema.object_types["tickets"].fields["id"]
+                .description
+                .as_deref(),
+            Some("Estimated maximum: 3 (the last value of its sequence when introspected)")
+        );
+    }
+
+    #[tokio::test]
+    async fn computed_fields_are_exposed_as_fields() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "invoices": {
+                    "schemaName": "public",
+                    "tableName": "invoices",
+                    "columns": {
+                        "due_date": {
+                            "name": "due_date",
+                            "type": { "scalarType": "date" }
+                        }
+                    },
+                    "computedFields": {
+                        "is_overdue": {
+                            "expression": "CASE WHEN due_date < current_date THEN true ELSE false END",
+                            "type": "bool",
+                            "nullable": "nonNullable",
+                            "description": "Whether the invoice is past its due date"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let is_overdue = &schema.object_types["invoices"].fields["is_overdue"];
+        assert_eq!(
+            is_overdue.description.as_deref(),
+            Some("Whether the invoice is past its due date")
+        );
+        assert!(matches!(
+            &is_overdue.r#type,
+            models::Type::Named { name } if name == "bool"
+        ));
+        assert!(schema.scalar_types.contains_key("bool"));
+    }
+
+    #[tokio::test]
+    async fn ranking_fields_are_exposed_as_int8_fields() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "Album": {
+                    "schemaName": "public",
+                    "tableName": "Album",
+                    "columns": {
+                        "ArtistId": { "name": "ArtistId", "type": { "scalarType": "int4" } },
+                        "Title": { "name": "Title", "type": { "scalarType": "varchar" } }
+                    },
+                    "rankingFields": {
+                        "PositionInArtist": {
+                            "function": "rowNumber",
+                            "partitionBy": ["ArtistId"],
+                            "orderBy": [{ "column": "Title" }],
+                            "description": "The position of the album among those of its artist"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let position = &schema.object_types["Album"].fields["PositionInArtist"];
+        assert_eq!(
+            position.description.as_deref(),
+            Some("The position of the album among those of its artist")
+        );
+        assert!(matches!(
+            &position.r#type,
+            models::Type::Named { name } if name == "int8"
+        ));
+        assert!(schema.scalar_types.contains_key("int8"));
+    }
+
+    #[tokio::test]
+    async fn search_rank_fields_are_exposed_with_their_argument() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "documents": {
+                    "schemaName": "public",
+                    "tableName": "documents",
+                    "columns": {
+                        "search_vector": {
+                            "name": "search_vector",
+                            "type": { "scalarType": "tsvector" }
+                        }
+                    },
+                    "searchRankFields": {
+                        "relevance": {
+                            "function": "tsRank",
+                            "column": "search_vector",
+                            "argument": "search",
+                            "description": "The relevance of the document to the search"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let relevance = &schema.object_types["documents"].fields["relevance"];
+        assert_eq!(
+            relevance.description.as_deref(),
+            Some("The relevance of the document to the search")
+        );
+        assert!(matches!(
+            &relevance.r#type,
+            models::Type::Nullable { underlying_type }
+                if matches!(&**underlying_type, models::Type::Named { name } if name == "float4")
+        ));
+        let collection = schema
+            .collections
+            .iter()
+            .find(|collection| collection.name == "documents")
+            .unwrap();
+        assert!(matches!(
+            &collection.arguments["search"].argument_type,
+            models::Type::Nullable { underlying_type }
+                if matches!(&**underlying_type, models::Type::Named { name } if name == "text")
+        ));
+        assert!(schema.scalar_types.contains_key("float4"));
+        assert!(schema.scalar_types.contains_key("text"));
+    }
+
+    #[tokio::test]
+    async fn row_level_security_policies_are_listed_in_the_collection_description() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "documents": {
+                    "schemaName": "public",
+                    "tableName": "documents",
+                    "columns": {},
+                    "description": "Shared documents",
+                    "rls": {
+                        "policies": {
+                            "own_documents": { "command": "select" },
+                            "admins": { "command": "all" }
+                        }
+                    }
+                },
+                "secrets": {
+                    "schemaName": "public",
+                    "tableName": "secrets",
+                    "columns": {},
+                    "rls": {}
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let descriptions: BTreeMap<&str, Option<&str>> = schema
+            .collections
+            .iter()
+            .map(|collection| (collection.name.as_str(), collection.description.as_deref()))
+            .collect();
+        assert_eq!(
+            descriptions["documents"],
+            Some("Shared documents\n\nRow-level security policies: admins (ALL), own_documents (SELECT)")
+        );
+        assert_eq!(
+            descriptions["secrets"],
+            Some("Row-level security policies: none")
+        );
+        // The object type keeps the plain description.
+        assert_eq!(
+            schema.object_types["documents"].description.as_deref(),
+            Some("Shared documents")
+        );
+    }
+
+    #[tokio::test]
+    async fn triggers_are_listed_in_the_collection_description() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "Invoice": {
+                    "schemaName": "public",
+                    "tableName": "Invoice",
+                    "columns": {},
+                    "triggers": {
+                        "invoice_audit": { "timing": "after", "events": ["insert"] },
+                        "invoice_guard": { "timing": "before", "events": ["update", "delete"] }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            schema.collections[0].description.as_deref(),
+            Some("Triggers: invoice_audit (AFTER INSERT), invoice_guard (BEFORE UPDATE OR DELETE)")
+        );
+    }
+
+    #[tokio::test]
+    async fn foreign_tables_are_marked_read_only_in_the_collection_description() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "remote_invoices": {
+                    "schemaName": "public",
+                    "tableName": "remote_invoices",
+                    "foreignServer": "billing",
+                    "columns": {}
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            schema.collections[0].description.as_deref(),
+            Some("Foreign table on the server 'billing'. It is read-only.")
+        );
+    }
+
+    #[tokio::test]
+    async fn insertable_columns_are_listed_in_the_collection_description() {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "orders": {
+                    "schemaName": "public",
+                    "tableName": "orders",
+                    "columns": {
+                        "id": {
+                            "name": "id",
+                            "type": { "scalarType": "int8" },
+                            "nullable": "nonNullable",
+                            "isIdentity": "identityAlways"
+                        },
+                        "note": {
+                            "name": "note",
+                            "type": { "scalarType": "text" }
+                        },
+                        "placed_at": {
+                            "name": "placed_at",
+                            "type": { "scalarType": "timestamptz" },
+                            "nullable": "nonNullable",
+                            "hasDefault": true
+                        },
+                        "total": {
+                            "name": "total",
+                            "type": { "scalarType": "numeric" },
+                            "nullable": "nonNullable"
+                        },
+                        "total_with_tax": {
+                            "name": "total_with_tax",
+                            "type": { "scalarType": "numeric" },
+                            "isGenerated": true
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let schema = get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            schema.collections[0].description.as_deref(),
+            Some("Insertable columns: note, placed_at (optional), total")
+        );
+        // Generated columns cannot be inserted, but they are still fields which can be selected.
+        assert_eq!(
+            schema.object_types["orders"].fields["total_with_tax"]
+                .description
+                .as_deref(),
+            Some("Generated from the other columns of its row, and read-only.")
+        );
+    }
+
+    /// The schema of a table with a column of a custom type nothing is known about.
+    async fn schema_with_unknown_scalar_type(
+        unknown_scalar_strategy: UnknownScalarStrategy,
+    ) -> Result<models::SchemaResponse, connector::SchemaError> {
+        let metadata: metadata::Metadata = serde_json::from_value(serde_json::json!({
+            "tables": {
+                "shapes": {
+                    "schemaName": "public",
+                    "tableName": "shapes",
+                    "columns": {
+                        "id": {
+                            "name": "id",
+                            "type": { "scalarType": "int4" },
+                            "nullable": "nonNullable"
+                        },
+                        "outline": {
+                            "name": "outline",
+                            "type": { "scalarType": "polygon_2d" },
+                            "nullable": "nullable"
+                        }
+                    }
+                }
+            },
+            "comparisonOperators": {
+                "int4": {
+                    "_gt": { "operatorName": ">", "argumentType": "int4" }
+                }
+            }
+        }))
+        .unwrap();
+
+        get_schema(&configuration::RuntimeConfiguration {
+            metadata,
+            unknown_scalar_strategy,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// The name of the scalar type of a nullable field.
+    fn nullable_field_type_name(field: &models::ObjectField) -> &str {
+        match &field.r#type {
+            models::Type::Nullable { underlying_type } => match underlying_type.as_ref() {
+                models::Type::Named { name } => name,
+                _ => panic!("expected a named type"),
+            },
+            _ => panic!("expected a nullable type"),
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_scalar_types_are_opaque_by_default() {
+        let schema = schema_with_unknown_scalar_type(UnknownScalarStrategy::TreatAsJson)
+            .await
+            .unwrap();
+
+        let outline = &schema.object_types["shapes"].fields["outline"];
+        assert_eq!(nullable_field_type_name(outline), "polygon_2d");
+        let scalar_type = &schema.scalar_types["polygon_2d"];
+        assert!(scalar_type.comparison_operators.is_empty());
+        assert!(scalar_type.aggregate_functions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_scalar_types_can_be_exposed_as_text() {
+        let schema = schema_with_unknown_scalar_type(UnknownScalarStrategy::TreatAsString)
+            .await
+            .unwrap();
+
+        let outline = &schema.object_types["shapes"].fields["outline"];
+        assert_eq!(nullable_field_type_name(outline), "text");
+        assert!(schema.scalar_types.contains_key("text"));
+        assert!(!schema.scalar_types.contains_key("polygon_2d"));
+        // Known types are left alone.
+        assert!(schema.scalar_types["int4"]
+            .comparison_operators
+            .contains_key("_gt"));
     }
 }
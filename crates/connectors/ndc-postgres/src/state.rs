@@ -6,31 +6,136 @@ use percent_encoding::percent_decode_str;
 use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions, PgRow};
 use sqlx::{ConnectOptions, Row};
 use thiserror::Error;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tracing::{info_span, Instrument};
 use url::Url;
 
-use crate::configuration::PoolSettings;
+use crate::configuration::version2;
+use crate::configuration::{
+    ConnectionUriError, PoolSettings, ResolvedSecret, RuntimeConfiguration,
+};
 use query_engine_execution::database_info::{self, DatabaseInfo, DatabaseVersion};
+use query_engine_execution::listen;
 use query_engine_execution::metrics;
+use query_engine_execution::pool::ConnectionPool;
 
 /// State for our connector.
 #[derive(Debug)]
 pub struct State {
-    pub pool: PgPool,
+    pub pool: ConnectionPool,
     pub database_info: DatabaseInfo,
     pub metrics: metrics::Metrics,
+    /// Which of the regional connection URIs the pool connects with, if not the connection URI.
+    pub regional_connection_uri: Option<usize>,
+}
+
+impl State {
+    /// Connect with the credentials in a freshly resolved connection URI from now on, the same
+    /// one the pool was created with. The connection URI variable is read again, so that the
+    /// credentials it holds now are used.
+    ///
+    /// Existing connections are kept until they fail, so that a request which failed because
+    /// the credentials were rotated can be retried straight away.
+    pub fn reconnect(
+        &self,
+        configuration: &RuntimeConfiguration,
+    ) -> Result<(), InitializationError> {
+        let connection_uri = match self
+            .regional_connection_uri
+            .and_then(|index| configuration.regional_connection_uris.get(index))
+        {
+            Some(connection_uri) => connection_uri.clone(),
+            None => resolve_connection_uri(configuration)?,
+        };
+        let connection_url: Url = connection_uri
+            .0
+            .parse()
+            .map_err(InitializationError::InvalidConnectionUri)?;
+        self.pool.set_connect_options(connect_options(
+            &connection_url,
+            &configuration.pool_settings,
+        )?);
+        Ok(())
+    }
+
+    /// Experimental: subscribe to the notifications sent to a channel, which are received on a
+    /// dedicated connection rather than one of the pool's.
+    pub async fn subscribe(
+        &self,
+        channel: &str,
+    ) -> Result<UnboundedReceiver<listen::Notification>, sqlx::Error> {
+        listen::listen(&self.pool, channel).await
+    }
+}
+
+/// Create a connector State with the first of the regional connection URIs the database can be
+/// reached with, in the order of preference, and otherwise with the connection URI.
+///
+/// The URIs are not logged, as they may hold credentials.
+pub async fn create_state_for_region(
+    configuration: &RuntimeConfiguration,
+    metrics_registry: &mut prometheus::Registry,
+) -> Result<State, InitializationError> {
+    for (index, connection_uri) in configuration.regional_connection_uris.iter().enumerate() {
+        match create_state(
+            &connection_uri.0,
+            &configuration.pool_settings,
+            &configuration.after_connect_sql,
+            metrics_registry,
+        )
+        .await
+        {
+            Ok(state) => {
+                return Ok(State {
+                    regional_connection_uri: Some(index),
+                    ..state
+                })
+            }
+            Err(
+                err @ (InitializationError::UnableToCreatePool(_)
+                | InitializationError::UnableToConnect(_)
+                | InitializationError::ConnectionCheckFailed(_)),
+            ) => {
+                tracing::warn!(
+                    "unable to connect with regional connection URI {}, trying the next one: {}",
+                    index,
+                    err
+                );
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    create_state(
+        &resolve_connection_uri(configuration)?.0,
+        &configuration.pool_settings,
+        &configuration.after_connect_sql,
+        metrics_registry,
+    )
+    .await
+}
+
+/// Read the connection URI the configuration connects with.
+fn resolve_connection_uri(
+    configuration: &RuntimeConfiguration,
+) -> Result<ResolvedSecret, InitializationError> {
+    configuration
+        .resolve_connection_uri()
+        .map_err(InitializationError::UnresolvedConnectionUri)
 }
 
 /// Create a connection pool and wrap it inside a connector State.
+///
+/// The `after_connect_sql` statements are run on each connection the pool opens.
 pub async fn create_state(
     connection_uri: &str,
     pool_settings: &PoolSettings,
+    after_connect_sql: &[String],
     metrics_registry: &mut prometheus::Registry,
 ) -> Result<State, InitializationError> {
     let connection_url: Url = connection_uri
         .parse()
         .map_err(InitializationError::InvalidConnectionUri)?;
-    let pool = create_pool(&connection_url, pool_settings)
+    let pool = create_pool(&connection_url, pool_settings, after_connect_sql)
         .instrument(info_span!("Create connection pool"))
         .await?;
 
@@ -48,6 +153,21 @@ pub async fn create_state(
             .ok();
         // Extract the database version number.
         let number = connection.server_version_num();
+
+        // Having connected only shows that something accepts connections, which PgBouncer does
+        // even when the database behind it is down. Running the validation query on the same
+        // connection shows that the database can run queries.
+        if pool_settings.check_connection_on_startup {
+            let query = pool_settings
+                .validation_query
+                .as_deref()
+                .unwrap_or("SELECT 1");
+            query_engine_execution::pool::validate_connection(connection.as_mut(), query)
+                .instrument(info_span!("Check database connection"))
+                .await
+                .map_err(InitializationError::ConnectionCheckFailed)?;
+        }
+
         DatabaseVersion { string, number }
     };
     let database_info = parse_database_info(&connection_url, database_version)?;
@@ -61,10 +181,25 @@ pub async fn create_state(
     .instrument(info_span!("Setup metrics"))
     .await?;
 
+    let pool = ConnectionPool::new(
+        pool,
+        pool_settings.max_waiting_requests,
+        pool_settings.fair_acquisition,
+        pool_settings.statement_cache_capacity != Some(0),
+    );
+    let pool = match pool_settings.connection_check_interval {
+        None => pool,
+        Some(interval) => pool.check_idle_connections(
+            std::time::Duration::from_secs(interval),
+            pool_settings.validation_query.clone(),
+        ),
+    };
+
     Ok(State {
         pool,
         database_info,
         metrics,
+        regional_connection_uri: None,
     })
 }
 
@@ -73,9 +208,16 @@ pub async fn create_state(
 async fn create_pool(
     connection_url: &Url,
     pool_settings: &PoolSettings,
+    after_connect_sql: &[String],
 ) -> Result<PgPool, InitializationError> {
-    let connect_options = PgConnectOptions::from_url(connection_url)
-        .map_err(InitializationError::UnableToCreatePool)?;
+    let connect_options = connect_options(connection_url, pool_settings)?;
+    let after_connect_sql = after_connect_sql.to_vec();
+    // Connections checked in the background are not checked again when they are handed out.
+    let checked_in_background = pool_settings.connection_check_interval.is_some();
+    let validation_query = pool_settings
+        .validation_query
+        .clone()
+        .filter(|_| !checked_in_background);
     PgPoolOptions::new()
         .max_connections(pool_settings.max_connections)
         .acquire_timeout(std::time::Duration::from_secs(pool_settings.pool_timeout))
@@ -89,11 +231,45 @@ async fn create_pool(
                 .connection_lifetime
                 .map(std::time::Duration::from_secs),
         )
+        .after_connect(move |connection, _metadata| {
+            let after_connect_sql = after_connect_sql.clone();
+            Box::pin(async move {
+                version2::run_after_connect_sql(connection, &after_connect_sql).await
+            })
+        })
+        // An idle connection failing the validation query is closed rather than handed out.
+        .test_before_acquire(validation_query.is_none() && !checked_in_background)
+        .before_acquire(move |connection, _metadata| {
+            let validation_query = validation_query.clone();
+            Box::pin(async move {
+                match validation_query {
+                    None => Ok(true),
+                    Some(query) => Ok(query_engine_execution::pool::validate_connection(
+                        connection, &query,
+                    )
+                    .await
+                    .is_ok()),
+                }
+            })
+        })
         .connect_with(connect_options)
         .await
         .map_err(InitializationError::UnableToCreatePool)
 }
 
+/// The options for opening connections to the database.
+fn connect_options(
+    connection_url: &Url,
+    pool_settings: &PoolSettings,
+) -> Result<PgConnectOptions, InitializationError> {
+    let connect_options = PgConnectOptions::from_url(connection_url)
+        .map_err(InitializationError::UnableToCreatePool)?;
+    Ok(match pool_settings.statement_cache_capacity {
+        None => connect_options,
+        Some(capacity) => connect_options.statement_cache_capacity(capacity),
+    })
+}
+
 /// Parses the database information from the connection string.
 /// The database name is assumed to be "postgresql".
 /// The database version must be passed in as we cannot derive it.
@@ -138,10 +314,14 @@ fn decode_uri_component(component: &str) -> String {
 pub enum InitializationError {
     #[error("invalid connection URI: {0}")]
     InvalidConnectionUri(url::ParseError),
+    #[error("{0}")]
+    UnresolvedConnectionUri(ConnectionUriError),
     #[error("unable to initialize connection pool: {0}")]
     UnableToCreatePool(sqlx::Error),
     #[error("unable to connect to the database: {0}")]
     UnableToConnect(sqlx::Error),
+    #[error("the database did not respond to a connection check: {0}")]
+    ConnectionCheckFailed(sqlx::Error),
     #[error("error initializing metrics: {0}")]
     MetricsError(metrics::Error),
 }
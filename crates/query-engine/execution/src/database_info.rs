@@ -24,6 +24,16 @@ pub struct DatabaseInfo {
     pub server_database: Option<String>,
 }
 
+impl DatabaseInfo {
+    /// Whether the database is CockroachDB rather than PostgreSQL itself, as told by its version.
+    pub fn is_cockroach(&self) -> bool {
+        self.system_version
+            .string
+            .as_deref()
+            .map_or(false, |version| version.starts_with("CockroachDB"))
+    }
+}
+
 /// Version information derived from the database.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DatabaseVersion {
@@ -0,0 +1,126 @@
+//! Export the rows of a query with `COPY ... TO STDOUT`, passing them on as the database sends
+//! them rather than collecting them into a single response first.
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use sqlx::pool::PoolConnection;
+use sqlx::Postgres;
+use tokio::sync::mpsc;
+use tracing::{info_span, Instrument};
+
+use crate::database_info::DatabaseInfo;
+use crate::metrics;
+use crate::pool;
+use crate::query::{execute_statement, Error, QueryError};
+use query_engine_sql::sql;
+
+/// How many chunks of the output are buffered before the database has to wait for the receiver
+/// to catch up.
+const BUFFERED_CHUNKS: usize = 16;
+
+/// Export the rows of a query. The output is sent to the returned receiver in chunks, in the
+/// format of the export, as the database sends it.
+///
+/// The connection is held until the export completes or the receiver is dropped. Errors which
+/// occur once the export has started, including those of the query itself, are sent to the
+/// receiver in place of a chunk, after which it is closed.
+pub async fn export(
+    pool: &pool::ConnectionPool,
+    database_info: &DatabaseInfo,
+    metrics: &metrics::Metrics,
+    plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Export>,
+) -> Result<mpsc::Receiver<Result<Bytes, Error>>, Error> {
+    let sql::execution_plan::ExecutionPlan {
+        pre,
+        query: plan,
+        post,
+        max_sql_length,
+    } = plan;
+    let query = plan.export_sql();
+    sql::execution_plan::check_sql_length(&query, max_sql_length)?;
+
+    tracing::info!(generated_sql = query.sql, params = ?&query.params);
+
+    // The parameters are written into the query, which variables cannot be.
+    if let Some(sql::string::Param::Variable(var)) = query
+        .params
+        .iter()
+        .find(|param| matches!(param, sql::string::Param::Variable(_)))
+    {
+        return Err(Error::Query(QueryError::VariableNotFound(var.to_string())));
+    }
+
+    let mut connection = pool.acquire(metrics).await?;
+    let persistent = pool.persistent_statements();
+
+    let span = info_span!(
+        "Database request",
+        internal.visibility = "user",
+        db.system = database_info.system_name,
+        db.version_string = database_info.system_version.string,
+        db.version_number = database_info.system_version.number,
+        db.user = database_info.server_username,
+        db.name = database_info.server_database,
+        server.address = database_info.server_host,
+        server.port = database_info.server_port,
+    );
+    let (sender, receiver) = mpsc::channel(BUFFERED_CHUNKS);
+    tokio::spawn(
+        async move {
+            let result = copy_out(
+                &mut connection,
+                &pre,
+                &query.sql,
+                &post,
+                persistent,
+                &sender,
+            )
+            .await;
+            let completed = match result {
+                Ok(completed) => completed,
+                Err(err) => {
+                    // The receiver may be gone already, in which case there is nobody to tell.
+                    let _ = sender.send(Err(err)).await;
+                    false
+                }
+            };
+            // Don't hand a connection in the middle of a COPY or of an aborted transaction back
+            // to the pool.
+            if !completed {
+                if let Err(err) = connection.close().await {
+                    tracing::warn!("failed to close the export connection: {}", err);
+                }
+            }
+        }
+        .instrument(span),
+    );
+    Ok(receiver)
+}
+
+/// Run the statements before the export, the export itself, and the statements after it,
+/// forwarding the output to the sender. Returns whether the export completed, rather than being
+/// abandoned by the receiver.
+async fn copy_out(
+    connection: &mut PoolConnection<Postgres>,
+    pre: &[sql::string::Statement],
+    query: &str,
+    post: &[sql::string::Statement],
+    persistent: bool,
+    sender: &mpsc::Sender<Result<Bytes, Error>>,
+) -> Result<bool, Error> {
+    for statement in pre {
+        execute_statement(connection, statement, persistent).await?;
+    }
+    {
+        let mut chunks = connection.as_mut().copy_out_raw(query).await?;
+        while let Some(chunk) = chunks.next().await {
+            if sender.send(Ok(chunk?)).await.is_err() {
+                return Ok(false);
+            }
+        }
+    }
+    for statement in post {
+        execute_statement(connection, statement, persistent).await?;
+    }
+    Ok(true)
+}
@@ -2,6 +2,9 @@
 //! See `/architecture.md#execution` in the repository for more details.
 
 pub mod database_info;
+pub mod export;
+pub mod listen;
 pub mod metrics;
 pub mod mutation;
+pub mod pool;
 pub mod query;
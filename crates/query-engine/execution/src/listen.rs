@@ -0,0 +1,67 @@
+//! Experimental: forward the notifications sent to a channel with `NOTIFY` or `pg_notify`, for
+//! instance so that cached results can be invalidated when the data changes.
+
+use std::time::Duration;
+
+use sqlx::postgres::{PgListener, PgPoolOptions};
+use tokio::sync::mpsc;
+
+/// How long to wait before trying to listen again when the connection cannot be reopened.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// A notification received on a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
+}
+
+/// Listen on a channel on a dedicated connection, opened with the same options as the
+/// connections of the pool, and forward the notifications received to the returned receiver.
+///
+/// When the connection is lost, a new one is opened to listen on, though the notifications sent
+/// in between are missed. Listening stops once the receiver is dropped.
+pub async fn listen(
+    pool: &sqlx::PgPool,
+    channel: &str,
+) -> Result<mpsc::UnboundedReceiver<Notification>, sqlx::Error> {
+    // The listener holds on to a connection of this pool, and takes a new one when it loses it.
+    let connections = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_lazy_with((*pool.connect_options()).clone());
+    let mut listener = PgListener::connect_with(&connections).await?;
+    listener.listen(channel).await?;
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(forward_notifications(listener, sender));
+    Ok(receiver)
+}
+
+/// Forward notifications until the receiving end is dropped.
+async fn forward_notifications(
+    mut listener: PgListener,
+    sender: mpsc::UnboundedSender<Notification>,
+) {
+    loop {
+        let received = tokio::select! {
+            () = sender.closed() => return,
+            received = listener.recv() => received,
+        };
+        match received {
+            Ok(notification) => {
+                let notification = Notification {
+                    channel: notification.channel().to_string(),
+                    payload: notification.payload().to_string(),
+                };
+                if sender.send(notification).is_err() {
+                    return;
+                }
+            }
+            // The listener reconnects by itself the next time we ask it for a notification.
+            Err(err) => {
+                tracing::warn!("Unable to listen for notifications, retrying: {}", err);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
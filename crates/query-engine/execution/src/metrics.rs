@@ -4,6 +4,8 @@ use std::time::Duration;
 
 use prometheus::{Gauge, Histogram, HistogramTimer, IntCounter, IntGauge, Registry};
 
+use crate::pool;
+
 /// The collection of all metrics exposed through the `/metrics` endpoint.
 #[derive(Debug, Clone)]
 pub struct Metrics {
@@ -18,6 +20,7 @@ pub struct Metrics {
     pool_size: IntGauge,
     pool_idle_count: IntGauge,
     pool_active_count: IntGauge,
+    pool_waiting_count: IntGauge,
     pool_max_connections: IntGauge,
     pool_min_connections: IntGauge,
     pool_acquire_timeout: Gauge,
@@ -95,6 +98,12 @@ impl Metrics {
             "The number of connections current active. This does not include idle connections.",
         )?;
 
+        let pool_waiting_count = add_int_gauge_metric(
+            metrics_registry,
+            "ndc_postgres_pool_waiting",
+            "The number of requests currently waiting to acquire a connection.",
+        )?;
+
         let pool_max_connections = add_int_gauge_metric(
             metrics_registry,
             "ndc_postgres_pool_max_connections",
@@ -139,6 +148,7 @@ impl Metrics {
             pool_size,
             pool_idle_count,
             pool_active_count,
+            pool_waiting_count,
             pool_max_connections,
             pool_min_connections,
             pool_acquire_timeout,
@@ -209,7 +219,7 @@ impl Metrics {
     }
 
     // Update all metrics fed from the database pool.
-    pub fn update_pool_metrics(&self, pool: &sqlx::PgPool) {
+    pub fn update_pool_metrics(&self, pool: &pool::ConnectionPool) {
         let pool_size: i64 = pool.size().into();
         self.pool_size.set(pool_size);
 
@@ -218,6 +228,9 @@ impl Metrics {
 
         let pool_active: i64 = pool_size - pool_idle;
         self.pool_active_count.set(pool_active);
+
+        let pool_waiting: i64 = pool.num_waiting().into();
+        self.pool_waiting_count.set(pool_waiting);
     }
 }
 
@@ -329,6 +342,8 @@ pub struct ErrorMetrics {
     database_error_total: IntCounter,
     /// we failed to acquire a database connection from the pool
     connection_acquisition_error_total: IntCounter,
+    /// we refused to wait for a database connection because the pool was saturated
+    connection_acquisition_rejected_total: IntCounter,
 }
 
 impl ErrorMetrics {
@@ -370,6 +385,12 @@ impl ErrorMetrics {
             "Total number of failures to acquire a database connection.",
         )?;
 
+        let connection_acquisition_rejected_total = add_int_counter_metric(
+            metrics_registry,
+            "ndc_postgres_error_connection_acquisition_rejected_total_count",
+            "Total number of requests rejected because too many requests were waiting for a database connection.",
+        )?;
+
         Ok(ErrorMetrics {
             invalid_request_total,
             unsupported_capability_total,
@@ -377,6 +398,7 @@ impl ErrorMetrics {
             connector_error_total,
             database_error_total,
             connection_acquisition_error_total,
+            connection_acquisition_rejected_total,
         })
     }
 
@@ -398,4 +420,7 @@ impl ErrorMetrics {
     pub fn record_connection_acquisition_error(&self) {
         self.connection_acquisition_error_total.inc()
     }
+    pub fn record_connection_acquisition_rejected(&self) {
+        self.connection_acquisition_rejected_total.inc()
+    }
 }
@@ -4,36 +4,29 @@ use bytes::{BufMut, Bytes, BytesMut};
 use query_engine_sql::sql::helpers::transaction_rollback;
 use sqlx;
 use sqlx::pool::PoolConnection;
-use sqlx::{Postgres, Row};
+use sqlx::{Executor, Postgres, Row};
 use tracing::{info_span, Instrument};
 
 use crate::database_info::DatabaseInfo;
 use crate::metrics;
+use crate::pool;
 use query_engine_sql::sql;
 
 /// Execute mutations against postgres.
 pub async fn execute(
-    pool: &sqlx::PgPool,
+    pool: &pool::ConnectionPool,
     database_info: &DatabaseInfo,
     metrics: &metrics::Metrics,
-    plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
 ) -> Result<Bytes, Error> {
-    let acquisition_timer = metrics.time_connection_acquisition_wait();
-    let connection_result = pool
-        .acquire()
-        .instrument(info_span!("Acquire connection"))
-        .await;
-    let mut connection = acquisition_timer
-        .complete_with(connection_result)
-        .map_err(|err| {
-            metrics.error_metrics.record_connection_acquisition_error();
-            err
-        })?;
+    let mut connection = pool.acquire(metrics).await?;
 
     let query_timer = metrics.time_query_execution();
+    let persistent = pool.persistent_statements();
     let rows_result = rollback_on_exception(
-        execute_mutations(&mut connection, database_info, plan).await,
+        execute_mutations(&mut connection, database_info, plan, persistent).await,
         &mut connection,
+        persistent,
     )
     .await;
     query_timer.complete_with(rows_result)
@@ -43,10 +36,17 @@ pub async fn execute(
 async fn execute_statement(
     connection: &mut PoolConnection<Postgres>,
     sql::string::Statement(statement): &sql::string::Statement,
+    persistent: bool,
 ) -> Result<(), Error> {
-    sqlx::query(&statement.sql)
-        .execute(connection.as_mut())
-        .await?;
+    if persistent {
+        sqlx::query(&statement.sql)
+            .execute(connection.as_mut())
+            .await?;
+    } else {
+        // The statement has no parameters, so it can be run with the simple query protocol,
+        // without preparing it at all.
+        connection.as_mut().execute(statement.sql.as_str()).await?;
+    }
     Ok(())
 }
 
@@ -57,10 +57,11 @@ async fn execute_statement(
 async fn execute_mutations(
     connection: &mut PoolConnection<Postgres>,
     database_info: &DatabaseInfo,
-    plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
+    persistent: bool,
 ) -> Result<Bytes, Error> {
-    for statement in plan.pre {
-        execute_statement(connection, &statement).await?;
+    for statement in &plan.pre {
+        execute_statement(connection, statement, persistent).await?;
     }
 
     // this buffer represents the JSON response
@@ -72,33 +73,35 @@ async fn execute_mutations(
     // iterate over mutations
     let mut i = plan.query.0.iter();
     if let Some(mutation) = i.next() {
-        let mutation_sql = mutation.query_sql();
-
-        tracing::info!(
-            generated_sql = mutation_sql.sql,
-            params = ?&mutation_sql.params,
-        );
-
-        execute_query(connection, database_info, &mutation_sql, &mut buffer).await?;
+        execute_mutation(
+            connection,
+            database_info,
+            mutation,
+            plan.max_sql_length,
+            &mut buffer,
+            persistent,
+        )
+        .await?;
         for mutation in i {
             buffer.put(&[b','][..]); // each result, except the first, is prefixed by a ','
 
-            let mutation_sql = mutation.query_sql();
-
-            tracing::info!(
-                generated_sql = mutation_sql.sql,
-                params = ?&mutation_sql.params,
-            );
-
-            execute_query(connection, database_info, &mutation_sql, &mut buffer).await?;
+            execute_mutation(
+                connection,
+                database_info,
+                mutation,
+                plan.max_sql_length,
+                &mut buffer,
+                persistent,
+            )
+            .await?;
         }
     }
 
     buffer.put(&[b']'][..]); // we end by closing the array
     buffer.put(&[b'}'][..]); // and then the object
 
-    for statement in plan.post {
-        execute_statement(connection, &statement).await?
+    for statement in &plan.post {
+        execute_statement(connection, statement, persistent).await?
     }
 
     Ok(buffer.freeze())
@@ -109,9 +112,11 @@ async fn execute_mutations(
 async fn rollback_on_exception<T>(
     result: Result<T, Error>,
     connection: &mut PoolConnection<Postgres>,
+    persistent: bool,
 ) -> Result<T, Error> {
     match result {
-        Err(err1) => match execute_statement(connection, &transaction_rollback()).await {
+        Err(err1) => match execute_statement(connection, &transaction_rollback(), persistent).await
+        {
             Err(err2) => Err(Error::Multiple(Box::new(err1), Box::new(err2))),
             Ok(()) => Err(err1),
         },
@@ -119,15 +124,77 @@ async fn rollback_on_exception<T>(
     }
 }
 
+/// Execute a single mutation, and append its result to the given buffer.
+async fn execute_mutation(
+    connection: &mut PoolConnection<Postgres>,
+    database_info: &DatabaseInfo,
+    mutation: &sql::execution_plan::Mutation,
+    max_sql_length: Option<usize>,
+    buffer: &mut (impl BufMut + Send),
+    persistent: bool,
+) -> Result<(), Error> {
+    let mutation_sql = mutation.query_sql();
+    sql::execution_plan::check_sql_length(&mutation_sql, max_sql_length)?;
+
+    tracing::info!(
+        generated_sql = mutation_sql.sql,
+        params = ?&mutation_sql.params,
+    );
+
+    match mutation.query {
+        sql::execution_plan::MutationQuery::Select(_) => {
+            execute_query(connection, database_info, &mutation_sql, buffer, persistent).await
+        }
+        sql::execution_plan::MutationQuery::Statement(_) => {
+            let affected_rows =
+                execute_statement_query(connection, database_info, &mutation_sql, persistent)
+                    .await?;
+            buffer.put(&b"{\"affected_rows\":"[..]);
+            buffer.put(affected_rows.to_string().as_bytes());
+            buffer.put(&b",\"returning\":[]}"[..]);
+            Ok(())
+        }
+    }
+}
+
+/// Execute a statement which returns no rows, and return the number of rows it affected.
+async fn execute_statement_query(
+    connection: &mut PoolConnection<Postgres>,
+    database_info: &DatabaseInfo,
+    query: &sql::string::SQL,
+    persistent: bool,
+) -> Result<u64, Error> {
+    let sqlx_query = build_query_with_params(query, persistent)
+        .instrument(info_span!("Build query with params"))
+        .await?;
+
+    let result = sqlx_query
+        .execute(connection.as_mut())
+        .instrument(info_span!(
+            "Database request",
+            internal.visibility = "user",
+            db.system = database_info.system_name,
+            db.version_string = database_info.system_version.string,
+            db.version_number = database_info.system_version.number,
+            db.user = database_info.server_username,
+            db.name = database_info.server_database,
+            server.address = database_info.server_host,
+            server.port = database_info.server_port,
+        ))
+        .await?;
+    Ok(result.rows_affected())
+}
+
 /// Execute the query, and append the result to the given buffer.
 async fn execute_query(
     connection: &mut PoolConnection<Postgres>,
     database_info: &DatabaseInfo,
     query: &sql::string::SQL,
     buffer: &mut (impl BufMut + Send),
+    persistent: bool,
 ) -> Result<(), Error> {
     // build query
-    let sqlx_query = build_query_with_params(query)
+    let sqlx_query = build_query_with_params(query, persistent)
         .instrument(info_span!("Build query with params"))
         .await?;
 
@@ -163,8 +230,9 @@ async fn execute_query(
 /// Create a SQLx query based on our SQL query and bind our parameters to it.
 async fn build_query_with_params(
     query: &sql::string::SQL,
+    persistent: bool,
 ) -> Result<sqlx::query::Query<'_, sqlx::Postgres, sqlx::postgres::PgArguments>, Error> {
-    let sqlx_query = sqlx::query(query.sql.as_str());
+    let sqlx_query = sqlx::query(query.sql.as_str()).persistent(persistent);
 
     let sqlx_query = query
         .params
@@ -182,11 +250,15 @@ async fn build_query_with_params(
 pub enum Error {
     Query(QueryError),
     DB(sqlx::Error),
+    /// No connection was free to run the mutations on, and too many requests were waiting for
+    /// one.
+    PoolSaturated(pool::PoolSaturated),
     Multiple(Box<Error>, Box<Error>),
 }
 
 pub enum QueryError {
     NotSupported(String),
+    SqlTooLong(sql::execution_plan::SqlTooLong),
 }
 
 impl std::fmt::Display for QueryError {
@@ -195,6 +267,7 @@ impl std::fmt::Display for QueryError {
             QueryError::NotSupported(thing) => {
                 write!(f, "{} are not supported.", thing)
             }
+            QueryError::SqlTooLong(err) => write!(f, "{}", err),
         }
     }
 }
@@ -208,6 +281,9 @@ impl std::fmt::Display for Error {
             Error::DB(err) => {
                 write!(f, "{}", err)
             }
+            Error::PoolSaturated(err) => {
+                write!(f, "{}", err)
+            }
             Error::Multiple(err1, err2) => {
                 write!(f, "1. {}\n2. {}", err1, err2)
             }
@@ -220,3 +296,18 @@ impl From<sqlx::Error> for Error {
         Error::DB(err)
     }
 }
+
+impl From<pool::AcquireError> for Error {
+    fn from(err: pool::AcquireError) -> Error {
+        match err {
+            pool::AcquireError::Saturated(err) => Error::PoolSaturated(err),
+            pool::AcquireError::DB(err) => Error::DB(err),
+        }
+    }
+}
+
+impl From<sql::execution_plan::SqlTooLong> for Error {
+    fn from(err: sql::execution_plan::SqlTooLong) -> Error {
+        Error::Query(QueryError::SqlTooLong(err))
+    }
+}
@@ -0,0 +1,457 @@
+//! The database connection pool, along with our policies for acquiring connections from it.
+
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Connection, Executor, PgConnection, Postgres};
+use tokio::task::JoinHandle;
+use tracing::{info_span, Instrument};
+
+use crate::metrics;
+
+/// A connection pool for the database.
+///
+/// This dereferences to the underlying [sqlx::PgPool], but connections for running queries should
+/// be acquired through [ConnectionPool::acquire], which applies backpressure when the pool is
+/// saturated.
+#[derive(Debug, Clone)]
+pub struct ConnectionPool {
+    pool: sqlx::PgPool,
+    waiting_requests: WaitingRequests,
+    queue: Option<Queue>,
+    persistent_statements: bool,
+    idle_connection_checks: Option<Arc<BackgroundTask>>,
+}
+
+/// How urgently a request needs a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Wait behind the requests which arrived earlier.
+    Normal,
+    /// Skip the line, as health checks should answer quickly however busy the pool is. This
+    /// only makes a difference when acquisition is fair.
+    High,
+}
+
+impl ConnectionPool {
+    /// Wrap a pool. When `max_waiting_requests` is set, requests arriving while there are no idle
+    /// connections and that many requests are already waiting are rejected immediately, rather
+    /// than waiting for the pool timeout.
+    ///
+    /// With `fair_acquisition`, connections go to the waiting requests strictly in the order they
+    /// arrived, other than to those with a high priority.
+    ///
+    /// Unless `persistent_statements` is set, queries are not kept as prepared statements on
+    /// their connection once they have run.
+    pub fn new(
+        pool: sqlx::PgPool,
+        max_waiting_requests: Option<u32>,
+        fair_acquisition: bool,
+        persistent_statements: bool,
+    ) -> Self {
+        Self {
+            pool,
+            waiting_requests: WaitingRequests::new(max_waiting_requests),
+            queue: fair_acquisition.then(Queue::default),
+            persistent_statements,
+            idle_connection_checks: None,
+        }
+    }
+
+    /// Check the idle connections every `interval` in the background, with the validation query
+    /// if there is one and a ping otherwise, and close those which fail the check. The checks
+    /// stop when the pool is closed, or when every clone of it has been dropped.
+    pub fn check_idle_connections(
+        mut self,
+        interval: Duration,
+        validation_query: Option<String>,
+    ) -> Self {
+        let pool = self.pool.clone();
+        let task = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes straight away.
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                if pool.is_closed() {
+                    break;
+                }
+                check_idle_connections(&pool, validation_query.as_deref())
+                    .instrument(info_span!("Check idle connections"))
+                    .await;
+            }
+        });
+        self.idle_connection_checks = Some(Arc::new(BackgroundTask(task)));
+        self
+    }
+
+    /// Whether queries should be kept as prepared statements on their connection.
+    pub fn persistent_statements(&self) -> bool {
+        self.persistent_statements
+    }
+
+    /// The number of requests currently waiting to acquire a connection.
+    pub fn num_waiting(&self) -> u32 {
+        self.waiting_requests.count()
+    }
+
+    /// Acquire a connection from the pool, recording the relevant metrics.
+    pub async fn acquire(
+        &self,
+        metrics: &metrics::Metrics,
+    ) -> Result<PoolConnection<Postgres>, AcquireError> {
+        self.acquire_with_priority(metrics, Priority::Normal).await
+    }
+
+    /// Acquire a connection from the pool with the given priority, recording the relevant
+    /// metrics.
+    pub async fn acquire_with_priority(
+        &self,
+        metrics: &metrics::Metrics,
+        priority: Priority,
+    ) -> Result<PoolConnection<Postgres>, AcquireError> {
+        let waiting = self
+            .waiting_requests
+            .enter(self.pool.num_idle())
+            .ok_or_else(|| {
+                metrics
+                    .error_metrics
+                    .record_connection_acquisition_rejected();
+                AcquireError::Saturated(PoolSaturated {
+                    waiting_requests: self.waiting_requests.count(),
+                })
+            })?;
+
+        let acquisition_timer = metrics.time_connection_acquisition_wait();
+        let connection_result = self
+            .acquire_in_turn(priority)
+            .instrument(info_span!("Acquire connection"))
+            .await;
+        drop(waiting);
+
+        acquisition_timer
+            .complete_with(connection_result)
+            .map_err(|err| {
+                metrics.error_metrics.record_connection_acquisition_error();
+                AcquireError::DB(err)
+            })
+    }
+
+    /// Acquire a connection, after the requests which arrived earlier when acquisition is fair.
+    async fn acquire_in_turn(
+        &self,
+        priority: Priority,
+    ) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+        match &self.queue {
+            None => self.pool.acquire().await,
+            // The pool's own timeout only starts once a request is at the head of the line, so
+            // we limit the whole wait to it as well.
+            Some(queue) => tokio::time::timeout(
+                self.pool.options().get_acquire_timeout(),
+                queue.wait_in_line(priority, self.pool.acquire()),
+            )
+            .await
+            .unwrap_or(Err(sqlx::Error::PoolTimedOut)),
+        }
+    }
+
+    /// Open new connections with these options from now on, for instance because the
+    /// credentials have been rotated. Open connections are left alone, and are replaced once they
+    /// fail or expire.
+    pub fn set_connect_options(&self, connect_options: PgConnectOptions) {
+        self.pool.set_connect_options(connect_options);
+    }
+}
+
+/// Why a connection could not be acquired from the pool.
+#[derive(Debug)]
+pub enum AcquireError {
+    /// The request was rejected without waiting, as the pool was saturated.
+    Saturated(PoolSaturated),
+    /// The pool failed to provide a connection, for instance because it timed out waiting for
+    /// one or could not connect to the database.
+    DB(sqlx::Error),
+}
+
+impl std::fmt::Display for AcquireError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AcquireError::Saturated(err) => write!(f, "{}", err),
+            AcquireError::DB(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for AcquireError {}
+
+/// No connections were idle and `maxWaitingRequests` requests were waiting for one already.
+/// Unlike a pool timeout, this says nothing about the database, and the request can be retried
+/// once the load has eased.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSaturated {
+    /// The number of requests waiting for a connection when this one was rejected.
+    pub waiting_requests: u32,
+}
+
+impl std::fmt::Display for PoolSaturated {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "The connection pool is saturated, with {} requests waiting for a connection. Try again later.",
+            self.waiting_requests
+        )
+    }
+}
+
+/// Run the validation query of the pool settings on a connection. It runs in a read-only
+/// transaction, which is rolled back, so that it cannot change the database or the session. It is
+/// not kept as a prepared statement, so that it takes no room in the statement cache.
+pub async fn validate_connection(
+    connection: &mut PgConnection,
+    validation_query: &str,
+) -> Result<(), sqlx::Error> {
+    Executor::execute(&mut *connection, "BEGIN READ ONLY").await?;
+    let result = sqlx::query(validation_query)
+        .persistent(false)
+        .execute(&mut *connection)
+        .await;
+    Executor::execute(&mut *connection, "ROLLBACK").await?;
+    result.map(|_| ())
+}
+
+/// Check each of the idle connections of a pool once, and close those which fail the check.
+async fn check_idle_connections(pool: &sqlx::PgPool, validation_query: Option<&str>) {
+    // Idle connections are handed out in the order they were returned to the pool, so taking as
+    // many as there are, one at a time, goes through each of them.
+    for _ in 0..pool.num_idle() {
+        let Some(mut connection) = pool.try_acquire() else {
+            break;
+        };
+        let checked = match validation_query {
+            None => connection.ping().await,
+            Some(query) => validate_connection(connection.as_mut(), query).await,
+        };
+        if let Err(err) = checked {
+            tracing::warn!(error = %err, "Closing an idle connection which failed its check");
+            // The connection is gone either way, so there is nothing to do if closing it fails.
+            let _ = connection.close().await;
+        }
+    }
+}
+
+/// A task running in the background, which is aborted when this is dropped.
+#[derive(Debug)]
+struct BackgroundTask(JoinHandle<()>);
+
+impl Drop for BackgroundTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Whether the database refused the credentials we connected with, an error in the
+/// "invalid authorization specification" class.
+pub fn is_authentication_error(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => db_err.code().map_or(false, |code| code.starts_with("28")),
+        _ => false,
+    }
+}
+
+impl Deref for ConnectionPool {
+    type Target = sqlx::PgPool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pool
+    }
+}
+
+/// Keeps track of the requests waiting for a connection.
+#[derive(Debug, Clone)]
+struct WaitingRequests {
+    max: Option<u32>,
+    count: Arc<AtomicU32>,
+}
+
+impl WaitingRequests {
+    fn new(max: Option<u32>) -> Self {
+        Self {
+            max,
+            count: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    fn count(&self) -> u32 {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Register a request as waiting, unless it should be rejected because there are no idle
+    /// connections and too many requests are waiting already.
+    ///
+    /// The request stops counting as waiting when the returned guard is dropped.
+    fn enter(&self, num_idle: usize) -> Option<Waiting> {
+        let already_waiting = self.count.fetch_add(1, Ordering::SeqCst);
+        let waiting = Waiting(self.count.clone());
+        match self.max {
+            Some(max) if num_idle == 0 && already_waiting >= max => None,
+            _ => Some(waiting),
+        }
+    }
+}
+
+/// A request waiting for a connection.
+struct Waiting(Arc<AtomicU32>);
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The line of requests waiting for a connection, when acquisition is fair.
+///
+/// Only the request at the head of the line waits for the pool itself, so connections go to the
+/// requests in the order they joined the line, whichever order the pool would wake them up in.
+#[derive(Debug, Clone, Default)]
+struct Queue(Arc<tokio::sync::Mutex<()>>);
+
+impl Queue {
+    /// Wait for `acquire` once the requests ahead in line have had their turn, or straight away
+    /// with a high priority. Tokio's mutex hands out its lock in the order it was asked for.
+    async fn wait_in_line<T>(&self, priority: Priority, acquire: impl Future<Output = T>) -> T {
+        match priority {
+            Priority::Normal => {
+                let _turn = self.0.lock().await;
+                acquire.await
+            }
+            Priority::High => acquire.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn saturated_pool_rejects_new_requests() {
+        let waiting_requests = WaitingRequests::new(Some(2));
+
+        let first = waiting_requests.enter(0);
+        let second = waiting_requests.enter(0);
+        assert!(first.is_some());
+        assert!(second.is_some());
+
+        // No idle connections and two requests waiting already.
+        assert!(waiting_requests.enter(0).is_none());
+        assert_eq!(waiting_requests.count(), 2);
+
+        // Once a request stops waiting there is room again.
+        drop(first);
+        assert!(waiting_requests.enter(0).is_some());
+    }
+
+    #[test]
+    fn requests_are_not_rejected_when_connections_are_idle() {
+        let waiting_requests = WaitingRequests::new(Some(0));
+
+        assert!(waiting_requests.enter(1).is_some());
+        assert!(waiting_requests.enter(0).is_none());
+    }
+
+    #[test]
+    fn requests_are_never_rejected_without_a_limit() {
+        let waiting_requests = WaitingRequests::new(None);
+
+        let waiting: Vec<_> = (0..100).map(|_| waiting_requests.enter(0)).collect();
+        assert!(waiting.iter().all(Option::is_some));
+        assert_eq!(waiting_requests.count(), 100);
+    }
+
+    #[tokio::test]
+    async fn saturated_pool_fails_with_its_own_error() {
+        // The pool is never connected, so it has no idle connections.
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgresql://localhost/saturated")
+            .unwrap();
+        let pool = ConnectionPool::new(pool, Some(0), false, false);
+        let metrics = metrics::Metrics::initialize(&mut prometheus::Registry::new()).unwrap();
+
+        let result = pool.acquire(&metrics).await;
+
+        assert!(matches!(
+            result,
+            Err(AcquireError::Saturated(PoolSaturated {
+                waiting_requests: 0
+            }))
+        ));
+    }
+
+    /// Take one of the free connections, checking for one every millisecond, so that whichever
+    /// waiting request checks first when one is freed gets it.
+    async fn take_connection(free_connections: &AtomicU32) {
+        while free_connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |free| {
+                free.checked_sub(1)
+            })
+            .is_err()
+        {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn fair_queue_hands_out_connections_in_order() {
+        let queue = Queue::default();
+        let free_connections = Arc::new(AtomicU32::new(0));
+        let acquired = Arc::new(std::sync::Mutex::new(vec![]));
+
+        let requests = [
+            Priority::Normal,
+            Priority::Normal,
+            Priority::Normal,
+            Priority::Normal,
+            Priority::High,
+        ];
+        for (index, priority) in requests.into_iter().enumerate() {
+            let queue = queue.clone();
+            let free_connections = free_connections.clone();
+            let acquired = acquired.clone();
+            tokio::spawn(async move {
+                queue
+                    .wait_in_line(priority, take_connection(&free_connections))
+                    .await;
+                acquired.lock().unwrap().push(index);
+            });
+            // Let the request join the line before the next one arrives.
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // Free the connections one at a time.
+        for count in 1..=requests.len() {
+            free_connections.fetch_add(1, Ordering::SeqCst);
+            while acquired.lock().unwrap().len() < count {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+
+        let acquired = acquired.lock().unwrap().clone();
+        // The high priority request only competes with the request at the head of the line.
+        assert!(acquired.iter().position(|index| *index == 4) <= Some(1));
+        assert_eq!(
+            acquired
+                .into_iter()
+                .filter(|index| *index != 4)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+    }
+}
@@ -7,22 +7,29 @@ use serde_json;
 use sqlformat;
 use sqlx;
 use sqlx::pool::PoolConnection;
-use sqlx::{Postgres, Row};
+use sqlx::{Column, Executor, Postgres, Row};
 use tracing::{info_span, Instrument};
 
 use crate::database_info::DatabaseInfo;
 use crate::metrics;
+use crate::pool;
 use query_engine_sql::sql;
 
 /// Execute a query against postgres.
 pub async fn execute(
-    pool: &sqlx::PgPool,
+    pool: &pool::ConnectionPool,
     database_info: &DatabaseInfo,
     metrics: &metrics::Metrics,
-    plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
 ) -> Result<Bytes, Error> {
-    let plan = plan.query;
+    let sql::execution_plan::ExecutionPlan {
+        pre,
+        query: plan,
+        post,
+        max_sql_length,
+    } = plan;
     let query = plan.query_sql();
+    sql::execution_plan::check_sql_length(&query, *max_sql_length)?;
 
     tracing::info!(
         generated_sql = query.sql,
@@ -30,32 +37,69 @@ pub async fn execute(
         variables = ?&plan.variables,
     );
 
-    let acquisition_timer = metrics.time_connection_acquisition_wait();
-    let connection_result = pool
-        .acquire()
-        .instrument(info_span!("Acquire connection"))
-        .await;
-    let mut connection = acquisition_timer
-        .complete_with(connection_result)
-        .map_err(|err| {
-            metrics.error_metrics.record_connection_acquisition_error();
-            err
-        })?;
+    let mut connection = pool.acquire(metrics).await?;
 
     let query_timer = metrics.time_query_execution();
-    let rows_result = execute_query(&mut connection, database_info, query, plan.variables).await;
+    let persistent = pool.persistent_statements();
+    let in_transaction = !pre.is_empty();
+    let rows_result = async {
+        for statement in pre {
+            execute_statement(&mut connection, statement, persistent).await?;
+        }
+        for column_types in &plan.native_query_column_types {
+            check_native_query_column_types(&mut connection, column_types).await?;
+        }
+        let result = execute_query(
+            &mut connection,
+            database_info,
+            query,
+            plan.variables.as_deref(),
+            persistent,
+        )
+        .await?;
+        for statement in post {
+            execute_statement(&mut connection, statement, persistent).await?;
+        }
+        Ok::<_, Error>(result)
+    }
+    .await;
+    // Don't hand a connection with an aborted transaction back to the pool.
+    let rows_result = match rows_result {
+        Err(err) if in_transaction => {
+            if let Err(rollback_err) = execute_statement(
+                &mut connection,
+                &sql::helpers::transaction_rollback(),
+                persistent,
+            )
+            .await
+            {
+                tracing::error!(
+                    "failed to roll back the query transaction: {}",
+                    rollback_err
+                );
+            }
+            Err(err)
+        }
+        result => result,
+    };
     query_timer.complete_with(rows_result)
 }
 
 /// Convert a query to an EXPLAIN query and execute it against postgres.
+///
+/// With the JSON format, the plan is returned as a tree of the planned nodes with their estimated
+/// costs and rows, rather than as the database's text.
 pub async fn explain(
-    pool: &sqlx::PgPool,
+    pool: &pool::ConnectionPool,
     database_info: &DatabaseInfo,
     metrics: &metrics::Metrics,
     plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+    format: sql::ast::ExplainFormat,
 ) -> Result<(String, String), Error> {
+    let max_sql_length = plan.max_sql_length;
     let plan = plan.query;
-    let query = plan.explain_query_sql();
+    let query = plan.explain_query_sql(format);
+    sql::execution_plan::check_sql_length(&query, max_sql_length)?;
 
     tracing::info!(
         generated_sql = query.sql,
@@ -63,22 +107,16 @@ pub async fn explain(
         variables = ?&plan.variables,
     );
 
-    let sqlx_query = build_query_with_params(&query, plan.variables)
-        .instrument(info_span!("Build query with params"))
-        .await?;
+    let sqlx_query = build_query_with_params(
+        &query,
+        plan.variables.as_deref(),
+        pool.persistent_statements(),
+    )
+    .instrument(info_span!("Build query with params"))
+    .await?;
 
     let rows: Vec<sqlx::postgres::PgRow> = {
-        let acquisition_timer = metrics.time_connection_acquisition_wait();
-        let connection_result = pool
-            .acquire()
-            .instrument(info_span!("Acquire connection"))
-            .await;
-        let mut connection = acquisition_timer
-            .complete_with(connection_result)
-            .map_err(|err| {
-                metrics.error_metrics.record_connection_acquisition_error();
-                err
-            })?;
+        let mut connection = pool.acquire(metrics).await?;
 
         // run and fetch from the database
         sqlx_query
@@ -97,15 +135,33 @@ pub async fn explain(
             .await?
     };
 
-    let mut results: Vec<String> = vec![];
-    for row in rows.into_iter() {
-        match row.get(0) {
-            None => {}
-            Some(col) => {
-                results.push(col);
+    let plan = match format {
+        sql::ast::ExplainFormat::Text => {
+            let mut results: Vec<String> = vec![];
+            for row in rows.into_iter() {
+                match row.get(0) {
+                    None => {}
+                    Some(col) => {
+                        results.push(col);
+                    }
+                }
             }
+            results.join("\n")
         }
-    }
+        sql::ast::ExplainFormat::Json => {
+            // The plan is a single row holding a list with one entry per statement.
+            let plans: Vec<serde_json::Value> = rows
+                .iter()
+                .filter_map(|row| row.get::<Option<serde_json::Value>, _>(0))
+                .flat_map(|output| match output {
+                    serde_json::Value::Array(statements) => statements,
+                    other => vec![other],
+                })
+                .map(|statement| structured_plan(&statement["Plan"]))
+                .collect();
+            serde_json::to_string_pretty(&plans).unwrap_or_default()
+        }
+    };
 
     let pretty = sqlformat::format(
         &query.sql,
@@ -113,7 +169,103 @@ pub async fn explain(
         sqlformat::FormatOptions::default(),
     );
 
-    Ok((pretty, results.join("\n")))
+    Ok((pretty, plan))
+}
+
+/// Keep the node type, estimated costs and rows of each node of a plan output by
+/// `EXPLAIN (FORMAT JSON)`.
+fn structured_plan(node: &serde_json::Value) -> serde_json::Value {
+    let children: Vec<serde_json::Value> = match &node["Plans"] {
+        serde_json::Value::Array(children) => children.iter().map(structured_plan).collect(),
+        _ => vec![],
+    };
+    serde_json::json!({
+        "nodeType": node["Node Type"],
+        "relationName": node["Relation Name"],
+        "startupCost": node["Startup Cost"],
+        "totalCost": node["Total Cost"],
+        "planRows": node["Plan Rows"],
+        "plans": children,
+    })
+}
+
+/// Execute a sql statement against the database.
+pub(crate) async fn execute_statement(
+    connection: &mut PoolConnection<Postgres>,
+    sql::string::Statement(statement): &sql::string::Statement,
+    persistent: bool,
+) -> Result<(), sqlx::Error> {
+    if persistent {
+        sqlx::query(&statement.sql)
+            .execute(connection.as_mut())
+            .await?;
+    } else {
+        // The statement has no parameters, so it can be run with the simple query protocol,
+        // without preparing it at all.
+        connection.as_mut().execute(statement.sql.as_str()).await?;
+    }
+    Ok(())
+}
+
+/// Check that the columns a native query returns have the types it declares them with, as the
+/// values of columns of other types are returned as they are.
+///
+/// The native query is described rather than run, and the database then compares the types, as
+/// we only know the actual types by their oids and the declared ones by name.
+async fn check_native_query_column_types(
+    connection: &mut PoolConnection<Postgres>,
+    column_types: &sql::execution_plan::NativeQueryColumnTypes,
+) -> Result<(), Error> {
+    let query = column_types.query_sql();
+    let description = connection.as_mut().describe(query.sql.as_str()).await?;
+
+    let mut declared_types = vec![];
+    let mut actual_types = vec![];
+    for (column_name, declared_type) in &column_types.columns {
+        let column = description
+            .columns()
+            .iter()
+            .find(|column| column.name() == column_name)
+            .ok_or_else(|| {
+                Error::Query(QueryError::NativeQueryColumnTypeMismatch {
+                    native_query: column_types.name.clone(),
+                    column: column_name.clone(),
+                    declared_type: declared_type.clone(),
+                    actual_type: None,
+                })
+            })?;
+        declared_types.push(declared_type.clone());
+        actual_types.push(
+            column
+                .type_info()
+                .oid()
+                .map_or(0, |sqlx::postgres::types::Oid(oid)| i64::from(oid)),
+        );
+    }
+
+    let comparisons: Vec<(String, bool)> = sqlx::query_as(
+        "SELECT actual::oid::regtype::text, coalesce(to_regtype(declared) = actual::oid::regtype, false)
+        FROM unnest($1::text[], $2::int8[]) WITH ORDINALITY AS columns(declared, actual, position)
+        ORDER BY position",
+    )
+    .bind(&declared_types)
+    .bind(&actual_types)
+    .fetch_all(connection.as_mut())
+    .await?;
+
+    for ((column_name, declared_type), (actual_type, matches)) in
+        column_types.columns.iter().zip(comparisons)
+    {
+        if !matches {
+            return Err(Error::Query(QueryError::NativeQueryColumnTypeMismatch {
+                native_query: column_types.name.clone(),
+                column: column_name.clone(),
+                declared_type: declared_type.clone(),
+                actual_type: Some(actual_type),
+            }));
+        }
+    }
+    Ok(())
 }
 
 /// Execute the query and return the result as bytes.
@@ -121,12 +273,13 @@ async fn execute_query(
     connection: &mut PoolConnection<Postgres>,
     database_info: &DatabaseInfo,
     query: sql::string::SQL,
-    variables: Option<Vec<BTreeMap<String, serde_json::Value>>>,
+    variables: Option<&[BTreeMap<String, serde_json::Value>]>,
+    persistent: bool,
 ) -> Result<Bytes, Error> {
     let mut buffer = BytesMut::new();
 
     // build query
-    let sqlx_query = build_query_with_params(&query, variables)
+    let sqlx_query = build_query_with_params(&query, variables, persistent)
         .instrument(info_span!("Build query with params"))
         .await?;
 
@@ -160,11 +313,14 @@ async fn execute_query(
 }
 
 /// Create a SQLx query based on our SQL query and bind our parameters and variables to it.
-async fn build_query_with_params(
-    query: &sql::string::SQL,
-    variables: Option<Vec<BTreeMap<String, serde_json::Value>>>,
-) -> Result<sqlx::query::Query<'_, sqlx::Postgres, sqlx::postgres::PgArguments>, Error> {
-    let sqlx_query = sqlx::query(query.sql.as_str());
+///
+/// Unless `persistent` is set, the query is not kept as a prepared statement once it has run.
+async fn build_query_with_params<'a>(
+    query: &'a sql::string::SQL,
+    variables: Option<&[BTreeMap<String, serde_json::Value>]>,
+    persistent: bool,
+) -> Result<sqlx::query::Query<'a, sqlx::Postgres, sqlx::postgres::PgArguments>, Error> {
+    let sqlx_query = sqlx::query(query.sql.as_str()).persistent(persistent);
 
     let sqlx_query = query
         .params
@@ -174,7 +330,7 @@ async fn build_query_with_params(
             sql::string::Param::Variable(var)
                 if var == sql::helpers::VARIABLES_OBJECT_PLACEHOLDER =>
             {
-                match &variables {
+                match variables {
                     None => Err(Error::Query(QueryError::VariableNotFound(var.to_string()))),
                     Some(variables) => {
                         let vars = variables_to_json(variables)?;
@@ -218,15 +374,35 @@ fn variables_to_json(
     ))
 }
 
+/// Whether the query failed because it conflicted with a concurrent transaction, with a
+/// serialization failure or a deadlock. Running it again may well succeed.
+pub fn is_transaction_conflict(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => db_err
+            .code()
+            .map_or(false, |code| code == "40001" || code == "40P01"),
+        _ => false,
+    }
+}
+
 pub enum Error {
     Query(QueryError),
     DB(sqlx::Error),
+    /// No connection was free to run the query on, and too many requests were waiting for one.
+    PoolSaturated(pool::PoolSaturated),
 }
 
 pub enum QueryError {
     ReservedVariableName(String),
     VariableNotFound(String),
     NotSupported(String),
+    SqlTooLong(sql::execution_plan::SqlTooLong),
+    NativeQueryColumnTypeMismatch {
+        native_query: String,
+        column: String,
+        declared_type: String,
+        actual_type: Option<String>,
+    },
 }
 
 impl std::fmt::Display for QueryError {
@@ -245,6 +421,31 @@ impl std::fmt::Display for QueryError {
             QueryError::NotSupported(thing) => {
                 write!(f, "{} are not supported.", thing)
             }
+            QueryError::SqlTooLong(err) => write!(f, "{}", err),
+            QueryError::NativeQueryColumnTypeMismatch {
+                native_query,
+                column,
+                declared_type,
+                actual_type: None,
+            } => {
+                write!(
+                    f,
+                    "Column '{}' of native query '{}' is declared with type '{}', but the native query does not return it.",
+                    column, native_query, declared_type
+                )
+            }
+            QueryError::NativeQueryColumnTypeMismatch {
+                native_query,
+                column,
+                declared_type,
+                actual_type: Some(actual_type),
+            } => {
+                write!(
+                    f,
+                    "Column '{}' of native query '{}' is declared with type '{}', but is of type '{}'.",
+                    column, native_query, declared_type, actual_type
+                )
+            }
         }
     }
 }
@@ -254,3 +455,68 @@ impl From<sqlx::Error> for Error {
         Error::DB(err)
     }
 }
+
+impl From<pool::AcquireError> for Error {
+    fn from(err: pool::AcquireError) -> Error {
+        match err {
+            pool::AcquireError::Saturated(err) => Error::PoolSaturated(err),
+            pool::AcquireError::DB(err) => Error::DB(err),
+        }
+    }
+}
+
+impl From<sql::execution_plan::SqlTooLong> for Error {
+    fn from(err: sql::execution_plan::SqlTooLong) -> Error {
+        Error::Query(QueryError::SqlTooLong(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structured_plans_keep_node_types_costs_and_rows() {
+        let plan = serde_json::json!({
+            "Node Type": "Limit",
+            "Parallel Aware": false,
+            "Startup Cost": 0.0,
+            "Total Cost": 0.1,
+            "Plan Rows": 5,
+            "Plan Width": 32,
+            "Plans": [
+                {
+                    "Node Type": "Seq Scan",
+                    "Parent Relationship": "Outer",
+                    "Relation Name": "Album",
+                    "Alias": "Album",
+                    "Startup Cost": 0.0,
+                    "Total Cost": 6.47,
+                    "Plan Rows": 347,
+                    "Plan Width": 32
+                }
+            ]
+        });
+
+        assert_eq!(
+            structured_plan(&plan),
+            serde_json::json!({
+                "nodeType": "Limit",
+                "relationName": null,
+                "startupCost": 0.0,
+                "totalCost": 0.1,
+                "planRows": 5,
+                "plans": [
+                    {
+                        "nodeType": "Seq Scan",
+                        "relationName": "Album",
+                        "startupCost": 0.0,
+                        "totalCost": 6.47,
+                        "planRows": 347,
+                        "plans": []
+                    }
+                ]
+            })
+        );
+    }
+}
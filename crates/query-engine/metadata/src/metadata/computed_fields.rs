@@ -0,0 +1,417 @@
+//! Metadata information regarding the fields of tables which are computed from their columns.
+
+use super::database::*;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The keywords a computed field's expression may use.
+pub const ALLOWED_KEYWORDS: &[&str] = &[
+    "and",
+    "between",
+    "case",
+    "current_date",
+    "current_timestamp",
+    "distinct",
+    "else",
+    "end",
+    "false",
+    "from",
+    "ilike",
+    "in",
+    "interval",
+    "is",
+    "like",
+    "not",
+    "null",
+    "or",
+    "then",
+    "true",
+    "when",
+];
+
+/// The functions a computed field's expression may call.
+pub const ALLOWED_FUNCTIONS: &[&str] = &[
+    "abs",
+    "ceil",
+    "coalesce",
+    "date_trunc",
+    "floor",
+    "greatest",
+    "least",
+    "length",
+    "lower",
+    "now",
+    "nullif",
+    "round",
+    "trim",
+    "upper",
+];
+
+/// The operators a computed field's expression may use.
+pub const ALLOWED_OPERATORS: &[&str] = &[
+    "=", "<>", "!=", "<", "<=", ">", ">=", "+", "-", "*", "/", "%", "||",
+];
+
+/// The fields of a table computed from its columns, by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedFields(pub BTreeMap<String, ComputedField>);
+
+impl ComputedFields {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A field computed from the columns of its table, such as an `is_overdue` field computed by
+/// `CASE WHEN "due_date" < current_date THEN true ELSE false END`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedField {
+    /// The SQL expression computing the field. It may only refer to the columns of its table, by
+    /// their names in the database, and use a small set of keywords, operators and functions.
+    pub expression: String,
+    pub r#type: ScalarType,
+    #[serde(default)]
+    pub nullable: Nullable,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// The fields of a table ranking its rows, by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingFields(pub BTreeMap<String, RankingField>);
+
+impl RankingFields {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A field ranking the rows of its table within partitions of them with a window function, such
+/// as the position of an album among those of its artist, computed by
+/// `row_number() OVER (PARTITION BY "ArtistId" ORDER BY "Title")`. Its values are 'int8's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingField {
+    pub function: RankingFunction,
+    /// The columns partitioning the rows, by their names in the database. The rows are ranked all
+    /// together when there are none.
+    #[serde(default)]
+    pub partition_by: Vec<String>,
+    /// The columns ordering the rows of each partition, by their names in the database.
+    pub order_by: Vec<RankingOrderBy>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// The window functions ranking rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RankingFunction {
+    /// The position of the row in its partition, from 1, without ties.
+    RowNumber,
+    /// The position of the first row of those tied with the row, leaving gaps after ties.
+    Rank,
+    /// The number of distinct values before the row's, plus 1, leaving no gaps after ties.
+    DenseRank,
+}
+
+impl RankingFunction {
+    /// The name of the function in the database.
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            RankingFunction::RowNumber => "row_number",
+            RankingFunction::Rank => "rank",
+            RankingFunction::DenseRank => "dense_rank",
+        }
+    }
+}
+
+/// A column ordering the rows a ranking field ranks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RankingOrderBy {
+    pub column: String,
+    #[serde(default)]
+    pub direction: RankingOrderDirection,
+}
+
+/// The direction rows are ranked in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RankingOrderDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// The fields of a table ranking its rows by their relevance to a text search, by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchRankFields(pub BTreeMap<String, SearchRankField>);
+
+impl SearchRankFields {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A field ranking the rows of its table by their relevance to a text search given as a
+/// collection argument, such as the relevance of a document computed by
+/// `ts_rank("search_vector", websearch_to_tsquery($1))`. Its values are 'float4's, and null when
+/// the argument is null or not given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchRankField {
+    pub function: SearchRankFunction,
+    /// The 'tsvector' column searched, by its name in the database.
+    pub column: String,
+    /// The collection argument holding the search, in the syntax of 'websearch_to_tsquery'.
+    pub argument: String,
+    /// The weights of the labels of the lexemes. The database weighs them 'a': 100, 'b': 40,
+    /// 'c': 20 and 'd': 10 by default.
+    #[serde(default)]
+    pub weights: Option<SearchWeights>,
+    /// How the rank is normalized by the length of the document, as the bit mask the ranking
+    /// functions take. 0, the default, ignores the length.
+    #[serde(default)]
+    pub normalization: u32,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// The functions ranking rows by their relevance to a text search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchRankFunction {
+    /// Rank by the frequency of the matching lexemes.
+    TsRank,
+    /// Rank by the cover density of the matching lexemes, which also accounts for how close
+    /// together they are.
+    TsRankCd,
+}
+
+impl SearchRankFunction {
+    /// The name of the function in the database.
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            SearchRankFunction::TsRank => "ts_rank",
+            SearchRankFunction::TsRankCd => "ts_rank_cd",
+        }
+    }
+}
+
+/// The weights of the labels of lexemes, as percentages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchWeights {
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+}
+
+impl SearchWeights {
+    /// The weights as the array the ranking functions take, which lists them from 'd' to 'a'.
+    pub fn to_array_literal(&self) -> String {
+        let weight = |percentage: u8| format!("{}", f64::from(percentage) / 100.0);
+        format!(
+            "{{{},{},{},{}}}",
+            weight(self.d),
+            weight(self.c),
+            weight(self.b),
+            weight(self.a)
+        )
+    }
+}
+
+/// A part of a computed field's expression, either text or a reference to a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComputedFieldPart {
+    Text(String),
+    /// The name of a column. Unquoted names are folded to lower case, as the database does.
+    Column(String),
+}
+
+impl ComputedField {
+    /// Split the expression into the columns it refers to and the text around them, checking
+    /// that it only uses the allowed keywords, operators and functions. As the expression is
+    /// spliced into queries, anything else is rejected, including comments, casts, subqueries
+    /// and unbalanced parentheses.
+    pub fn parts(&self) -> Result<Vec<ComputedFieldPart>, String> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut depth: u32 = 0;
+        let mut rest = self.expression.as_str();
+        while let Some(c) = rest.chars().next() {
+            if c.is_whitespace() {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            } else if let Some(string) = rest.strip_prefix('\'') {
+                let end = string_literal_end(string)
+                    .ok_or_else(|| "unterminated string literal".to_string())?;
+                text.push_str(&rest[..end + 2]);
+                rest = &string[end + 1..];
+            } else if let Some(identifier) = rest.strip_prefix('"') {
+                let end = identifier
+                    .find('"')
+                    .ok_or_else(|| "unterminated quoted identifier".to_string())?;
+                if identifier[end + 1..].starts_with('"') {
+                    return Err("quoted identifiers may not contain quotes".to_string());
+                }
+                parts.push(ComputedFieldPart::Text(std::mem::take(&mut text)));
+                parts.push(ComputedFieldPart::Column(identifier[..end].to_string()));
+                rest = &identifier[end + 1..];
+            } else if c.is_alphabetic() || c == '_' {
+                let end = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let (word, after) = rest.split_at(end);
+                let lower_case = word.to_lowercase();
+                if after.starts_with(['\'', '"', '$']) {
+                    // Such as the prefix of an escape string, E'...', which reads differently.
+                    return Err(format!("'{word}' may not be followed by '{}'", &after[..1]));
+                } else if after.trim_start().starts_with('(') {
+                    if !ALLOWED_FUNCTIONS.contains(&lower_case.as_str())
+                        && !ALLOWED_KEYWORDS.contains(&lower_case.as_str())
+                    {
+                        return Err(format!("the function '{word}' is not allowed"));
+                    }
+                    text.push_str(word);
+                } else if ALLOWED_KEYWORDS.contains(&lower_case.as_str()) {
+                    text.push_str(word);
+                } else {
+                    parts.push(ComputedFieldPart::Text(std::mem::take(&mut text)));
+                    parts.push(ComputedFieldPart::Column(lower_case));
+                }
+                rest = after;
+            } else if c.is_ascii_digit() {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(rest.len());
+                text.push_str(&rest[..end]);
+                rest = &rest[end..];
+            } else if c == '(' || c == ')' || c == ',' {
+                match c {
+                    '(' => depth += 1,
+                    ')' => {
+                        depth = depth
+                            .checked_sub(1)
+                            .ok_or_else(|| "unbalanced parentheses".to_string())?
+                    }
+                    // Commas only separate the arguments of a function or the values of IN.
+                    _ if depth == 0 => return Err("',' is not allowed here".to_string()),
+                    _ => {}
+                }
+                text.push(c);
+                rest = &rest[1..];
+            } else if OPERATOR_CHARACTERS.contains(c) {
+                let end = rest
+                    .find(|c: char| !OPERATOR_CHARACTERS.contains(c))
+                    .unwrap_or(rest.len());
+                let operator = &rest[..end];
+                if !ALLOWED_OPERATORS.contains(&operator) {
+                    return Err(format!("the operator '{operator}' is not allowed"));
+                }
+                text.push_str(operator);
+                rest = &rest[end..];
+            } else {
+                return Err(format!("'{c}' is not allowed"));
+            }
+        }
+        if depth > 0 {
+            return Err("unbalanced parentheses".to_string());
+        }
+        parts.push(ComputedFieldPart::Text(text));
+        parts.retain(|part| *part != ComputedFieldPart::Text(String::new()));
+        Ok(parts)
+    }
+}
+
+/// The characters operators are made of, which we read greedily as the database does, so that
+/// `--` and `/*` are read as the comments they start.
+const OPERATOR_CHARACTERS: &str = "+-*/<>=~!@#%^&|`?";
+
+/// The position of the quote closing a string literal, given the text after the opening quote.
+fn string_literal_end(string: &str) -> Option<usize> {
+    let mut position = 0;
+    loop {
+        let end = position + string[position..].find('\'')?;
+        // Two quotes stand for one in the string.
+        if string[end + 1..].starts_with('\'') {
+            position = end + 2;
+        } else {
+            return Some(end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(expression: &str) -> Result<Vec<ComputedFieldPart>, String> {
+        ComputedField {
+            expression: expression.to_string(),
+            r#type: ScalarType("bool".to_string()),
+            nullable: Nullable::Nullable,
+            description: None,
+        }
+        .parts()
+    }
+
+    #[test]
+    fn columns_are_split_out_of_the_expression() {
+        assert_eq!(
+            parts("CASE WHEN \"DueDate\" < current_date AND paid_at IS NULL THEN true ELSE 'no''' END"),
+            Ok(vec![
+                ComputedFieldPart::Text("CASE WHEN ".to_string()),
+                ComputedFieldPart::Column("DueDate".to_string()),
+                ComputedFieldPart::Text(" < current_date AND ".to_string()),
+                ComputedFieldPart::Column("paid_at".to_string()),
+                ComputedFieldPart::Text(" IS NULL THEN true ELSE 'no''' END".to_string()),
+            ])
+        );
+        assert_eq!(
+            parts("coalesce(Total, 0) * 1.5"),
+            Ok(vec![
+                ComputedFieldPart::Text("coalesce(".to_string()),
+                ComputedFieldPart::Column("total".to_string()),
+                ComputedFieldPart::Text(", 0) * 1.5".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn search_weights_are_listed_from_d_to_a() {
+        let weights = SearchWeights {
+            a: 100,
+            b: 40,
+            c: 20,
+            d: 5,
+        };
+        assert_eq!(weights.to_array_literal(), "{0.05,0.2,0.4,1}");
+    }
+
+    #[test]
+    fn only_allowed_syntax_is_accepted() {
+        for expression in [
+            "total; DROP TABLE invoice",
+            "total -- comment",
+            "total /* comment */",
+            "total::text",
+            "pg_sleep(10)",
+            "total), (other",
+            "total, other",
+            "E'\\''",
+            "$$text$$",
+            "'unterminated",
+        ] {
+            assert!(parts(expression).is_err(), "{expression} was accepted");
+        }
+    }
+}
@@ -1,5 +1,7 @@
 //! Metadata information regarding the database and tracked information.
 
+use super::computed_fields::{ComputedFields, RankingFields, SearchRankFields};
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
@@ -9,13 +11,15 @@ use std::collections::{BTreeMap, BTreeSet};
 #[serde(rename_all = "camelCase")]
 pub struct ScalarType(pub String);
 
-/// The type of values that a column, field, or argument may take. These are either arrays or base
-/// scalar types.
+/// The type of values that a column, field, or argument may take. These are either arrays, base
+/// scalar types, or the composite types of nested values.
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Type {
     ArrayType(Box<Type>),
     ScalarType(ScalarType),
+    /// A nested object of one of the composite types, represented as `jsonb` in the database.
+    CompositeType(String),
 }
 
 /// The complete list of supported binary operators for scalar types.
@@ -30,6 +34,62 @@ pub struct ComparisonOperators(pub BTreeMap<ScalarType, BTreeMap<String, Compari
 pub struct ComparisonOperator {
     pub operator_name: String,
     pub argument_type: ScalarType,
+    /// The collation to compare with, instead of that of the column.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+}
+
+impl ComparisonOperators {
+    /// The quantified comparison operators of a scalar type, by name, along with the operator
+    /// each quantifies and how. Each of the operators of the type which can be quantified is
+    /// quantified with both `ANY` and `ALL`, so '_gt' gives '_gt_any' and '_gt_all', unless the
+    /// type has an operator of that name already.
+    pub fn quantified(
+        &self,
+        scalar_type: &ScalarType,
+    ) -> BTreeMap<String, (Quantifier, &ComparisonOperator)> {
+        let Some(operators) = self.0.get(scalar_type) else {
+            return BTreeMap::new();
+        };
+        operators
+            .iter()
+            .filter(|(_, operator)| {
+                QUANTIFIABLE_OPERATORS.contains(&operator.operator_name.as_str())
+            })
+            .flat_map(|(name, operator)| {
+                [Quantifier::Any, Quantifier::All].map(|quantifier| {
+                    (
+                        format!("{name}_{}", quantifier.suffix()),
+                        (quantifier, operator),
+                    )
+                })
+            })
+            .filter(|(name, _)| !operators.contains_key(name))
+            .collect()
+    }
+}
+
+/// The operators which can compare a value with each element of an array, using `ANY` or `ALL`.
+const QUANTIFIABLE_OPERATORS: &[&str] = &["=", "<>", "!=", "<", "<=", ">", ">="];
+
+/// How a quantified comparison operator combines the comparisons with each element of its array
+/// argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantifier {
+    /// The comparison holds for some element, so never for an empty array.
+    Any,
+    /// The comparison holds for every element, so always for an empty array.
+    All,
+}
+
+impl Quantifier {
+    /// The suffix of the names of operators quantified this way.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Quantifier::Any => "any",
+            Quantifier::All => "all",
+        }
+    }
 }
 
 /// Mapping from a "table" name to its information.
@@ -44,12 +104,41 @@ pub struct TableInfo {
     pub schema_name: String,
     pub table_name: String,
     pub columns: BTreeMap<String, ColumnInfo>,
+    /// The fields computed from the columns, which are selected like them.
+    #[serde(default, skip_serializing_if = "ComputedFields::is_empty")]
+    pub computed_fields: ComputedFields,
+    /// The fields ranking the rows with window functions, which are selected like columns.
+    #[serde(default, skip_serializing_if = "RankingFields::is_empty")]
+    pub ranking_fields: RankingFields,
+    /// The fields ranking the rows by their relevance to a text search, which are selected and
+    /// ordered by like columns.
+    #[serde(default, skip_serializing_if = "SearchRankFields::is_empty")]
+    pub search_rank_fields: SearchRankFields,
     #[serde(default)]
     pub uniqueness_constraints: UniquenessConstraints,
     #[serde(default)]
     pub foreign_relations: ForeignRelations,
+    /// The partial indexes on this table.
+    #[serde(default, skip_serializing_if = "Indexes::is_empty")]
+    pub indexes: Indexes,
     #[serde(default)]
     pub description: Option<String>,
+    /// The table access method, such as the default 'heap' or 'columnar'. Views and foreign tables
+    /// have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_method: Option<String>,
+    /// The foreign server this table's rows are fetched from, if it is a foreign table (e.g. one
+    /// created with 'postgres_fdw'). Foreign tables are exposed as read-only collections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreign_server: Option<String>,
+    /// The row-level security of this table, if it is enabled. This is informational only, as
+    /// the database enforces the policies itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rls: Option<RlsInfo>,
+    /// The triggers on this table, other than those the database creates to enforce constraints.
+    /// This is informational only, to help explain the side effects of mutations.
+    #[serde(default, skip_serializing_if = "Triggers::is_empty")]
+    pub triggers: Triggers,
 }
 
 /// Can this column contain null values
@@ -71,6 +160,54 @@ pub struct ColumnInfo {
     pub nullable: Nullable,
     #[serde(default)]
     pub description: Option<String>,
+    /// Why the column is deprecated, when its comment has a line starting with '@deprecated',
+    /// such as '@deprecated: use "Title"'. The reason is empty when none is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// The values this column is restricted to, if it has a check constraint of the form
+    /// 'column IN (value, ...)'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_values: Option<Vec<String>>,
+    /// The collation of the column, if it is not the default one for its type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+    /// The declared maximum length of a 'varchar(n)' or 'char(n)' column, or of the elements of
+    /// an array of those.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u32>,
+    /// The last value handed out by the sequence generating the values of a serial or identity
+    /// column, as an estimate of its largest value. It is only as recent as the introspection,
+    /// and values of rolled back inserts are never used, so it is approximate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence_last_value: Option<i64>,
+    /// Whether the column has a default value, which inserts leaving the column out use.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub has_default: bool,
+    /// Whether the column is an identity column, and whether inserts may give it a value.
+    #[serde(default, skip_serializing_if = "IsIdentity::is_not_identity")]
+    pub is_identity: IsIdentity,
+    /// Whether the column is a generated column, whose values are computed from the other columns
+    /// of its row and cannot be inserted.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_generated: bool,
+}
+
+/// Whether a column is an identity column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum IsIdentity {
+    #[default]
+    NotIdentity,
+    /// 'GENERATED BY DEFAULT AS IDENTITY': inserts may give the column a value.
+    IdentityByDefault,
+    /// 'GENERATED ALWAYS AS IDENTITY': inserts may not give the column a value.
+    IdentityAlways,
+}
+
+impl IsIdentity {
+    pub fn is_not_identity(&self) -> bool {
+        *self == IsIdentity::NotIdentity
+    }
 }
 
 /// A mapping from the name of a unique constraint to its value.
@@ -98,6 +235,89 @@ pub struct ForeignRelation {
     pub column_mapping: BTreeMap<String, String>,
 }
 
+/// A mapping from the name of an index to its information.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Indexes(pub BTreeMap<String, IndexInfo>);
+
+impl Indexes {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Information about a partial index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexInfo {
+    /// The predicate of the index, as a SQL expression.
+    pub predicate: String,
+}
+
+/// The row-level security policies of a table, by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RlsInfo {
+    #[serde(default)]
+    pub policies: BTreeMap<String, RlsPolicy>,
+}
+
+/// A row-level security policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RlsPolicy {
+    pub command: RlsPolicyCommand,
+}
+
+/// The commands a row-level security policy applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RlsPolicyCommand {
+    All,
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A mapping from the name of a trigger to its information.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Triggers(pub BTreeMap<String, TriggerInfo>);
+
+impl Triggers {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// When a trigger fires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerInfo {
+    pub timing: TriggerTiming,
+    pub events: Vec<TriggerEvent>,
+}
+
+/// Whether a trigger fires before, after or instead of the events it is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TriggerTiming {
+    Before,
+    After,
+    InsteadOf,
+}
+
+/// An event a trigger fires on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TriggerEvent {
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+}
+
 /// All supported aggregate functions, grouped by type.
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -108,3 +328,78 @@ pub struct AggregateFunctions(pub BTreeMap<ScalarType, BTreeMap<String, Aggregat
 pub struct AggregateFunction {
     pub return_type: ScalarType,
 }
+
+impl AggregateFunctions {
+    /// The type an aggregate function of a scalar type returns: that of the function found
+    /// during introspection, or else that of the standard aggregate of that name.
+    pub fn return_type(&self, scalar_type: &ScalarType, name: &str) -> Option<ScalarType> {
+        match self
+            .0
+            .get(scalar_type)
+            .and_then(|functions| functions.get(name))
+        {
+            Some(function) => Some(function.return_type.clone()),
+            None => standard_aggregate_functions(scalar_type)
+                .into_iter()
+                .find(|(function_name, _)| *function_name == name)
+                .map(|(_, return_type)| ScalarType(return_type.to_string())),
+        }
+    }
+}
+
+/// The standard numeric and boolean aggregates and their result types, as defined by PostgreSQL.
+///
+/// Note that `sum` over integers returns a wider type than its input, so that summing many large
+/// values does not overflow. `bool_and` and `bool_or` ignore nulls, and so return null when all
+/// the values are null, or when there are none.
+pub fn standard_aggregate_functions(scalar_type: &ScalarType) -> Vec<(&'static str, &'static str)> {
+    if scalar_type.0 == "bool" {
+        return vec![("bool_and", "bool"), ("bool_or", "bool")];
+    }
+    // (type of min/max, type of sum, type of avg)
+    let (same, sum, avg) = match scalar_type.0.as_str() {
+        "int2" => ("int2", "int8", "numeric"),
+        "int4" => ("int4", "int8", "numeric"),
+        "int8" => ("int8", "numeric", "numeric"),
+        "float4" => ("float4", "float4", "float8"),
+        "float8" => ("float8", "float8", "float8"),
+        "numeric" => ("numeric", "numeric", "numeric"),
+        _ => return vec![],
+    };
+    vec![("sum", sum), ("avg", avg), ("min", same), ("max", same)]
+}
+
+/// The extensions installed in the database, by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Extensions(pub BTreeMap<String, ExtensionInfo>);
+
+impl Extensions {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether the extension with this name is installed.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+}
+
+/// The labels of enum types, by type name, in the order they sort in.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumTypes(pub BTreeMap<ScalarType, Vec<String>>);
+
+impl EnumTypes {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Information about an installed extension.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionInfo {
+    /// The schema the objects of the extension are created in.
+    pub schema_name: String,
+}
@@ -0,0 +1,31 @@
+//! Metadata information regarding collections of rows given in requests.
+
+use super::database::*;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Collections of rows given in the request, as the 'rows' argument, rather than read from the
+/// database, by name. They are joined against other collections with a `VALUES` list, to filter
+/// or enrich them by a list of keys.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineRowsCollections(pub BTreeMap<String, InlineRowsInfo>);
+
+impl InlineRowsCollections {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Information about a collection of rows given in the request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineRowsInfo {
+    /// The columns of the rows. Their values are converted to the types of the columns, and are
+    /// null where a row does not have them.
+    pub columns: BTreeMap<String, ColumnInfo>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
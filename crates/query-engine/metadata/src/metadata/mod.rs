@@ -1,10 +1,14 @@
 //! Metadata information regarding the database and tracked information.
 
+pub mod computed_fields;
 pub mod database;
+pub mod inline_rows;
 pub mod native_queries;
 
 // re-export without modules
+pub use computed_fields::*;
 pub use database::*;
+pub use inline_rows::*;
 pub use native_queries::*;
 
 use schemars::JsonSchema;
@@ -18,8 +22,35 @@ pub struct Metadata {
     pub tables: TablesInfo,
     #[serde(default)]
     pub native_queries: NativeQueries,
+    #[serde(default, skip_serializing_if = "CompositeTypes::is_empty")]
+    pub composite_types: CompositeTypes,
+    /// Collections of rows given in the request rather than read from the database, by name.
+    #[serde(default, skip_serializing_if = "InlineRowsCollections::is_empty")]
+    pub inline_rows: InlineRowsCollections,
     #[serde(default)]
     pub aggregate_functions: AggregateFunctions,
     #[serde(default)]
     pub comparison_operators: ComparisonOperators,
+    /// The extensions installed in the database when it was introspected.
+    #[serde(default, skip_serializing_if = "Extensions::is_empty")]
+    pub extensions: Extensions,
+    /// The labels of the enum types of the database, by type name.
+    #[serde(default, skip_serializing_if = "EnumTypes::is_empty")]
+    pub enum_types: EnumTypes,
+}
+
+impl Metadata {
+    /// Whether introspection found neither comparison operators nor aggregate functions for a
+    /// scalar type, so that we know nothing about it.
+    pub fn is_unknown_scalar_type(&self, scalar_type: &ScalarType) -> bool {
+        self.comparison_operators
+            .0
+            .get(scalar_type)
+            .map_or(true, |operators| operators.is_empty())
+            && self
+                .aggregate_functions
+                .0
+                .get(scalar_type)
+                .map_or(true, |functions| functions.is_empty())
+    }
 }
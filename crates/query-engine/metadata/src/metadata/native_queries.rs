@@ -5,6 +5,7 @@ use super::database::*;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::fmt;
 
 // Types
 
@@ -34,6 +35,28 @@ pub struct NativeQueryInfo {
     pub is_procedure: bool,
 }
 
+/// The object types of the nested values returned by Native Queries, by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositeTypes(pub BTreeMap<String, CompositeType>);
+
+impl CompositeTypes {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// An object type for nested values, such as those built by a `jsonb_build_object` or
+/// `jsonb_agg` subquery in a Native Query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositeType {
+    /// The fields of the object
+    pub fields: BTreeMap<String, ColumnInfo>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
 /// A part of a Native Query text, either raw text or a parameter.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NativeQueryPart {
@@ -47,6 +70,52 @@ pub enum NativeQueryPart {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NativeQuerySql(pub Vec<NativeQueryPart>);
 
+impl NativeQuerySql {
+    /// The tables, and the functions used as tables, which the Native Query reads from or writes
+    /// to. The SQL is only scanned rather than parsed, so this is best-effort: function calls in
+    /// expressions are not included, and unusual syntax may hide some references.
+    pub fn referenced_objects(&self) -> Vec<ReferencedObject> {
+        // Parameters stand for values, so replacing them with one keeps the SQL well-formed.
+        let sql: String = self
+            .0
+            .iter()
+            .map(|part| match part {
+                NativeQueryPart::Text(text) => text.as_str(),
+                NativeQueryPart::Parameter(_) => "NULL",
+            })
+            .collect();
+        let tokens = tokenize(&sql);
+        let mut scanner = Scanner {
+            common_table_expressions: vec![],
+            tokens: &tokens,
+            position: 0,
+            objects: vec![],
+        };
+        while scanner.position < tokens.len() {
+            // Stray closing parentheses end a level early, so carry on with the rest.
+            scanner.scan_level();
+        }
+        scanner.objects
+    }
+}
+
+/// A database object named in a Native Query. Unquoted names are folded to lower case, as the
+/// database does.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReferencedObject {
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+impl fmt::Display for ReferencedObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.schema {
+            Some(schema) => write!(f, "{}.{}", schema, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
 // Serialization
 
 impl Serialize for NativeQuerySql {
@@ -130,9 +199,367 @@ fn parse_native_query(string: &str) -> Vec<NativeQueryPart> {
     vec.concat()
 }
 
+/// A token of SQL, as far as we need to tell them apart to find the objects it references.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// An unquoted identifier or keyword, in lower case.
+    Word(String),
+    QuotedIdentifier(String),
+    /// A string, number or other constant.
+    Literal,
+    Punctuation(char),
+}
+
+/// Split SQL into tokens, dropping comments.
+fn tokenize(sql: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+        } else if let Some(string) = rest.strip_prefix('\'') {
+            // An escaped quote ('') reads the same as two adjacent strings.
+            rest = string.find('\'').map_or("", |end| &string[end + 1..]);
+            tokens.push(Token::Literal);
+        } else if let Some(identifier) = rest.strip_prefix('"') {
+            let (identifier, after) = quoted_identifier(identifier);
+            tokens.push(Token::QuotedIdentifier(identifier));
+            rest = after;
+        } else if let Some(delimiter) = dollar_quote_delimiter(rest) {
+            let body = &rest[delimiter.len()..];
+            rest = body
+                .find(delimiter)
+                .map_or("", |end| &body[end + delimiter.len()..]);
+            tokens.push(Token::Literal);
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_lowercase()));
+            rest = &rest[end..];
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Literal);
+            rest = &rest[end..];
+        } else {
+            tokens.push(Token::Punctuation(c));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    tokens
+}
+
+/// Read a quoted identifier up to its closing quote, returning it along with the rest of the SQL.
+fn quoted_identifier(sql: &str) -> (String, &str) {
+    let mut identifier = String::new();
+    let mut rest = sql;
+    while let Some(end) = rest.find('"') {
+        identifier.push_str(&rest[..end]);
+        rest = &rest[end + 1..];
+        match rest.strip_prefix('"') {
+            Some(after) => {
+                identifier.push('"');
+                rest = after;
+            }
+            None => return (identifier, rest),
+        }
+    }
+    identifier.push_str(rest);
+    (identifier, "")
+}
+
+/// The delimiter of the dollar-quoted string the SQL starts with, such as `$$` or `$body$`.
+fn dollar_quote_delimiter(sql: &str) -> Option<&str> {
+    let tag = sql.strip_prefix('$')?;
+    let end = tag.find('$')?;
+    let tag = &tag[..end];
+    if tag.starts_with(|c: char| c.is_ascii_digit())
+        || !tag.chars().all(|c| c.is_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+    Some(&sql[..end + 2])
+}
+
+/// The tokens after the parenthesized tokens the given ones start with.
+fn skip_parenthesized(tokens: &[Token]) -> Option<&[Token]> {
+    if tokens.first() != Some(&Token::Punctuation('(')) {
+        return None;
+    }
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Punctuation('(') => depth += 1,
+            Token::Punctuation(')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&tokens[index + 1..]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn identifier(token: &Token) -> Option<String> {
+    match token {
+        Token::Word(word) | Token::QuotedIdentifier(word) => Some(word.clone()),
+        Token::Literal | Token::Punctuation(_) => None,
+    }
+}
+
+/// Keywords which start a statement, after which a `FROM` names tables.
+const STATEMENT_KEYWORDS: [&str; 6] = ["select", "insert", "update", "delete", "table", "copy"];
+
+/// Keywords which are followed by the name of a table. `TABLE name` is short for
+/// `SELECT * FROM name`, and `COPY name` reads or writes a whole table.
+const RELATION_KEYWORDS: [&str; 7] = ["from", "join", "into", "update", "using", "table", "copy"];
+
+/// Keywords which may precede a relation keyword without it naming a table, as in
+/// `IS DISTINCT FROM` or `FOR UPDATE`.
+const RELATION_KEYWORD_MODIFIERS: [&str; 4] = ["distinct", "for", "key", "do"];
+
+/// Keywords which may follow the name of a table, and so are not its alias.
+const NON_ALIAS_KEYWORDS: [&str; 27] = [
+    "where",
+    "join",
+    "inner",
+    "left",
+    "right",
+    "full",
+    "cross",
+    "natural",
+    "on",
+    "using",
+    "group",
+    "order",
+    "limit",
+    "offset",
+    "fetch",
+    "union",
+    "intersect",
+    "except",
+    "having",
+    "window",
+    "returning",
+    "set",
+    "values",
+    "select",
+    "default",
+    "for",
+    "with",
+];
+
+/// Walks through the tokens of a Native Query and collects the objects it references.
+struct Scanner<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// The names of the common table expressions in scope, which are not database objects.
+    common_table_expressions: Vec<String>,
+    objects: Vec<ReferencedObject>,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.position)?;
+        self.position += 1;
+        Some(token)
+    }
+
+    fn peek_word(&self, words: &[&str]) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if words.contains(&word.as_str()))
+    }
+
+    /// Scan up to and including the closing parenthesis of the current level.
+    fn scan_level(&mut self) {
+        // The common table expressions defined at this level are only in scope within it.
+        let outer_common_table_expressions = self.common_table_expressions.len();
+        // Whether this level holds a statement rather than, say, the arguments of a function, in
+        // which `FROM` does not name a table (as in `extract(year FROM date)`).
+        let mut in_statement = false;
+        while let Some(token) = self.advance() {
+            match token {
+                Token::Punctuation('(') => self.scan_level(),
+                Token::Punctuation(')') => break,
+                Token::Punctuation(';') => {
+                    self.common_table_expressions
+                        .truncate(outer_common_table_expressions);
+                    in_statement = false;
+                }
+                // `COPY` only starts a statement, and may otherwise be the name of a column.
+                Token::Word(word) if word == "copy" && !self.starts_statement() => {}
+                Token::Word(word) => {
+                    if word == "with" {
+                        let names = self.with_clause_names();
+                        self.common_table_expressions.extend(names);
+                    }
+                    if STATEMENT_KEYWORDS.contains(&word.as_str()) {
+                        in_statement = true;
+                    }
+                    if in_statement
+                        && RELATION_KEYWORDS.contains(&word.as_str())
+                        && !self.follows_modifier()
+                    {
+                        self.scan_relations(word == "from" || word == "using");
+                    }
+                }
+                Token::QuotedIdentifier(_) | Token::Literal | Token::Punctuation(_) => {}
+            }
+        }
+        self.common_table_expressions
+            .truncate(outer_common_table_expressions);
+    }
+
+    /// The names of the common table expressions defined by the `WITH` clause just read, which
+    /// may be recursive, list the names of their columns, or be marked as materialized, as in
+    /// `WITH RECURSIVE t(n) AS NOT MATERIALIZED (...)`. Other uses of `WITH`, as in
+    /// `WITH ORDINALITY`, define none.
+    fn with_clause_names(&self) -> Vec<String> {
+        let mut names = vec![];
+        let mut rest = &self.tokens[self.position..];
+        if let [Token::Word(recursive), after @ ..] = rest {
+            if recursive == "recursive" {
+                rest = after;
+            }
+        }
+        loop {
+            let Some(name) = rest.first().and_then(identifier) else {
+                return names;
+            };
+            rest = &rest[1..];
+            if rest.first() == Some(&Token::Punctuation('(')) {
+                // The column names.
+                let Some(after) = skip_parenthesized(rest) else {
+                    return names;
+                };
+                rest = after;
+            }
+            rest = match rest {
+                [Token::Word(as_), after @ ..] if as_ == "as" => after,
+                _ => return names,
+            };
+            rest = match rest {
+                [Token::Word(not), Token::Word(materialized), after @ ..]
+                    if not == "not" && materialized == "materialized" =>
+                {
+                    after
+                }
+                [Token::Word(materialized), after @ ..] if materialized == "materialized" => after,
+                rest => rest,
+            };
+            let Some(after) = skip_parenthesized(rest) else {
+                return names;
+            };
+            names.push(name);
+            rest = match after {
+                [Token::Punctuation(','), after @ ..] => after,
+                _ => return names,
+            };
+        }
+    }
+
+    /// Whether the token just read is the first of a statement.
+    fn starts_statement(&self) -> bool {
+        match self
+            .position
+            .checked_sub(2)
+            .map(|index| &self.tokens[index])
+        {
+            None | Some(Token::Punctuation(';')) => true,
+            Some(_) => false,
+        }
+    }
+
+    /// Whether the keyword just read is preceded by a word which changes its meaning.
+    fn follows_modifier(&self) -> bool {
+        match self
+            .position
+            .checked_sub(2)
+            .map(|index| &self.tokens[index])
+        {
+            Some(Token::Word(word)) => RELATION_KEYWORD_MODIFIERS.contains(&word.as_str()),
+            _ => false,
+        }
+    }
+
+    /// Note the objects named after a relation keyword. After `FROM` and `USING` these are a
+    /// comma-separated list.
+    fn scan_relations(&mut self, is_list: bool) {
+        loop {
+            while self.peek_word(&["only", "lateral"]) {
+                self.position += 1;
+            }
+            if self.peek() == Some(&Token::Punctuation('(')) {
+                // A subquery.
+                self.position += 1;
+                self.scan_level();
+            } else {
+                let Some(object) = self.object_name() else {
+                    return;
+                };
+                if self.peek() == Some(&Token::Punctuation('(')) {
+                    // A function used as a table, whose arguments may reference tables too.
+                    self.position += 1;
+                    self.scan_level();
+                    self.objects.push(object);
+                } else if object.schema.is_some()
+                    || !self.common_table_expressions.contains(&object.name)
+                {
+                    self.objects.push(object);
+                }
+            }
+            self.skip_alias();
+            if !is_list || self.peek() != Some(&Token::Punctuation(',')) {
+                return;
+            }
+            self.position += 1;
+        }
+    }
+
+    /// Read a name, qualified with its schema or not.
+    fn object_name(&mut self) -> Option<ReferencedObject> {
+        let name = identifier(self.peek()?)?;
+        self.position += 1;
+        if self.peek() == Some(&Token::Punctuation('.')) {
+            if let Some(qualified_name) = self.tokens.get(self.position + 1).and_then(identifier) {
+                self.position += 2;
+                return Some(ReferencedObject {
+                    schema: Some(name),
+                    name: qualified_name,
+                });
+            }
+        }
+        Some(ReferencedObject { schema: None, name })
+    }
+
+    fn skip_alias(&mut self) {
+        if self.peek_word(&["as"]) {
+            self.position += 1;
+        }
+        match self.peek() {
+            Some(Token::Word(word)) if !NON_ALIAS_KEYWORDS.contains(&word.as_str()) => {
+                self.position += 1
+            }
+            Some(Token::QuotedIdentifier(_)) => self.position += 1,
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{parse_native_query, NativeQueryPart};
+    use super::{parse_native_query, NativeQueryPart, NativeQuerySql, ReferencedObject};
 
     #[test]
     fn no_parameters() {
@@ -180,4 +607,97 @@ mod tests {
             ]
         );
     }
+
+    fn referenced_objects(sql: &str) -> Vec<String> {
+        NativeQuerySql(parse_native_query(sql))
+            .referenced_objects()
+            .iter()
+            .map(ReferencedObject::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn referenced_tables_and_schemas() {
+        assert_eq!(
+            referenced_objects(
+                r#"SELECT * FROM public."Album" a JOIN "Artist" USING ("ArtistId"), Genre AS g WHERE a."Title" = {{title}}"#
+            ),
+            vec!["public.Album", "Artist", "genre"]
+        );
+    }
+
+    #[test]
+    fn referenced_objects_in_subqueries_and_statements() {
+        assert_eq!(
+            referenced_objects(
+                r#"WITH recent AS (SELECT * FROM "Invoice" ORDER BY "InvoiceDate" DESC LIMIT 10)
+                   SELECT extract(year FROM "InvoiceDate"), (SELECT count(*) FROM "InvoiceLine" l WHERE l."InvoiceId" IS DISTINCT FROM recent."InvoiceId")
+                   FROM recent, generate_series(1, 3) /* FROM "Hidden" */ WHERE 'FROM "Quoted"' <> $$ FROM "Dollar" $$"#
+            ),
+            vec!["Invoice", "InvoiceLine", "generate_series"]
+        );
+        assert_eq!(
+            referenced_objects(r#"INSERT INTO public."Album" VALUES ({{id}}) RETURNING *"#),
+            vec!["public.Album"]
+        );
+        assert_eq!(
+            referenced_objects(
+                r#"UPDATE "Track" SET "Composer" = {{composer}} FROM "Album" WHERE "Album"."AlbumId" = "Track"."AlbumId""#
+            ),
+            vec!["Track", "Album"]
+        );
+    }
+
+    #[test]
+    fn tables_read_with_table_and_copy_are_referenced_objects() {
+        assert_eq!(
+            referenced_objects(r#"TABLE secrets UNION ALL (TABLE audit."Secrets")"#),
+            vec!["secrets", "audit.Secrets"]
+        );
+        assert_eq!(
+            referenced_objects(r#"COPY secrets TO STDOUT; COPY (SELECT * FROM "Album") TO STDOUT"#),
+            vec!["secrets", "Album"]
+        );
+        assert_eq!(
+            referenced_objects(r#"SELECT copy FROM "Album""#),
+            vec!["Album"]
+        );
+    }
+
+    #[test]
+    fn common_table_expressions_are_only_in_scope_of_their_with_clause() {
+        assert_eq!(
+            referenced_objects(
+                r#"SELECT * FROM (WITH secrets AS (SELECT 1) SELECT * FROM secrets) s, secrets"#
+            ),
+            vec!["secrets"]
+        );
+        assert_eq!(
+            referenced_objects(
+                r#"WITH secrets AS (SELECT 1) SELECT * FROM secrets; SELECT * FROM secrets"#
+            ),
+            vec!["secrets"]
+        );
+        assert_eq!(
+            referenced_objects(
+                r#"SELECT * FROM unnest(ARRAY[1]) WITH ORDINALITY AS secrets(n, i) JOIN secrets USING (n)"#
+            ),
+            vec!["unnest", "secrets"]
+        );
+    }
+
+    #[test]
+    fn recursive_common_table_expressions_are_not_referenced_objects() {
+        assert_eq!(
+            referenced_objects(
+                r#"WITH RECURSIVE chain("EmployeeId", depth) AS (
+                     SELECT "EmployeeId", 0 FROM "Employee" WHERE "ReportsTo" IS NULL
+                     UNION ALL
+                     SELECT e."EmployeeId", c.depth + 1 FROM "Employee" e JOIN chain c ON e."ReportsTo" = c."EmployeeId"
+                   ), managers AS MATERIALIZED (SELECT DISTINCT "ReportsTo" FROM "Employee")
+                   SELECT * FROM chain JOIN managers ON managers."ReportsTo" = chain."EmployeeId""#
+            ),
+            vec!["Employee", "Employee", "Employee"]
+        );
+    }
 }
@@ -6,6 +6,30 @@ use std::collections::BTreeMap;
 #[derive(Debug, Clone, PartialEq)]
 pub enum Explain<'a> {
     Select(&'a Select),
+    Statement(&'a [RawSql]),
+}
+
+/// The format of the plan returned by an EXPLAIN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+    Text,
+    Json,
+}
+
+/// A COPY of the rows of a query to the client
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyTo<'a> {
+    pub select: &'a Select,
+    pub format: CopyFormat,
+}
+
+/// The format of the rows sent by a COPY
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// CSV with a header line naming the columns.
+    Csv,
+    /// The query's single column as it is, one row per line, for instance a JSON object.
+    Lines,
 }
 
 /// A WITH clause
@@ -44,6 +68,9 @@ pub struct Select {
     pub select_list: SelectList,
     pub from: Option<From>,
     pub joins: Vec<Join>,
+    /// Read the data as of a point in time with CockroachDB's `AS OF SYSTEM TIME`, which is only
+    /// allowed on the top-level select.
+    pub as_of_system_time: Option<Expression>,
     pub where_: Where,
     pub group_by: GroupBy,
     pub order_by: OrderBy,
@@ -64,6 +91,10 @@ pub enum From {
     Table {
         reference: TableReference,
         alias: TableAlias,
+        /// Leave out the rows of the tables which inherit from this one, with `FROM ONLY`.
+        only: bool,
+        /// Only read a sample of the rows of the table, with `TABLESAMPLE`.
+        sample: Option<TableSample>,
     },
     /// Select from a subquery
     Select {
@@ -77,8 +108,53 @@ pub enum From {
         alias: TableAlias,
         columns: Vec<(ColumnAlias, ScalarType)>,
     },
+    /// The elements of an array, along with their positions in it, counting from 1.
+    /// `unnest(<expr>) WITH ORDINALITY AS <alias>(<element>, <ordinality>)`
+    Unnest {
+        expression: Expression,
+        alias: TableAlias,
+        element: ColumnAlias,
+        ordinality: ColumnAlias,
+    },
+    /// Rows given as lists of values, in the order of the columns.
+    /// `(VALUES (<expr>, ...), ...) AS <alias>(<column>, ...)`
+    Values {
+        rows: Vec<Vec<Expression>>,
+        alias: TableAlias,
+        columns: Vec<ColumnAlias>,
+    },
+}
+
+/// A sample of the rows of a table.
+/// `TABLESAMPLE <method> (<percentage>)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSample {
+    pub method: TableSampleMethod,
+    pub percentage: Expression,
+}
+
+/// How the rows of a table sample are chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableSampleMethod {
+    /// Whole pages of the table at a time, which is faster.
+    System,
+    /// Each row on its own, which is more random.
+    Bernoulli,
 }
 
+/// A hint to the planner, read by the pg_hint_plan extension, to scan a table with one of the
+/// given indexes.
+/// `IndexScan(<table> <index> ...)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexHint {
+    pub table: TableAlias,
+    pub indexes: Vec<IndexName>,
+}
+
+/// The name of an index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexName(pub String);
+
 /// A JOIN clause
 #[derive(Debug, Clone, PartialEq)]
 pub enum Join {
@@ -133,6 +209,8 @@ pub struct OrderBy {
 pub struct OrderByElement {
     pub target: Expression,
     pub direction: OrderByDirection,
+    /// Where the nulls go, when not where the direction puts them.
+    pub nulls: Option<NullsOrder>,
 }
 
 /// A direction for a single ORDER BY element
@@ -142,11 +220,21 @@ pub enum OrderByDirection {
     Desc,
 }
 
+/// NULLS FIRST or NULLS LAST in an ORDER BY element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
 /// LIMIT and OFFSET clauses
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Limit {
     pub limit: Option<u32>,
     pub offset: Option<u32>,
+    /// Also keep the rows which sort equal to the last row within the limit. This requires an
+    /// ORDER BY clause.
+    pub with_ties: bool,
 }
 
 /// A scalar expression
@@ -205,9 +293,37 @@ pub enum Expression {
         expression: Box<Expression>,
         r#type: ScalarType,
     },
+    /// An expression with an explicit collation
+    Collate {
+        expression: Box<Expression>,
+        collation: String,
+    },
     /// A COUNT clause
     Count(CountType),
     ArrayConstructor(Vec<Expression>),
+    /// An expression written by a user, such as that of a computed field
+    RawSql(Vec<RawSql>),
+    /// A window function call, such as `COUNT(*) OVER ()`, which is computed over the rows of
+    /// its partition rather than grouping them
+    WindowFunction {
+        function: Box<Expression>,
+        partition_by: Vec<Expression>,
+        order_by: OrderBy,
+    },
+    /// An aggregate function call whose result depends on the order in which it reads the rows,
+    /// such as `array_agg(x ORDER BY y)`
+    OrderedAggregate {
+        function: Function,
+        args: Vec<Expression>,
+        order_by: OrderBy,
+    },
+    /// A SELECT which returns a single value, used as an expression
+    ScalarSelect(Box<Select>),
+    /// A CASE expression, which takes the result of the first condition which holds
+    Case {
+        cases: Vec<(Expression, Expression)>,
+        otherwise: Box<Expression>,
+    },
 }
 
 /// An unary operator
@@ -316,9 +432,14 @@ pub struct ColumnAlias {
 
 /// Transactions manipulation
 pub mod transaction {
-    /// Begin a transaction
+    /// Begin a transaction, with the database's default isolation level unless one is given
     pub struct Begin {
-        pub isolation_level: IsolationLevel,
+        pub isolation_level: Option<IsolationLevel>,
+    }
+
+    /// Set the role for the rest of the current transaction
+    pub struct SetLocalRole {
+        pub role: String,
     }
 
     /// Commit a transaction
@@ -330,5 +451,8 @@ pub mod transaction {
     /// The isolation level for the transaction
     pub enum IsolationLevel {
         ReadCommitedReadWrite,
+        ReadCommitted,
+        RepeatableRead,
+        Serializable,
     }
 }
@@ -66,10 +66,35 @@ impl RawSql {
 }
 
 impl Explain<'_> {
-    pub fn to_sql(&self, sql: &mut SQL) {
-        sql.append_syntax("EXPLAIN ");
+    pub fn to_sql(&self, format: ExplainFormat, sql: &mut SQL) {
+        match format {
+            ExplainFormat::Text => sql.append_syntax("EXPLAIN "),
+            ExplainFormat::Json => sql.append_syntax("EXPLAIN (FORMAT JSON, ANALYZE false) "),
+        }
         match self {
             Explain::Select(select) => select.to_sql(sql),
+            Explain::Statement(statement) => {
+                for item in statement.iter() {
+                    item.to_sql(sql);
+                }
+            }
+        }
+    }
+}
+
+impl CopyTo<'_> {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax("COPY (");
+        self.select.to_sql(sql);
+        sql.append_syntax(") TO STDOUT ");
+        match self.format {
+            CopyFormat::Csv => sql.append_syntax("WITH (FORMAT csv, HEADER)"),
+            // The text format would escape the backslashes of the values. In CSV, a value is only
+            // quoted when it holds the delimiter, the quote or a line break, so with control
+            // characters as those and no line breaks in the values, they are sent as they are.
+            CopyFormat::Lines => {
+                sql.append_syntax(r"WITH (FORMAT csv, QUOTE E'\x01', DELIMITER E'\x02')")
+            }
         }
     }
 }
@@ -113,6 +138,11 @@ impl Select {
             join.to_sql(sql)
         }
 
+        if let Some(as_of_system_time) = &self.as_of_system_time {
+            sql.append_syntax(" AS OF SYSTEM TIME ");
+            as_of_system_time.to_sql(sql);
+        }
+
         self.where_.to_sql(sql);
 
         self.order_by.to_sql(sql);
@@ -125,10 +155,21 @@ impl From {
     pub fn to_sql(&self, sql: &mut SQL) {
         sql.append_syntax("FROM ");
         match &self {
-            From::Table { reference, alias } => {
+            From::Table {
+                reference,
+                alias,
+                only,
+                sample,
+            } => {
+                if *only {
+                    sql.append_syntax("ONLY ");
+                }
                 reference.to_sql(sql);
                 sql.append_syntax(" AS ");
                 alias.to_sql(sql);
+                if let Some(sample) = sample {
+                    sample.to_sql(sql);
+                }
             }
             From::Select { select, alias } => {
                 sql.append_syntax("(");
@@ -160,6 +201,55 @@ impl From {
                 }
                 sql.append_syntax(")");
             }
+            From::Unnest {
+                expression,
+                alias,
+                element,
+                ordinality,
+            } => {
+                sql.append_syntax("unnest");
+                sql.append_syntax("(");
+                expression.to_sql(sql);
+                sql.append_syntax(")");
+                sql.append_syntax(" WITH ORDINALITY AS ");
+                alias.to_sql(sql);
+                sql.append_syntax("(");
+                element.to_sql(sql);
+                sql.append_syntax(", ");
+                ordinality.to_sql(sql);
+                sql.append_syntax(")");
+            }
+            From::Values {
+                rows,
+                alias,
+                columns,
+            } => {
+                sql.append_syntax("(VALUES ");
+                for (index, row) in rows.iter().enumerate() {
+                    sql.append_syntax("(");
+                    for (index, value) in row.iter().enumerate() {
+                        value.to_sql(sql);
+                        if index < (row.len() - 1) {
+                            sql.append_syntax(", ")
+                        }
+                    }
+                    sql.append_syntax(")");
+                    if index < (rows.len() - 1) {
+                        sql.append_syntax(", ")
+                    }
+                }
+                sql.append_syntax(")");
+                sql.append_syntax(" AS ");
+                alias.to_sql(sql);
+                sql.append_syntax("(");
+                for (index, column) in columns.iter().enumerate() {
+                    column.to_sql(sql);
+                    if index < (columns.len() - 1) {
+                        sql.append_syntax(", ")
+                    }
+                }
+                sql.append_syntax(")");
+            }
         }
     }
 }
@@ -229,6 +319,14 @@ impl Expression {
                 r#type.to_sql(sql);
                 sql.append_syntax(")");
             }
+            Expression::Collate {
+                expression,
+                collation,
+            } => {
+                expression.to_sql(sql);
+                sql.append_syntax(" COLLATE ");
+                sql.append_identifier(collation);
+            }
             Expression::And { left, right } => {
                 sql.append_syntax("(");
                 left.to_sql(sql);
@@ -345,6 +443,65 @@ impl Expression {
                 }
                 sql.append_syntax("]");
             }
+            Expression::RawSql(raw_sql) => {
+                sql.append_syntax("(");
+                for item in raw_sql {
+                    item.to_sql(sql);
+                }
+                sql.append_syntax(")");
+            }
+            Expression::WindowFunction {
+                function,
+                partition_by,
+                order_by,
+            } => {
+                function.to_sql(sql);
+                sql.append_syntax(" OVER (");
+                if !partition_by.is_empty() {
+                    sql.append_syntax("PARTITION BY ");
+                    for (index, expression) in partition_by.iter().enumerate() {
+                        expression.to_sql(sql);
+                        if index < (partition_by.len() - 1) {
+                            sql.append_syntax(", ")
+                        }
+                    }
+                }
+                order_by.to_sql(sql);
+                sql.append_syntax(")");
+            }
+            Expression::OrderedAggregate {
+                function,
+                args,
+                order_by,
+            } => {
+                function.to_sql(sql);
+                sql.append_syntax("(");
+                for (index, arg) in args.iter().enumerate() {
+                    arg.to_sql(sql);
+                    if index < (args.len() - 1) {
+                        sql.append_syntax(", ")
+                    }
+                }
+                order_by.to_sql(sql);
+                sql.append_syntax(")");
+            }
+            Expression::ScalarSelect(select) => {
+                sql.append_syntax("(");
+                select.to_sql(sql);
+                sql.append_syntax(")");
+            }
+            Expression::Case { cases, otherwise } => {
+                sql.append_syntax("CASE");
+                for (condition, result) in cases {
+                    sql.append_syntax(" WHEN ");
+                    condition.to_sql(sql);
+                    sql.append_syntax(" THEN ");
+                    result.to_sql(sql);
+                }
+                sql.append_syntax(" ELSE ");
+                otherwise.to_sql(sql);
+                sql.append_syntax(" END");
+            }
         }
     }
 }
@@ -357,6 +514,56 @@ impl UnaryOperator {
     }
 }
 
+/// Render the hints of a query as the comment at its start which pg_hint_plan reads them from,
+/// such as `/*+ IndexScan("%0_Album" "PK_Album") */`.
+///
+/// Nothing in a comment can be escaped, so hints naming a table or an index with `/*` or `*/` in
+/// its name, which would nest or end the comment, are left out.
+pub fn hints_to_sql(hints: &[IndexHint], sql: &mut SQL) {
+    let hints: Vec<&IndexHint> = hints.iter().filter(|hint| hint.fits_in_comment()).collect();
+    if hints.is_empty() {
+        return;
+    }
+    sql.append_syntax("/*+");
+    for hint in hints {
+        sql.append_syntax(" ");
+        hint.to_sql(sql);
+    }
+    sql.append_syntax(" */ ");
+}
+
+impl IndexHint {
+    /// Whether none of the names in the hint would nest or end the comment it is written in.
+    pub fn fits_in_comment(&self) -> bool {
+        std::iter::once(&self.table.name)
+            .chain(self.indexes.iter().map(|index| &index.0))
+            .all(|name| !name.contains("/*") && !name.contains("*/"))
+    }
+
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax("IndexScan(");
+        self.table.to_sql(sql);
+        for index in &self.indexes {
+            sql.append_syntax(" ");
+            sql.append_identifier(&index.0);
+        }
+        sql.append_syntax(")");
+    }
+}
+
+impl TableSample {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax(" TABLESAMPLE ");
+        match self.method {
+            TableSampleMethod::System => sql.append_syntax("SYSTEM"),
+            TableSampleMethod::Bernoulli => sql.append_syntax("BERNOULLI"),
+        }
+        sql.append_syntax(" (");
+        self.percentage.to_sql(sql);
+        sql.append_syntax(")");
+    }
+}
+
 impl BinaryOperator {
     pub fn to_sql(&self, sql: &mut SQL) {
         sql.append_syntax(" ");
@@ -430,6 +637,20 @@ impl ScalarType {
 
 impl Limit {
     pub fn to_sql(&self, sql: &mut SQL) {
+        if self.with_ties {
+            // Ties can only be kept with the standard syntax, in which the offset comes first.
+            if let Some(offset) = self.offset {
+                sql.append_syntax(" OFFSET ");
+                sql.append_syntax(format!("{}", offset).as_str());
+                sql.append_syntax(" ROWS");
+            }
+            if let Some(limit) = self.limit {
+                sql.append_syntax(" FETCH FIRST ");
+                sql.append_syntax(format!("{}", limit).as_str());
+                sql.append_syntax(" ROWS WITH TIES");
+            }
+            return;
+        }
         match self.limit {
             None => (),
             Some(limit) => {
@@ -509,7 +730,12 @@ impl OrderBy {
 impl OrderByElement {
     pub fn to_sql(&self, sql: &mut SQL) {
         self.target.to_sql(sql);
-        self.direction.to_sql(sql)
+        self.direction.to_sql(sql);
+        match self.nulls {
+            None => (),
+            Some(NullsOrder::First) => sql.append_syntax("NULLS FIRST "),
+            Some(NullsOrder::Last) => sql.append_syntax("NULLS LAST "),
+        }
     }
 }
 
@@ -524,8 +750,18 @@ impl OrderByDirection {
 
 impl transaction::Begin {
     pub fn to_sql(&self, sql: &mut SQL) {
-        sql.append_syntax("BEGIN ");
-        self.isolation_level.to_sql(sql);
+        sql.append_syntax("BEGIN");
+        if let Some(isolation_level) = &self.isolation_level {
+            sql.append_syntax(" ");
+            isolation_level.to_sql(sql);
+        }
+    }
+}
+
+impl transaction::SetLocalRole {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax("SET LOCAL ROLE ");
+        sql.append_identifier(&self.role);
     }
 }
 
@@ -536,6 +772,9 @@ impl transaction::IsolationLevel {
             transaction::IsolationLevel::ReadCommitedReadWrite => {
                 sql.append_syntax(" READ COMMITTED READ WRITE")
             }
+            transaction::IsolationLevel::ReadCommitted => sql.append_syntax("READ COMMITTED"),
+            transaction::IsolationLevel::RepeatableRead => sql.append_syntax("REPEATABLE READ"),
+            transaction::IsolationLevel::Serializable => sql.append_syntax("SERIALIZABLE"),
         }
     }
 }
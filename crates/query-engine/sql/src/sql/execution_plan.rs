@@ -13,6 +13,40 @@ pub struct ExecutionPlan<Query> {
     pub query: Query,
     /// Run after the query. Should be a sql::ast in the future.
     pub post: Vec<sql::string::Statement>,
+    /// The most bytes of SQL the query may be rendered to. Longer queries are rejected rather
+    /// than sent to the database.
+    pub max_sql_length: Option<usize>,
+}
+
+/// The SQL of a query is longer than the maximum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlTooLong {
+    pub length: usize,
+    pub max_length: usize,
+}
+
+impl std::fmt::Display for SqlTooLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "The request translates to {} bytes of SQL, more than the maximum of {}.",
+            self.length, self.max_length
+        )
+    }
+}
+
+/// Check that rendered SQL is no longer than the maximum, if there is one.
+pub fn check_sql_length(
+    sql: &sql::string::SQL,
+    max_sql_length: Option<usize>,
+) -> Result<(), SqlTooLong> {
+    match max_sql_length {
+        Some(max_length) if sql.sql.len() > max_length => Err(SqlTooLong {
+            length: sql.sql.len(),
+            max_length,
+        }),
+        _ => Ok(()),
+    }
 }
 
 /// The query we want to run with some additional information.
@@ -24,15 +58,73 @@ pub struct Query {
     pub variables: Option<Vec<BTreeMap<String, serde_json::Value>>>,
     /// The query.
     pub query: sql::ast::Select,
+    /// The native queries whose column types to check before running the query.
+    pub native_query_column_types: Vec<NativeQueryColumnTypes>,
+    /// Which identifiers are quoted in the SQL of the query.
+    pub identifier_quoting: sql::string::IdentifierQuoting,
+    /// The indexes the planner is hinted to scan tables with, by pg_hint_plan.
+    pub index_hints: Vec<sql::ast::IndexHint>,
 }
 
-impl Query {
+/// The types the columns of a native query are declared with, to check against those the database
+/// reports for the columns it returns.
+#[derive(Debug)]
+pub struct NativeQueryColumnTypes {
+    /// The name of the native query.
+    pub name: String,
+    /// A query selecting the rows of the native query. It is only described, not run.
+    pub query: sql::ast::Select,
+    /// The declared type of each column, by column name, as a type name such as 'int4[]'.
+    pub columns: BTreeMap<String, String>,
+}
+
+impl NativeQueryColumnTypes {
     /// Extract the query component as SQL.
     pub fn query_sql(&self) -> sql::string::SQL {
         select_to_sql(&self.query)
     }
-    pub fn explain_query_sql(&self) -> sql::string::SQL {
-        explain_to_sql(&sql::ast::Explain::Select(&self.query))
+}
+
+impl Query {
+    /// Extract the query component as SQL.
+    pub fn query_sql(&self) -> sql::string::SQL {
+        let mut sql = sql::string::SQL::with_identifier_quoting(self.identifier_quoting);
+        sql::convert::hints_to_sql(&self.index_hints, &mut sql);
+        self.query.to_sql(&mut sql);
+        sql
+    }
+    pub fn explain_query_sql(&self, format: sql::ast::ExplainFormat) -> sql::string::SQL {
+        let mut sql = sql::string::SQL::with_identifier_quoting(self.identifier_quoting);
+        sql::convert::hints_to_sql(&self.index_hints, &mut sql);
+        sql::ast::Explain::Select(&self.query).to_sql(format, &mut sql);
+        sql
+    }
+}
+
+/// A query whose rows are exported with `COPY`, rather than collected into a JSON response.
+#[derive(Debug)]
+pub struct Export {
+    /// The name of the exported collection.
+    pub root_field: String,
+    /// The query selecting the exported rows.
+    pub query: sql::ast::Select,
+    /// The format of the exported rows.
+    pub format: sql::ast::CopyFormat,
+    /// Which identifiers are quoted in the SQL of the export.
+    pub identifier_quoting: sql::string::IdentifierQuoting,
+}
+
+impl Export {
+    /// Extract the export component as SQL. `COPY` cannot take parameters, so they are written
+    /// into it as literals.
+    pub fn export_sql(&self) -> sql::string::SQL {
+        let mut sql = sql::string::SQL::with_literal_params(self.identifier_quoting);
+        sql::ast::CopyTo {
+            select: &self.query,
+            format: self.format,
+        }
+        .to_sql(&mut sql);
+        sql
     }
 }
 
@@ -42,9 +134,12 @@ pub fn select_to_sql(select: &sql::ast::Select) -> sql::string::SQL {
     sql
 }
 
-pub fn explain_to_sql(explain: &sql::ast::Explain) -> sql::string::SQL {
+pub fn explain_to_sql(
+    explain: &sql::ast::Explain,
+    format: sql::ast::ExplainFormat,
+) -> sql::string::SQL {
     let mut sql = sql::string::SQL::new();
-    explain.to_sql(&mut sql);
+    explain.to_sql(format, &mut sql);
     sql
 }
 
@@ -60,8 +155,12 @@ pub fn simple_query_execution_plan(
             variables,
             root_field,
             query,
+            native_query_column_types: vec![],
+            identifier_quoting: sql::string::IdentifierQuoting::default(),
+            index_hints: vec![],
         },
         post: vec![],
+        max_sql_length: None,
     }
 }
 
@@ -75,24 +174,53 @@ pub struct Mutation {
     /// The root field name of the top-most collection.
     pub root_field: String,
     /// The query.
-    pub query: sql::ast::Select,
+    pub query: MutationQuery,
+}
+
+/// How a mutation is run against the database.
+#[derive(Debug)]
+pub enum MutationQuery {
+    /// A query returning the affected row count and the returned rows as JSON.
+    Select(sql::ast::Select),
+    /// A statement which returns no rows, such as an `UPDATE` without a `RETURNING` clause.
+    /// The affected row count is the one reported by the database.
+    Statement(Vec<sql::ast::RawSql>),
 }
 
 impl Mutation {
     /// Extract the query component as SQL.
     pub fn query_sql(&self) -> sql::string::SQL {
-        select_to_sql(&self.query)
+        match &self.query {
+            MutationQuery::Select(select) => select_to_sql(select),
+            MutationQuery::Statement(statement) => statement_to_sql(statement),
+        }
     }
-    pub fn explain_query_sql(&self) -> sql::string::SQL {
-        explain_to_sql(&sql::ast::Explain::Select(&self.query))
+    pub fn explain_query_sql(&self, format: sql::ast::ExplainFormat) -> sql::string::SQL {
+        match &self.query {
+            MutationQuery::Select(select) => {
+                explain_to_sql(&sql::ast::Explain::Select(select), format)
+            }
+            MutationQuery::Statement(statement) => {
+                explain_to_sql(&sql::ast::Explain::Statement(statement), format)
+            }
+        }
     }
 }
 
+pub fn statement_to_sql(statement: &[sql::ast::RawSql]) -> sql::string::SQL {
+    let mut sql = sql::string::SQL::new();
+    for item in statement {
+        item.to_sql(&mut sql);
+    }
+    sql
+}
+
 /// A simple mutation execution plan with only a root field and a query.
 pub fn simple_mutations_execution_plan(mutations: Vec<Mutation>) -> ExecutionPlan<Mutations> {
     ExecutionPlan {
         pre: sql::helpers::mutation_begin(),
         query: Mutations(mutations),
         post: sql::helpers::mutation_end(),
+        max_sql_length: None,
     }
 }
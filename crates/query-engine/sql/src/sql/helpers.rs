@@ -11,6 +11,9 @@ pub enum SelectSet {
     Rows(Select),
     Aggregates(Select),
     RowsAndAggregates(Select, Select),
+    /// Rows along with star counts of all the rows they are a page of, which are selected by
+    /// the aggregate select.
+    RowsAndTotalCount(Select, Select),
 }
 
 // Empty clauses //
@@ -42,6 +45,7 @@ pub fn empty_limit() -> Limit {
     Limit {
         limit: None,
         offset: None,
+        with_ties: false,
     }
 }
 
@@ -82,6 +86,7 @@ pub fn simple_select(select_list: Vec<(ColumnAlias, Expression)>) -> Select {
         select_list: SelectList::SelectList(select_list),
         from: None,
         joins: vec![],
+        as_of_system_time: None,
         where_: Where(empty_where()),
         group_by: empty_group_by(),
         order_by: empty_order_by(),
@@ -96,6 +101,7 @@ pub fn star_select(from: From) -> Select {
         select_list: SelectList::SelectStar,
         from: Some(from),
         joins: vec![],
+        as_of_system_time: None,
         where_: Where(empty_where()),
         group_by: empty_group_by(),
         order_by: empty_order_by(),
@@ -146,12 +152,12 @@ pub fn select_rowset_without_variables(
     let mut final_select = simple_select(row);
 
     let wrap_row =
-        |row_sel| select_rows_as_json(row_sel, row_column_alias, row_table_alias.clone());
+        |row_sel| select_rows_as_json(row_sel, row_column_alias.clone(), row_table_alias.clone());
 
     let wrap_aggregate = |aggregate_sel| {
         select_row_as_json_with_default(
             aggregate_sel,
-            aggregate_column_alias,
+            aggregate_column_alias.clone(),
             aggregate_table_alias.clone(),
         )
     };
@@ -193,6 +199,24 @@ pub fn select_rowset_without_variables(
                 select: Box::new(select_star),
             })
         }
+        SelectSet::RowsAndTotalCount(row_select, count_select) => {
+            let select_star = star_select(From::Select {
+                alias: row_table_alias.clone(),
+                select: Box::new(select_rows_and_total_count_as_json(
+                    row_select,
+                    count_select,
+                    (row_table_alias.clone(), row_column_alias.clone()),
+                    (
+                        aggregate_table_alias.clone(),
+                        aggregate_column_alias.clone(),
+                    ),
+                )),
+            });
+            final_select.from = Some(From::Select {
+                alias: output_table_alias,
+                select: Box::new(select_star),
+            })
+        }
     }
     final_select
 }
@@ -268,12 +292,12 @@ pub fn select_rowset_with_variables(
     let mut final_select = simple_select(row);
 
     let wrap_row =
-        |row_sel| select_rows_as_json(row_sel, row_column_alias, row_table_alias.clone());
+        |row_sel| select_rows_as_json(row_sel, row_column_alias.clone(), row_table_alias.clone());
 
     let wrap_aggregate = |aggregate_sel| {
         select_row_as_json_with_default(
             aggregate_sel,
-            aggregate_column_alias,
+            aggregate_column_alias.clone(),
             aggregate_table_alias.clone(),
         )
     };
@@ -285,6 +309,7 @@ pub fn select_rowset_with_variables(
                 column: make_column_alias(VARIABLE_ORDER_FIELD.to_string()),
             }),
             direction: OrderByDirection::Asc,
+            nulls: None,
         }],
     };
 
@@ -336,6 +361,27 @@ pub fn select_rowset_with_variables(
                 }),
             ];
         }
+        SelectSet::RowsAndTotalCount(row_select, count_select) => {
+            let mut select_star = star_select(From::Select {
+                alias: row_table_alias.clone(),
+                select: Box::new(select_rows_and_total_count_as_json(
+                    row_select,
+                    count_select,
+                    (row_table_alias.clone(), row_column_alias.clone()),
+                    (
+                        aggregate_table_alias.clone(),
+                        aggregate_column_alias.clone(),
+                    ),
+                )),
+            });
+
+            select_star.order_by = order_by;
+
+            final_select.joins = vec![Join::CrossJoinLateral(CrossJoin {
+                select: Box::new(select_star),
+                alias: output_table_alias,
+            })];
+        }
     }
 
     final_select
@@ -427,6 +473,116 @@ pub fn select_rows_as_json(
     select
 }
 
+/// Select a page of rows along with star counts of all the rows it is a page of, scanning them
+/// once rather than once for the rows and again for the counts:
+///
+/// ```sql
+/// SELECT
+///   coalesce(json_agg(row_to_json(<row_table_alias>)), '[]') AS <row_column_alias>,
+///   json_build_object(
+///     '<count alias>', coalesce(max(<count_table_alias>."%total_count"), (<count_select>))
+///   ) AS <aggregate_column_alias>
+/// FROM (
+///   SELECT <row columns>, COUNT(*) OVER () AS "%total_count" ... LIMIT ...
+/// ) AS <count_table_alias>
+/// CROSS JOIN LATERAL (
+///   SELECT <count_table_alias>.<row column> AS <row column>, ...
+/// ) AS <row_table_alias>
+/// ```
+///
+/// The window function counts the rows before the limit and offset are applied, but this means
+/// the database finds every matching row even when only a small page of them is returned, where
+/// it could otherwise stop early. When the page is empty there is no row to read the count from,
+/// and the rows are counted again by `count_select`, which the database only runs then.
+pub fn select_rows_and_total_count_as_json(
+    mut row_select: Select,
+    count_select: Select,
+    (row_table_alias, row_column_alias): (TableAlias, ColumnAlias),
+    (count_table_alias, count_column_alias): (TableAlias, ColumnAlias),
+) -> Select {
+    let total_count_alias = make_column_alias(TOTAL_COUNT_FIELD.to_string());
+
+    // rows are always selected by name, so we can pick their columns out again.
+    let row_columns: Vec<(ColumnAlias, Expression)> = match &mut row_select.select_list {
+        SelectList::SelectList(columns) => {
+            let row_columns = columns
+                .iter()
+                .map(|(alias, _)| {
+                    (
+                        alias.clone(),
+                        Expression::ColumnReference(ColumnReference::AliasedColumn {
+                            table: TableReference::AliasedTable(count_table_alias.clone()),
+                            column: alias.clone(),
+                        }),
+                    )
+                })
+                .collect();
+            columns.push((
+                total_count_alias.clone(),
+                Expression::WindowFunction {
+                    function: Box::new(Expression::Count(CountType::Star)),
+                    partition_by: vec![],
+                    order_by: empty_order_by(),
+                },
+            ));
+            row_columns
+        }
+        SelectList::SelectStar => vec![],
+    };
+
+    let counts = match &count_select.select_list {
+        SelectList::SelectList(columns) => columns
+            .iter()
+            .map(|(alias, expression)| {
+                let recount = Select {
+                    select_list: SelectList::SelectList(vec![(alias.clone(), expression.clone())]),
+                    ..count_select.clone()
+                };
+                (
+                    alias.name.clone(),
+                    Box::new(Expression::FunctionCall {
+                        function: Function::Coalesce,
+                        args: vec![
+                            Expression::FunctionCall {
+                                function: Function::Unknown("max".to_string()),
+                                args: vec![Expression::ColumnReference(
+                                    ColumnReference::AliasedColumn {
+                                        table: TableReference::AliasedTable(
+                                            count_table_alias.clone(),
+                                        ),
+                                        column: total_count_alias.clone(),
+                                    },
+                                )],
+                            },
+                            Expression::ScalarSelect(Box::new(recount)),
+                        ],
+                    }),
+                )
+            })
+            .collect(),
+        SelectList::SelectStar => BTreeMap::new(),
+    };
+
+    let mut select = simple_select(vec![
+        (
+            row_column_alias,
+            wrap_in_json_agg(Expression::RowToJson(TableReference::AliasedTable(
+                row_table_alias.clone(),
+            ))),
+        ),
+        (count_column_alias, Expression::JsonBuildObject(counts)),
+    ]);
+    select.from = Some(From::Select {
+        select: Box::new(row_select),
+        alias: count_table_alias,
+    });
+    select.joins = vec![Join::CrossJoinLateral(CrossJoin {
+        select: Box::new(simple_select(row_columns)),
+        alias: row_table_alias,
+    })];
+    select
+}
+
 /// Wrap a query that returns a single row in the following:
 ///
 /// ```sql
@@ -458,6 +614,27 @@ pub fn select_row_as_json_with_default(
     final_select
 }
 
+/// Given a select, select each of its rows as a json object.
+///
+/// ```sql
+/// SELECT
+///   row_to_json(<table_alias>) AS <column_alias>
+/// FROM <query> as <table_alias>
+/// ```
+pub fn select_each_row_as_json(
+    select: Select,
+    column_alias: ColumnAlias,
+    table_alias: TableAlias,
+) -> Select {
+    let expression = Expression::RowToJson(TableReference::AliasedTable(table_alias.clone()));
+    let mut final_select = simple_select(vec![(column_alias, expression)]);
+    final_select.from = Some(From::Select {
+        select: Box::new(select),
+        alias: table_alias,
+    });
+    final_select
+}
+
 /// Create a FROM clause for variables.
 ///
 /// Something of the form:
@@ -525,18 +702,30 @@ pub const VARIABLES_OBJECT_PLACEHOLDER: &str = "%VARIABLES_OBJECT_PLACEHOLDER";
 /// SQL field name to be used for ordering results with multiple variable sets.
 pub const VARIABLE_ORDER_FIELD: &str = "%variable_order";
 
+/// SQL field name of the count of all the rows a page of rows is taken from.
+pub const TOTAL_COUNT_FIELD: &str = "%total_count";
+
 pub fn mutation_begin() -> Vec<string::Statement> {
+    transaction_begin(Some(transaction::IsolationLevel::ReadCommitedReadWrite))
+}
+
+pub fn mutation_end() -> Vec<string::Statement> {
+    transaction_commit()
+}
+
+/// Statements to begin a transaction with the given isolation level, or the database's default.
+pub fn transaction_begin(
+    isolation_level: Option<transaction::IsolationLevel>,
+) -> Vec<string::Statement> {
     vec![{
         let mut sql = string::SQL::new();
-        transaction::Begin {
-            isolation_level: transaction::IsolationLevel::ReadCommitedReadWrite,
-        }
-        .to_sql(&mut sql);
+        transaction::Begin { isolation_level }.to_sql(&mut sql);
         string::Statement(sql)
     }]
 }
 
-pub fn mutation_end() -> Vec<string::Statement> {
+/// Statements to commit the current transaction.
+pub fn transaction_commit() -> Vec<string::Statement> {
     vec![{
         let mut sql = string::SQL::new();
         transaction::Commit {}.to_sql(&mut sql);
@@ -544,6 +733,13 @@ pub fn mutation_end() -> Vec<string::Statement> {
     }]
 }
 
+/// A statement to run the rest of the current transaction as the given role.
+pub fn set_local_role(role: String) -> string::Statement {
+    let mut sql = string::SQL::new();
+    transaction::SetLocalRole { role }.to_sql(&mut sql);
+    string::Statement(sql)
+}
+
 pub fn transaction_rollback() -> string::Statement {
     let mut sql = string::SQL::new();
     transaction::Rollback {}.to_sql(&mut sql);
@@ -150,6 +150,9 @@ pub fn normalize_expr(expr: Expression) -> Expression {
         Expression::Exists { select } => Expression::Exists {
             select: Box::new(normalize_select(*select)),
         },
+        Expression::ScalarSelect(select) => {
+            Expression::ScalarSelect(Box::new(normalize_select(*select)))
+        }
         // reverse not on literal bool.
         Expression::Not(expr) => match normalize_expr(*expr) {
             Expression::Value(Value::Bool(false)) => Expression::Value(Value::Bool(true)),
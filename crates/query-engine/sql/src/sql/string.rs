@@ -5,6 +5,22 @@
 pub struct SQL {
     pub sql: String,
     pub params: Vec<Param>,
+    /// Whether string parameters are written into the SQL as literals, for statements such as
+    /// `COPY` which cannot take parameters.
+    literal_params: bool,
+    /// Which identifiers are quoted.
+    identifier_quoting: IdentifierQuoting,
+}
+
+/// Which identifiers are quoted in the SQL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdentifierQuoting {
+    /// Quote every identifier.
+    #[default]
+    Always,
+    /// Only quote the identifiers which would not be read the same unquoted: those with other
+    /// characters than lowercase letters, digits and underscores, and keywords.
+    WhenNeeded,
 }
 
 impl Default for SQL {
@@ -31,6 +47,24 @@ impl SQL {
         SQL {
             sql: "".to_string(),
             params: vec![],
+            literal_params: false,
+            identifier_quoting: IdentifierQuoting::default(),
+        }
+    }
+    /// A builder which quotes identifiers as given.
+    pub fn with_identifier_quoting(identifier_quoting: IdentifierQuoting) -> SQL {
+        SQL {
+            identifier_quoting,
+            ..SQL::new()
+        }
+    }
+    /// A builder which writes string parameters into the SQL as literals rather than as `$1`,
+    /// `$2`, and so on. They are still recorded in `params`, but must not be bound. Variables
+    /// cannot be written as literals, and are left as placeholders.
+    pub fn with_literal_params(identifier_quoting: IdentifierQuoting) -> SQL {
+        SQL {
+            literal_params: true,
+            ..SQL::with_identifier_quoting(identifier_quoting)
         }
     }
     /// Append regular SQL syntax like a keyword (like `SELECT`), punctuation, etc.
@@ -38,19 +72,241 @@ impl SQL {
         self.sql.push_str(sql);
     }
     /// Append a SQL identifier like a column or a table name, which will be
-    /// inserted surrounded by quotes
+    /// inserted surrounded by quotes, unless it does not need them and the builder only quotes
+    /// identifiers when needed.
     pub fn append_identifier(&mut self, sql: &String) {
-        // todo: sanitize
-        self.sql.push_str(format!("\"{}\"", sql).as_str());
+        if self.identifier_quoting == IdentifierQuoting::WhenNeeded && !needs_quoting(sql) {
+            self.sql.push_str(sql);
+        } else {
+            // double quotes within a quoted identifier are written twice.
+            self.sql
+                .push_str(format!("\"{}\"", sql.replace('"', "\"\"")).as_str());
+        }
     }
     /// Append a parameter to a parameterized query. Will be represented as $1, $2, and so on,
     /// in the sql query text, and will be inserted to the `params` vector, so we can
     /// bind them later when we run the query.
     pub fn append_param(&mut self, param: Param) {
-        // we want the postgres param to start from 1
-        // so we first push the param and then check the length of the vector.
-        self.params.push(param);
-        self.sql
-            .push_str(format!("${}", self.params.len()).as_str());
+        match &param {
+            Param::String(string) if self.literal_params => {
+                // An escape string literal, which is read the same whatever the setting of
+                // 'standard_conforming_strings'.
+                self.sql.push_str("E'");
+                self.sql
+                    .push_str(&string.replace('\\', "\\\\").replace('\'', "''"));
+                self.sql.push('\'');
+                self.params.push(param);
+            }
+            _ => {
+                // we want the postgres param to start from 1
+                // so we first push the param and then check the length of the vector.
+                self.params.push(param);
+                self.sql
+                    .push_str(format!("${}", self.params.len()).as_str());
+            }
+        }
+    }
+}
+
+/// Whether an identifier has to be quoted to be read as it is: unquoted identifiers are folded to
+/// lowercase, may only hold letters, digits and underscores, and cannot be keywords.
+fn needs_quoting(identifier: &str) -> bool {
+    let mut chars = identifier.chars();
+    let is_plain = chars
+        .next()
+        .map_or(false, |first| first.is_ascii_lowercase() || first == '_')
+        && chars.all(|char| char.is_ascii_lowercase() || char.is_ascii_digit() || char == '_');
+    !is_plain || KEYWORDS.binary_search(&identifier).is_ok()
+}
+
+/// The keywords of PostgreSQL which cannot be used as identifiers everywhere without quoting them:
+/// the reserved ones, and those which can only be used as the names of some kinds of objects.
+/// The unreserved keywords can be used as identifiers anywhere. Sorted, to be searched.
+///
+/// Those of PostgreSQL 17, as listed by
+/// `SELECT word FROM pg_get_keywords() WHERE catcode <> 'U' ORDER BY word COLLATE "C"`.
+const KEYWORDS: &[&str] = &[
+    "all",
+    "analyse",
+    "analyze",
+    "and",
+    "any",
+    "array",
+    "as",
+    "asc",
+    "asymmetric",
+    "authorization",
+    "between",
+    "bigint",
+    "binary",
+    "bit",
+    "boolean",
+    "both",
+    "case",
+    "cast",
+    "char",
+    "character",
+    "check",
+    "coalesce",
+    "collate",
+    "collation",
+    "column",
+    "concurrently",
+    "constraint",
+    "create",
+    "cross",
+    "current_catalog",
+    "current_date",
+    "current_role",
+    "current_schema",
+    "current_time",
+    "current_timestamp",
+    "current_user",
+    "dec",
+    "decimal",
+    "default",
+    "deferrable",
+    "desc",
+    "distinct",
+    "do",
+    "else",
+    "end",
+    "except",
+    "exists",
+    "extract",
+    "false",
+    "fetch",
+    "float",
+    "for",
+    "foreign",
+    "freeze",
+    "from",
+    "full",
+    "grant",
+    "greatest",
+    "group",
+    "grouping",
+    "having",
+    "ilike",
+    "in",
+    "initially",
+    "inner",
+    "inout",
+    "int",
+    "integer",
+    "intersect",
+    "interval",
+    "into",
+    "is",
+    "isnull",
+    "join",
+    "json",
+    "json_array",
+    "json_arrayagg",
+    "json_exists",
+    "json_object",
+    "json_objectagg",
+    "json_query",
+    "json_scalar",
+    "json_serialize",
+    "json_table",
+    "json_value",
+    "lateral",
+    "leading",
+    "least",
+    "left",
+    "like",
+    "limit",
+    "localtime",
+    "localtimestamp",
+    "merge_action",
+    "national",
+    "natural",
+    "nchar",
+    "none",
+    "normalize",
+    "not",
+    "notnull",
+    "null",
+    "nullif",
+    "numeric",
+    "offset",
+    "on",
+    "only",
+    "or",
+    "order",
+    "out",
+    "outer",
+    "overlaps",
+    "overlay",
+    "placing",
+    "position",
+    "precision",
+    "primary",
+    "real",
+    "references",
+    "returning",
+    "right",
+    "row",
+    "select",
+    "session_user",
+    "setof",
+    "similar",
+    "smallint",
+    "some",
+    "substring",
+    "symmetric",
+    "system_user",
+    "table",
+    "tablesample",
+    "then",
+    "time",
+    "timestamp",
+    "to",
+    "trailing",
+    "treat",
+    "trim",
+    "true",
+    "union",
+    "unique",
+    "user",
+    "using",
+    "values",
+    "varchar",
+    "variadic",
+    "verbose",
+    "when",
+    "where",
+    "window",
+    "with",
+    "xmlattributes",
+    "xmlconcat",
+    "xmlelement",
+    "xmlexists",
+    "xmlforest",
+    "xmlnamespaces",
+    "xmlparse",
+    "xmlpi",
+    "xmlroot",
+    "xmlserialize",
+    "xmltable",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords_are_sorted() {
+        assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn keywords_are_quoted_when_needed() {
+        let mut sql = SQL::with_identifier_quoting(IdentifierQuoting::WhenNeeded);
+        sql.append_identifier(&"json_table".to_string());
+        sql.append_syntax(", ");
+        sql.append_identifier(&"album_id".to_string());
+        assert_eq!(sql.sql, r#""json_table", album_id"#);
     }
 }
@@ -10,6 +10,10 @@ pub enum Error {
     ColumnNotFoundInCollection(String, String),
     RelationshipNotFound(String),
     ArgumentNotFound(String),
+    AmbiguousArgument {
+        argument_name: String,
+        candidates: Vec<String>,
+    },
     OperatorNotFound {
         operator_name: String,
         type_name: database::ScalarType,
@@ -22,7 +26,21 @@ pub enum Error {
     MissingAggregateForArrayRelationOrdering,
     NoFields,
     TypeMismatch(serde_json::Value, database::ScalarType),
+    ArgumentTypeMismatch {
+        argument_name: String,
+        value: serde_json::Value,
+        r#type: database::Type,
+    },
+    InvalidRangeBounds {
+        value: serde_json::Value,
+        range_type: database::ScalarType,
+    },
+    InvalidBase64(String),
     UnexpectedVariable,
+    LimitWithTiesWithoutOrdering,
+    AsOfSystemTimeNotSupported,
+    InvalidInlineRows(String, serde_json::Value),
+    InvalidTableSample(serde_json::Value),
     CapabilityNotSupported(UnsupportedCapabilities),
     UnableToDeserializeNumberAsF64(serde_json::Number),
     UnableToSerializeJsonValueToString(String),
@@ -61,6 +79,21 @@ impl std::fmt::Display for Error {
             Error::ArgumentNotFound(argument) => {
                 write!(f, "Argument '{}' not found.", argument)
             }
+            Error::AmbiguousArgument {
+                argument_name,
+                candidates,
+            } => {
+                write!(
+                    f,
+                    "Argument '{}' is ambiguous, as it differs only in case from each of {}.",
+                    argument_name,
+                    candidates
+                        .iter()
+                        .map(|candidate| format!("'{candidate}'"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
             Error::OperatorNotFound {
                 operator_name,
                 type_name,
@@ -89,12 +122,63 @@ impl std::fmt::Display for Error {
             Error::TypeMismatch(value, typ) => {
                 write!(f, "Value '{:?}' is not of type '{:?}'.", value, typ)
             }
+            Error::ArgumentTypeMismatch {
+                argument_name,
+                value,
+                r#type,
+            } => {
+                write!(
+                    f,
+                    "Argument '{}' has value {} which is not compatible with its type {:?}.",
+                    argument_name, value, r#type
+                )
+            }
+            Error::InvalidRangeBounds { value, range_type } => {
+                write!(
+                    f,
+                    "Value {} does not describe a range of type {:?}. Expected an object with optional 'lower', 'upper' and 'bounds' fields, where the bounds are one of '[)', '[]', '(]' or '()'.",
+                    value, range_type
+                )
+            }
             Error::UnexpectedVariable => {
                 write!(
                     f,
                     "Unexpected variable in a query request which does not contain variables."
                 )
             }
+            Error::LimitWithTiesWithoutOrdering => {
+                write!(
+                    f,
+                    "Queries with a limit must be ordered when ties are kept with the last row."
+                )
+            }
+            Error::AsOfSystemTimeNotSupported => {
+                write!(
+                    f,
+                    "The 'as_of' argument is only supported on CockroachDB, which can read data as of a point in time."
+                )
+            }
+            Error::InvalidInlineRows(collection_name, value) => {
+                write!(
+                    f,
+                    "The 'rows' argument of '{}' must be a non-empty array of objects with its columns, not {}.",
+                    collection_name, value
+                )
+            }
+            Error::InvalidTableSample(value) => {
+                write!(
+                    f,
+                    "The 'sample' argument must be an object with a 'method' of 'system' or 'bernoulli' and a 'percentage' between 0 and 100, not {}.",
+                    value
+                )
+            }
+            Error::InvalidBase64(value) => {
+                write!(
+                    f,
+                    "Value '{}' is not valid base64. bytea values are given as base64 strings.",
+                    value
+                )
+            }
             Error::UnableToDeserializeNumberAsF64(num) => {
                 write!(f, "Unable to deserialize the number '{}' as f64.", num)
             }
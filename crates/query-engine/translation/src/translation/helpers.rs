@@ -1,6 +1,6 @@
 //! Helpers for processing requests and building SQL.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use ndc_sdk::models;
 
@@ -13,6 +13,120 @@ use query_engine_sql::sql;
 pub struct Env<'a> {
     metadata: &'a metadata::Metadata,
     relationships: BTreeMap<String, models::Relationship>,
+    options: QueryOptions,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Settings from the configuration which change how queries are translated.
+pub struct QueryOptions {
+    /// Keep the rows which sort equal to the last row within a limit, using
+    /// `FETCH FIRST n ROWS WITH TIES`. Limited queries must then be ordered.
+    pub limit_with_ties: bool,
+    /// The tables to select from with `FROM ONLY`, excluding the rows of their descendants.
+    pub exclude_inherited_rows: BTreeSet<String>,
+    /// The filters every query applies to the rows of each collection, whatever it asks for.
+    pub mandatory_filters: BTreeMap<String, MandatoryFilter>,
+    /// The columns to order the rows of each collection by when a query does not order them.
+    pub default_order_by: BTreeMap<String, Vec<DefaultOrderByColumn>>,
+    /// The collations to sort the columns of each collection by, rather than their own, by the
+    /// names of the columns.
+    pub order_by_collations: BTreeMap<String, BTreeMap<String, String>>,
+    /// Order the rows of tables by a uniqueness constraint after the columns a query orders them
+    /// by, so that the ordering is total.
+    pub deterministic_ordering: bool,
+    /// Match the arguments of native queries to their declared names regardless of case, when
+    /// no argument has exactly the name used.
+    pub case_insensitive_native_query_arguments: bool,
+    /// Check the types of the columns native queries return against their declared types before
+    /// running queries.
+    pub strict_native_query_column_types: bool,
+    /// Where the nulls go in orderings, when not where the direction puts them.
+    pub null_ordering: Option<sql::ast::NullsOrder>,
+    /// How the values of `interval` columns are returned.
+    pub interval_representation: IntervalRepresentation,
+    /// How the values of `int8` and `numeric` columns, and of aggregates which return such
+    /// values, are returned.
+    pub big_number_representation: BigNumberRepresentation,
+    /// The longest SQL, in bytes, a request may be translated to, which the execution plan
+    /// checks once the SQL is rendered. Longer requests are rejected rather than sent to the
+    /// database.
+    pub max_sql_length: Option<usize>,
+    /// The case the labels of enum types are exposed in, which filters give them in.
+    pub enum_value_case: Option<EnumValueCase>,
+    /// Whether the database supports `AS OF SYSTEM TIME`, as CockroachDB does, so that queries
+    /// may read the data as of a point in time with the 'as_of' argument.
+    pub as_of_system_time: bool,
+    /// Which identifiers are quoted in the generated SQL.
+    pub identifier_quoting: sql::string::IdentifierQuoting,
+    /// The indexes to make the planner scan each collection with, by collection name.
+    pub index_hints: BTreeMap<String, Vec<String>>,
+    /// The limit of the rows of queries which do not give one.
+    pub default_limit: Option<u32>,
+    /// Compare the values of the scalar types nothing is known about as text, which they are
+    /// exposed as.
+    pub unknown_scalar_types_as_text: bool,
+}
+
+/// The extension which reads the hints of queries, which are left out when it is not installed.
+pub const PG_HINT_PLAN_EXTENSION: &str = "pg_hint_plan";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A filter on the rows of a collection: only the rows whose column equals a setting of the
+/// database session are seen.
+pub struct MandatoryFilter {
+    /// The name of the column in the collection.
+    pub column: String,
+    /// The setting to compare the column to, such as `app.tenant_id`.
+    pub setting: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How the values of `interval` columns are returned.
+pub enum IntervalRepresentation {
+    /// As the database writes them out, such as `1 year 2 mons 03:04:05`.
+    #[default]
+    PostgresText,
+    /// As ISO 8601 durations, such as `P1Y2M0DT3H4M5S`.
+    Iso8601,
+    /// As objects of the months, days and microseconds an interval is made of, which are kept
+    /// apart because the length of a month or a day in time varies.
+    Object,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How the values of `int8` and `numeric` columns are returned.
+pub enum BigNumberRepresentation {
+    /// As JSON numbers, which clients may read as doubles and so round beyond 2^53.
+    #[default]
+    Number,
+    /// As strings of their digits, which keep their precision.
+    String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The case the labels of enum types are exposed in.
+pub enum EnumValueCase {
+    Upper,
+    Lower,
+}
+
+impl EnumValueCase {
+    /// Convert a label to this case.
+    pub fn apply(self, label: &str) -> String {
+        match self {
+            EnumValueCase::Upper => label.to_uppercase(),
+            EnumValueCase::Lower => label.to_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A column of the default ordering of a collection.
+pub struct DefaultOrderByColumn {
+    pub column: String,
+    pub direction: models::OrderDirection,
+    /// Where the nulls go, when not where the direction puts them.
+    pub nulls: Option<sql::ast::NullsOrder>,
 }
 
 #[derive(Debug)]
@@ -21,6 +135,7 @@ pub struct State {
     native_queries: NativeQueries,
     global_table_index: TableAliasIndex,
     variables_table: Option<sql::ast::TableReference>,
+    index_hints: Vec<sql::ast::IndexHint>,
 }
 
 #[derive(Debug)]
@@ -39,6 +154,7 @@ struct NativeQueries {
 #[derive(Debug)]
 /// Information we store about a native query call.
 pub struct NativeQueryInfo {
+    pub name: String,
     pub info: metadata::NativeQueryInfo,
     pub arguments: BTreeMap<String, models::Argument>,
     pub alias: sql::ast::TableAlias,
@@ -85,8 +201,17 @@ pub enum CollectionInfo {
         name: String,
         info: metadata::NativeQueryInfo,
     },
+    /// Rows given in the request, as the 'rows' argument of an inline rows collection, rather
+    /// than read from the database.
+    InlineRows {
+        name: String,
+        info: metadata::InlineRowsInfo,
+    },
 }
 
+/// The argument of inline rows collections which gives their rows, as an array of objects.
+pub const INLINE_ROWS_ARGUMENT: &str = "rows";
+
 impl<'a> Env<'a> {
     /// Create a new Env by supplying the metadata and relationships.
     pub fn new(
@@ -96,8 +221,65 @@ impl<'a> Env<'a> {
         Env {
             metadata,
             relationships,
+            options: QueryOptions::default(),
         }
     }
+
+    /// Translate queries with these options rather than the default ones.
+    pub fn with_options(self, options: QueryOptions) -> Env<'a> {
+        Env { options, ..self }
+    }
+
+    /// The options queries are translated with.
+    pub fn options(&self) -> &QueryOptions {
+        &self.options
+    }
+
+    /// Whether the values of a scalar type are compared as text, because nothing is known about
+    /// it and it is exposed as text.
+    pub fn compares_as_text(&self, scalar_type: &metadata::ScalarType) -> bool {
+        self.options.unknown_scalar_types_as_text
+            && self.metadata.is_unknown_scalar_type(scalar_type)
+    }
+
+    /// Translate the labels of enum types in a filter value from the case they are exposed in back
+    /// to the labels of the database. Other values, and labels which match none of the type, are
+    /// left alone.
+    pub fn enum_labels_to_database(
+        &self,
+        value: serde_json::Value,
+        r#type: &metadata::Type,
+    ) -> serde_json::Value {
+        let Some(enum_value_case) = self.options.enum_value_case else {
+            return value;
+        };
+        match (value, r#type) {
+            (serde_json::Value::String(label), metadata::Type::ScalarType(scalar_type)) => {
+                let database_label = self
+                    .metadata
+                    .enum_types
+                    .0
+                    .get(scalar_type)
+                    .and_then(|labels| {
+                        labels
+                            .iter()
+                            .find(|database_label| enum_value_case.apply(database_label) == label)
+                    })
+                    .cloned();
+                serde_json::Value::String(database_label.unwrap_or(label))
+            }
+            (serde_json::Value::Array(values), metadata::Type::ArrayType(element_type)) => {
+                serde_json::Value::Array(
+                    values
+                        .into_iter()
+                        .map(|value| self.enum_labels_to_database(value, element_type))
+                        .collect(),
+                )
+            }
+            (value, _) => value,
+        }
+    }
+
     /// Lookup a collection's information in the metadata.
     pub fn lookup_collection(&self, collection_name: &str) -> Result<CollectionInfo, Error> {
         let table = self
@@ -121,6 +303,16 @@ impl<'a> Env<'a> {
                     name: collection_name.to_string(),
                     info: nq.clone(),
                 })
+                .or_else(|| {
+                    self.metadata
+                        .inline_rows
+                        .0
+                        .get(collection_name)
+                        .map(|info| CollectionInfo::InlineRows {
+                            name: collection_name.to_string(),
+                            info: info.clone(),
+                        })
+                })
                 .ok_or(Error::CollectionNotFound(collection_name.to_string())),
         }
     }
@@ -159,6 +351,31 @@ impl<'a> Env<'a> {
                 type_name: scalar_type.clone(),
             })
     }
+
+    /// Looks up the type an aggregate function of a scalar type returns, which is that of the
+    /// standard aggregate of that name when introspection did not find the function.
+    pub fn lookup_aggregate_return_type(
+        &self,
+        scalar_type: &metadata::ScalarType,
+        name: &str,
+    ) -> Option<metadata::ScalarType> {
+        self.metadata
+            .aggregate_functions
+            .return_type(scalar_type, name)
+    }
+
+    /// Lookup a quantified comparison operator of a scalar type, such as '_gt_all', along with
+    /// its quantifier.
+    pub fn lookup_quantified_comparison_operator(
+        &self,
+        scalar_type: &metadata::ScalarType,
+        name: &str,
+    ) -> Option<(metadata::Quantifier, &'a metadata::ComparisonOperator)> {
+        self.metadata
+            .comparison_operators
+            .quantified(scalar_type)
+            .remove(name)
+    }
 }
 
 impl CollectionInfo {
@@ -172,6 +389,18 @@ impl CollectionInfo {
                     name: sql::ast::ColumnName(column_info.name.clone()),
                     r#type: column_info.r#type.clone(),
                 })
+                // Search rank fields are computed as columns of the table, named after them.
+                .or_else(|| {
+                    info.search_rank_fields
+                        .0
+                        .contains_key(column_name)
+                        .then(|| ColumnInfo {
+                            name: sql::ast::ColumnName(column_name.to_string()),
+                            r#type: metadata::Type::ScalarType(metadata::ScalarType(
+                                "float4".to_string(),
+                            )),
+                        })
+                })
                 .ok_or(Error::ColumnNotFoundInCollection(
                     column_name.to_string(),
                     name.clone(),
@@ -187,6 +416,33 @@ impl CollectionInfo {
                     column_name.to_string(),
                     name.clone(),
                 )),
+            CollectionInfo::InlineRows { name, info } => info
+                .columns
+                .get(column_name)
+                .map(|column_info| ColumnInfo {
+                    name: sql::ast::ColumnName(column_info.name.clone()),
+                    r#type: column_info.r#type.clone(),
+                })
+                .ok_or(Error::ColumnNotFoundInCollection(
+                    column_name.to_string(),
+                    name.clone(),
+                )),
+        }
+    }
+
+    /// Lookup a computed field of a collection. Only tables have them.
+    pub fn lookup_computed_field(&self, field_name: &str) -> Option<&metadata::ComputedField> {
+        match self {
+            CollectionInfo::Table { info, .. } => info.computed_fields.0.get(field_name),
+            CollectionInfo::NativeQuery { .. } | CollectionInfo::InlineRows { .. } => None,
+        }
+    }
+
+    /// Lookup a ranking field of a collection. Only tables have them.
+    pub fn lookup_ranking_field(&self, field_name: &str) -> Option<&metadata::RankingField> {
+        match self {
+            CollectionInfo::Table { info, .. } => info.ranking_fields.0.get(field_name),
+            CollectionInfo::NativeQuery { .. } | CollectionInfo::InlineRows { .. } => None,
         }
     }
 }
@@ -197,6 +453,7 @@ impl Default for State {
             native_queries: NativeQueries::new(),
             global_table_index: TableAliasIndex(0),
             variables_table: None,
+            index_hints: vec![],
         }
     }
 }
@@ -246,6 +503,7 @@ impl State {
     ) -> sql::ast::TableReference {
         let alias = self.make_native_query_table_alias(&name);
         self.native_queries.native_queries.push(NativeQueryInfo {
+            name,
             info,
             arguments,
             alias: alias.clone(),
@@ -253,6 +511,33 @@ impl State {
         sql::ast::TableReference::AliasedTable(alias)
     }
 
+    /// Hint the planner to scan the table of a collection under this alias with the indexes
+    /// configured for the collection, if there are any and the extension which reads hints is
+    /// installed.
+    pub fn add_index_hint(
+        &mut self,
+        env: &Env,
+        collection_name: &str,
+        table: &sql::ast::TableAlias,
+    ) {
+        if let Some(indexes) = env.options.index_hints.get(collection_name) {
+            if env.metadata.extensions.contains(PG_HINT_PLAN_EXTENSION) {
+                self.index_hints.push(sql::ast::IndexHint {
+                    table: table.clone(),
+                    indexes: indexes
+                        .iter()
+                        .map(|index| sql::ast::IndexName(index.clone()))
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    /// Take the index hints for the tables of the query plan.
+    pub fn take_index_hints(&mut self) -> Vec<sql::ast::IndexHint> {
+        std::mem::take(&mut self.index_hints)
+    }
+
     /// Fetch the tracked native queries used in the query plan and their table alias.
     pub fn get_native_queries(self) -> Vec<NativeQueryInfo> {
         self.native_queries.native_queries
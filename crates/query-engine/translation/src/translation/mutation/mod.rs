@@ -6,17 +6,18 @@ use std::collections::BTreeMap;
 use ndc_sdk::models;
 
 use crate::translation::error::Error;
-use crate::translation::helpers::{Env, State, TableNameAndReference};
+use crate::translation::helpers::{Env, QueryOptions, State, TableNameAndReference};
 use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
 /// Translate the incoming MutationOperation to an ExecutionPlan (SQL) to be run against the database.
 pub fn translate(
     metadata: &metadata::Metadata,
+    options: QueryOptions,
     operation: models::MutationOperation,
     collection_relationships: BTreeMap<String, models::Relationship>,
 ) -> Result<sql::execution_plan::Mutation, Error> {
-    let env = Env::new(metadata, collection_relationships);
+    let env = Env::new(metadata, collection_relationships).with_options(options);
     let mut state = State::new();
 
     match operation {
@@ -41,6 +42,22 @@ pub fn translate(
                 })
                 .collect();
 
+            // a procedure without columns has nothing to return, so it cannot be selected from.
+            // run it as a statement instead, and use the affected row count the database reports.
+            if procedure.columns.is_empty() {
+                let statement = crate::translation::query::native_queries::translate_sql(
+                    &env,
+                    procedure.clone(),
+                    &arguments,
+                    &state.get_variables_table(),
+                )?;
+
+                return Ok(sql::execution_plan::Mutation {
+                    root_field: name,
+                    query: sql::execution_plan::MutationQuery::Statement(statement),
+                });
+            }
+
             // insert the procedure as a native query and get a reference to it.
             let table_reference =
                 state.insert_native_query(name.clone(), procedure.clone(), arguments);
@@ -50,6 +67,8 @@ pub fn translate(
             let from_clause = sql::ast::From::Table {
                 reference: table_reference.clone(),
                 alias: table_alias.clone(),
+                only: false,
+                sample: None,
             };
 
             // define the query selecting from the native query,
@@ -77,6 +96,7 @@ pub fn translate(
                 &current_table,
                 &from_clause,
                 &query,
+                None,
             )?;
 
             // affected rows
@@ -106,7 +126,7 @@ pub fn translate(
             // add the procedure native query definition is a with clause.
             select.with = sql::ast::With {
                 common_table_expressions: crate::translation::query::native_queries::translate(
-                    state,
+                    &env, state,
                 )?,
             };
 
@@ -115,7 +135,7 @@ pub fn translate(
 
             Ok(sql::execution_plan::Mutation {
                 root_field: name.clone(),
-                query: select,
+                query: sql::execution_plan::MutationQuery::Select(select),
             })
         }
     }
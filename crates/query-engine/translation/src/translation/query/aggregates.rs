@@ -5,12 +5,30 @@ use indexmap::IndexMap;
 use ndc_sdk::models;
 
 use crate::translation::error::Error;
+use crate::translation::helpers::{BigNumberRepresentation, CollectionInfo, Env};
+use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
+/// The aggregate functions which collect the rows they read in order, and so take the ordering
+/// of the query into account.
+const ORDERED_AGGREGATE_FUNCTIONS: [&str; 3] = ["array_agg", "json_agg", "jsonb_agg"];
+
 /// Translate any aggregates we should include in the query into our SQL AST.
+///
+/// Column counts translate to `COUNT(column)`, or `COUNT(DISTINCT column)` when `distinct` is set.
+/// In both cases null values are not counted.
+///
+/// Aggregates which collect values into an array, such as `array_agg`, collect them in the order
+/// of the query, if any: `array_agg(column ORDER BY ...)`.
+///
+/// Aggregates which return `int8` or `numeric` values, such as the sum of an `int8` column, are
+/// returned as strings when such values are configured to be, like the values of columns.
 pub fn translate(
+    env: &Env,
+    collection_info: &CollectionInfo,
     table: &sql::ast::TableReference,
     aggregates: IndexMap<String, models::Aggregate>,
+    order_by: &sql::ast::OrderBy,
 ) -> Result<Vec<(sql::ast::ColumnAlias, sql::ast::Expression)>, Error> {
     aggregates
         .into_iter()
@@ -35,14 +53,34 @@ pub fn translate(
                     }
                 }
                 models::Aggregate::SingleColumn { column, function } => {
-                    sql::ast::Expression::FunctionCall {
-                        function: sql::ast::Function::Unknown(function),
-                        args: vec![sql::ast::Expression::ColumnReference(
-                            sql::ast::ColumnReference::AliasedColumn {
-                                table: table.clone(),
-                                column: sql::helpers::make_column_alias(column),
-                            },
-                        )],
+                    let args = vec![sql::ast::Expression::ColumnReference(
+                        sql::ast::ColumnReference::AliasedColumn {
+                            table: table.clone(),
+                            column: sql::helpers::make_column_alias(column.clone()),
+                        },
+                    )];
+                    let as_string = returns_big_number(env, collection_info, &column, &function)?;
+                    let aggregate = if ORDERED_AGGREGATE_FUNCTIONS.contains(&function.as_str())
+                        && !order_by.elements.is_empty()
+                    {
+                        sql::ast::Expression::OrderedAggregate {
+                            function: sql::ast::Function::Unknown(function),
+                            args,
+                            order_by: order_by.clone(),
+                        }
+                    } else {
+                        sql::ast::Expression::FunctionCall {
+                            function: sql::ast::Function::Unknown(function),
+                            args,
+                        }
+                    };
+                    if as_string {
+                        sql::ast::Expression::Cast {
+                            expression: Box::new(aggregate),
+                            r#type: sql::ast::ScalarType("text".to_string()),
+                        }
+                    } else {
+                        aggregate
                     }
                 }
                 models::Aggregate::StarCount {} => {
@@ -53,3 +91,36 @@ pub fn translate(
         })
         .collect::<Result<Vec<_>, Error>>()
 }
+
+/// Whether an aggregate function of a column returns `int8` or `numeric` values, and these are
+/// returned as strings.
+fn returns_big_number(
+    env: &Env,
+    collection_info: &CollectionInfo,
+    column: &str,
+    function: &str,
+) -> Result<bool, Error> {
+    if env.options().big_number_representation != BigNumberRepresentation::String {
+        return Ok(false);
+    }
+    match collection_info.lookup_column(column)?.r#type {
+        metadata::Type::ScalarType(scalar_type) => Ok(env
+            .lookup_aggregate_return_type(&scalar_type, function)
+            .is_some_and(|return_type| matches!(return_type.0.as_str(), "int8" | "numeric"))),
+        metadata::Type::ArrayType(_) | metadata::Type::CompositeType(_) => Ok(false),
+    }
+}
+
+/// Whether a query asks for star counts and nothing else among its aggregates, which can be
+/// counted along with its rows.
+pub fn are_only_star_counts(aggregates: &Option<IndexMap<String, models::Aggregate>>) -> bool {
+    match aggregates {
+        Some(aggregates) => {
+            !aggregates.is_empty()
+                && aggregates
+                    .values()
+                    .all(|aggregate| matches!(aggregate, models::Aggregate::StarCount {}))
+        }
+        None => false,
+    }
+}
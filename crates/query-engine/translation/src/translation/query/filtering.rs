@@ -70,29 +70,74 @@ pub fn translate_expression(
             value,
         } => {
             let mut joins = vec![];
-            let left_typ = get_comparison_target_type(env, root_and_current_tables, column)?;
+            let left_typ = get_comparison_target_column_type(env, root_and_current_tables, column)?;
             let (left, left_joins) =
                 translate_comparison_target(env, state, root_and_current_tables, column)?;
-            let (op, argument_type) =
-                operators::translate_comparison_operator(env, &left_typ, operator)?;
-            let (right, right_joins) = translate_comparison_value(
-                env,
-                state,
-                root_and_current_tables,
-                value.clone(),
-                &argument_type,
-            )?;
-
             joins.extend(left_joins);
-            joins.extend(right_joins);
-            Ok((
-                sql::ast::Expression::BinaryOperation {
-                    left: Box::new(left),
-                    operator: op,
-                    right: Box::new(right),
-                },
-                joins,
-            ))
+
+            match left_typ {
+                database::Type::ScalarType(left_typ) => {
+                    let (left, left_typ) = compare_as_text(env, left, left_typ);
+                    if let Some((op, element_type, collation, quantifier)) =
+                        operators::translate_quantified_comparison_operator(
+                            env, &left_typ, operator,
+                        )
+                    {
+                        let (right, right_joins) = translate_comparison_value(
+                            env,
+                            state,
+                            root_and_current_tables,
+                            value.clone(),
+                            &database::Type::ArrayType(Box::new(database::Type::ScalarType(
+                                element_type,
+                            ))),
+                        )?;
+                        joins.extend(right_joins);
+                        return Ok((
+                            sql::ast::Expression::BinaryOperation {
+                                left: Box::new(operators::collate(left, collation)),
+                                operator: op,
+                                right: Box::new(operators::quantify(quantifier, right)),
+                            },
+                            joins,
+                        ));
+                    }
+                    let (op, argument_type, collation) =
+                        operators::translate_comparison_operator(env, &left_typ, operator)?;
+                    let (right, right_joins) = translate_comparison_value(
+                        env,
+                        state,
+                        root_and_current_tables,
+                        value.clone(),
+                        &database::Type::ScalarType(argument_type),
+                    )?;
+                    joins.extend(right_joins);
+                    Ok((
+                        sql::ast::Expression::BinaryOperation {
+                            left: Box::new(operators::collate(left, collation)),
+                            operator: op,
+                            right: Box::new(right),
+                        },
+                        joins,
+                    ))
+                }
+                database::Type::ArrayType(_) => {
+                    let (op, argument_type) =
+                        operators::translate_array_comparison_operator(&left_typ, operator)?;
+                    let (right, right_joins) = translate_comparison_value(
+                        env,
+                        state,
+                        root_and_current_tables,
+                        value.clone(),
+                        &database::Type::ScalarType(argument_type),
+                    )?;
+                    joins.extend(right_joins);
+                    Ok((op.apply(left, right), joins))
+                }
+                database::Type::CompositeType(_) => {
+                    Err(Error::NonScalarTypeUsedInOperator { r#type: left_typ })
+                }
+            }
         }
         models::Expression::BinaryArrayComparisonOperator {
             column,
@@ -104,6 +149,7 @@ pub fn translate_expression(
             let (left, left_joins) =
                 translate_comparison_target(env, state, root_and_current_tables, column)?;
             joins.extend(left_joins);
+            let (left, typ) = compare_as_text(env, left, typ);
             let right = values
                 .iter()
                 .map(|value| {
@@ -112,7 +158,7 @@ pub fn translate_expression(
                         state,
                         root_and_current_tables,
                         value.clone(),
-                        &typ,
+                        &database::Type::ScalarType(typ.clone()),
                     )?;
                     joins.extend(right_joins);
                     Ok(right)
@@ -163,6 +209,26 @@ pub fn translate_expression(
     }
 }
 
+/// Cast the values of a scalar type which are compared as text to text, so that they are compared
+/// with the operators of text and to values given as text.
+fn compare_as_text(
+    env: &Env,
+    left: sql::ast::Expression,
+    left_typ: database::ScalarType,
+) -> (sql::ast::Expression, database::ScalarType) {
+    if env.compares_as_text(&left_typ) {
+        (
+            sql::ast::Expression::Cast {
+                expression: Box::new(left),
+                r#type: sql::ast::ScalarType("text".to_string()),
+            },
+            database::ScalarType("text".to_string()),
+        )
+    } else {
+        (left, left_typ)
+    }
+}
+
 /// Given a vector of PathElements and the table alias for the table the
 /// expression is over, we return a join in the form of:
 ///
@@ -337,22 +403,18 @@ fn translate_comparison_value(
     state: &mut State,
     root_and_current_tables: &RootAndCurrentTables,
     value: models::ComparisonValue,
-    typ: &database::ScalarType,
+    typ: &database::Type,
 ) -> Result<(sql::ast::Expression, Vec<sql::ast::Join>), Error> {
     match value {
         models::ComparisonValue::Column { column } => {
             translate_comparison_target(env, state, root_and_current_tables, &column)
         }
-        models::ComparisonValue::Scalar { value: json_value } => Ok((
-            values::translate_json_value(&json_value, &database::Type::ScalarType(typ.clone()))?,
-            vec![],
-        )),
+        models::ComparisonValue::Scalar { value: json_value } => {
+            let json_value = env.enum_labels_to_database(json_value, typ);
+            Ok((values::translate_json_value(&json_value, typ)?, vec![]))
+        }
         models::ComparisonValue::Variable { name: var } => Ok((
-            values::translate_variable(
-                state.get_variables_table()?,
-                var.clone(),
-                &database::Type::ScalarType(typ.clone()),
-            ),
+            values::translate_variable(state.get_variables_table()?, var.clone(), typ),
             vec![],
         )),
     }
@@ -507,46 +569,35 @@ fn get_comparison_target_type(
     root_and_current_tables: &RootAndCurrentTables,
     column: &models::ComparisonTarget,
 ) -> Result<database::ScalarType, Error> {
-    match column {
-        models::ComparisonTarget::RootCollectionColumn { name } => {
-            let column = env
-                .lookup_collection(&root_and_current_tables.root_table.name)?
-                .lookup_column(name)?;
-
-            match column.r#type {
-                database::Type::ScalarType(scalar_type) => Ok(scalar_type),
-                database::Type::ArrayType(_) => Err(Error::NonScalarTypeUsedInOperator {
-                    r#type: column.r#type,
-                }),
-            }
+    match get_comparison_target_column_type(env, root_and_current_tables, column)? {
+        database::Type::ScalarType(scalar_type) => Ok(scalar_type),
+        r#type @ (database::Type::ArrayType(_) | database::Type::CompositeType(_)) => {
+            Err(Error::NonScalarTypeUsedInOperator { r#type })
         }
+    }
+}
+
+/// Extract the type of a comparison target, which may be an array.
+fn get_comparison_target_column_type(
+    env: &Env,
+    root_and_current_tables: &RootAndCurrentTables,
+    column: &models::ComparisonTarget,
+) -> Result<database::Type, Error> {
+    let column = match column {
+        models::ComparisonTarget::RootCollectionColumn { name } => env
+            .lookup_collection(&root_and_current_tables.root_table.name)?
+            .lookup_column(name)?,
         models::ComparisonTarget::Column { name, path } => match path.last() {
-            None => {
-                let column = env
-                    .lookup_collection(&root_and_current_tables.current_table.name)?
-                    .lookup_column(name)?;
-
-                match column.r#type {
-                    database::Type::ScalarType(scalar_type) => Ok(scalar_type),
-                    database::Type::ArrayType(_) => Err(Error::NonScalarTypeUsedInOperator {
-                        r#type: column.r#type,
-                    }),
-                }
-            }
-            Some(last) => {
-                let column = env
-                    .lookup_collection(
-                        &env.lookup_relationship(&last.relationship)?
-                            .target_collection,
-                    )?
-                    .lookup_column(name)?;
-                match column.r#type {
-                    database::Type::ScalarType(scalar_type) => Ok(scalar_type),
-                    database::Type::ArrayType(_) => Err(Error::NonScalarTypeUsedInOperator {
-                        r#type: column.r#type,
-                    }),
-                }
-            }
+            None => env
+                .lookup_collection(&root_and_current_tables.current_table.name)?
+                .lookup_column(name)?,
+            Some(last) => env
+                .lookup_collection(
+                    &env.lookup_relationship(&last.relationship)?
+                        .target_collection,
+                )?
+                .lookup_column(name)?,
         },
-    }
+    };
+    Ok(column.r#type)
 }
@@ -9,19 +9,30 @@ pub mod root;
 mod sorting;
 mod values;
 
+use std::collections::BTreeMap;
+
 use ndc_sdk::models;
 
 use crate::translation::error::Error;
-use crate::translation::helpers::{Env, State, TableNameAndReference};
+use crate::translation::helpers::{
+    CollectionInfo, Env, QueryOptions, State, TableNameAndReference,
+};
 use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
+/// The argument of a table which reads its data as of a point in time, on CockroachDB.
+pub const AS_OF_ARGUMENT: &str = "as_of";
+
+/// The argument of a table which only reads a sample of its rows, with `TABLESAMPLE`.
+pub const SAMPLE_ARGUMENT: &str = "sample";
+
 /// Translate the incoming QueryRequest to an ExecutionPlan (SQL) to be run against the database.
 pub fn translate(
     metadata: &metadata::Metadata,
+    options: QueryOptions,
     query_request: models::QueryRequest,
 ) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>, Error> {
-    let env = Env::new(metadata, query_request.collection_relationships);
+    let env = Env::new(metadata, query_request.collection_relationships).with_options(options);
     let mut state = State::new();
     let variables_from = state.make_variables_table(&query_request.variables);
     let (current_table, from_clause) = root::make_from_clause_and_reference(
@@ -32,14 +43,26 @@ pub fn translate(
         None,
     )?;
 
+    let count_with_rows = aggregates::are_only_star_counts(&query_request.query.aggregates);
+    // only the root query is limited by default, not the arrays of related rows nested in it.
     let select_set = translate_query(
         &env,
         &mut state,
         &current_table,
         &from_clause,
         query_request.query,
+        env.options().default_limit,
     )?;
 
+    // when only the total count is asked for along with a page of rows,
+    // count the rows with a window function rather than scanning them twice.
+    let select_set = match select_set {
+        sql::helpers::SelectSet::RowsAndAggregates(rows, aggregates) if count_with_rows => {
+            sql::helpers::SelectSet::RowsAndTotalCount(rows, aggregates)
+        }
+        select_set => select_set,
+    };
+
     // form a single JSON item shaped `{ rows: [], aggregates: {} }`
     // that matches the models::RowSet type
     let mut json_select = sql::helpers::select_rowset(
@@ -59,29 +82,142 @@ pub fn translate(
         select_set,
     );
 
+    let index_hints = state.take_index_hints();
+
     // add native queries if there are any
+    let (common_table_expressions, native_query_column_types) =
+        native_queries::translate_with_column_types(&env, state)?;
     json_select.with = sql::ast::With {
-        common_table_expressions: native_queries::translate(state)?,
+        common_table_expressions,
     };
 
     // normalize ast
-    let json_select = sql::rewrites::constant_folding::normalize_select(json_select);
+    let mut json_select = sql::rewrites::constant_folding::normalize_select(json_select);
+
+    // read the data as of a point in time, which only the top-level select may ask for
+    json_select.as_of_system_time =
+        translate_as_of_system_time(&env, &query_request.collection, &query_request.arguments)?;
 
-    Ok(sql::execution_plan::simple_query_execution_plan(
+    let mut plan = sql::execution_plan::simple_query_execution_plan(
         query_request.variables,
         query_request.collection,
         json_select,
-    ))
+    );
+    plan.query.native_query_column_types = native_query_column_types;
+    plan.query.identifier_quoting = env.options().identifier_quoting;
+    plan.query.index_hints = index_hints;
+    plan.max_sql_length = env.options().max_sql_length;
+    Ok(plan)
+}
+
+/// Translate the 'as_of' argument of a table, if it is given, to the point in time to read the
+/// data as of, such as '-10s' or a timestamp. Only CockroachDB supports this; the arguments of
+/// native queries are left to them.
+fn translate_as_of_system_time(
+    env: &Env,
+    collection_name: &str,
+    arguments: &BTreeMap<String, models::Argument>,
+) -> Result<Option<sql::ast::Expression>, Error> {
+    let Some(argument) = arguments.get(AS_OF_ARGUMENT) else {
+        return Ok(None);
+    };
+    if let CollectionInfo::NativeQuery { .. } = env.lookup_collection(collection_name)? {
+        return Ok(None);
+    }
+    if !env.options().as_of_system_time {
+        return Err(Error::AsOfSystemTimeNotSupported);
+    }
+    match argument {
+        models::Argument::Literal {
+            value: serde_json::Value::String(time),
+        } => Ok(Some(sql::ast::Expression::Value(sql::ast::Value::String(
+            time.clone(),
+        )))),
+        models::Argument::Literal { value } => Err(Error::ArgumentTypeMismatch {
+            argument_name: AS_OF_ARGUMENT.to_string(),
+            value: value.clone(),
+            r#type: metadata::Type::ScalarType(metadata::ScalarType("text".to_string())),
+        }),
+        models::Argument::Variable { .. } => Err(Error::NotImplementedYet(
+            "variables as the 'as_of' argument".to_string(),
+        )),
+    }
+}
+
+/// Translate the incoming QueryRequest to an export of the rows it selects, one row per row of
+/// the collection rather than a single JSON response. Only the fields of the rows are exported,
+/// and variables are not supported.
+pub fn translate_export(
+    metadata: &metadata::Metadata,
+    options: QueryOptions,
+    query_request: models::QueryRequest,
+    format: sql::ast::CopyFormat,
+) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Export>, Error> {
+    if query_request.variables.is_some() {
+        return Err(Error::NotImplementedYet("variables".to_string()));
+    }
+    let env = Env::new(metadata, query_request.collection_relationships).with_options(options);
+    let mut state = State::new();
+    let (current_table, from_clause) = root::make_from_clause_and_reference(
+        &query_request.collection,
+        &query_request.arguments,
+        &env,
+        &mut state,
+        None,
+    )?;
+
+    let rows_select = root::translate_rows_query(
+        &env,
+        &mut state,
+        &current_table,
+        &from_clause,
+        &query_request.query,
+        env.options().default_limit,
+    )?;
+
+    // each row is exported as a json object in its own line, or as the columns of a CSV line.
+    let mut export_select = match format {
+        sql::ast::CopyFormat::Lines => sql::helpers::select_each_row_as_json(
+            rows_select,
+            sql::helpers::make_column_alias("row".to_string()),
+            state.make_table_alias("export".to_string()),
+        ),
+        sql::ast::CopyFormat::Csv => rows_select,
+    };
+
+    // add native queries if there are any
+    let (common_table_expressions, _) = native_queries::translate_with_column_types(&env, state)?;
+    export_select.with = sql::ast::With {
+        common_table_expressions,
+    };
+
+    // normalize ast
+    let export_select = sql::rewrites::constant_folding::normalize_select(export_select);
+
+    let plan = sql::execution_plan::ExecutionPlan {
+        pre: vec![],
+        query: sql::execution_plan::Export {
+            root_field: query_request.collection,
+            query: export_select,
+            format,
+            identifier_quoting: env.options().identifier_quoting,
+        },
+        post: vec![],
+        max_sql_length: env.options().max_sql_length,
+    };
+    Ok(plan)
 }
 
 /// Translate a query to sql ast.
 /// We return a SELECT for the 'rows' field and a SELECT for the 'aggregates' field.
+/// The rows are limited to the default limit given if the query gives no limit of its own.
 pub fn translate_query(
     env: &Env,
     state: &mut State,
     current_table: &TableNameAndReference,
     from_clause: &sql::ast::From,
     query: models::Query,
+    default_limit: Option<u32>,
 ) -> Result<sql::helpers::SelectSet, Error> {
     // Error::NoFields becomes Ok(None)
     // everything stays Err
@@ -94,9 +230,15 @@ pub fn translate_query(
     let wrap_ok = |a| Ok(Some(a));
 
     // translate rows query. if there are no fields, make this a None
-    let row_select: Option<sql::ast::Select> =
-        root::translate_rows_query(env, state, current_table, from_clause, &query)
-            .map_or_else(map_no_fields_error_to_none, wrap_ok)?;
+    let row_select: Option<sql::ast::Select> = root::translate_rows_query(
+        env,
+        state,
+        current_table,
+        from_clause,
+        &query,
+        default_limit,
+    )
+    .map_or_else(map_no_fields_error_to_none, wrap_ok)?;
 
     // translate aggregate select. if there are no fields, make this a None
     let aggregate_select: Option<sql::ast::Select> =
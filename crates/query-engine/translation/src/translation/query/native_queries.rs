@@ -1,62 +1,167 @@
 //! Handle native queries translation after building the query.
 
+use std::collections::BTreeMap;
+
 use ndc_sdk::models;
 
 use super::values;
 use crate::translation::error::Error;
-use crate::translation::helpers::State;
+use crate::translation::helpers::{Env, State};
 use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
 /// Translate native queries collected in State by the translation proccess into CTEs.
-pub fn translate(state: State) -> Result<Vec<sql::ast::CommonTableExpression>, Error> {
+pub fn translate(env: &Env, state: State) -> Result<Vec<sql::ast::CommonTableExpression>, Error> {
+    let (ctes, _) = translate_with_column_types(env, state)?;
+    Ok(ctes)
+}
+
+/// Translate native queries collected in State into CTEs, along with the types their columns are
+/// declared with when those should be checked before running the query.
+pub fn translate_with_column_types(
+    env: &Env,
+    state: State,
+) -> Result<
+    (
+        Vec<sql::ast::CommonTableExpression>,
+        Vec<sql::execution_plan::NativeQueryColumnTypes>,
+    ),
+    Error,
+> {
     let mut ctes = vec![];
+    let mut column_types = vec![];
     let variables_table = state.get_variables_table();
     let native_queries = state.get_native_queries();
 
     // for each found table expression
     for native_query in native_queries {
-        // convert metadata representation to sql::ast representation
-        let sql: Vec<sql::ast::RawSql> = native_query
+        let columns = native_query
             .info
-            .sql
-            .0
-            .into_iter()
-            .map(|part| match part {
-                metadata::NativeQueryPart::Text(text) => Ok(sql::ast::RawSql::RawText(text)),
-                metadata::NativeQueryPart::Parameter(param) => {
-                    let typ = match native_query.info.arguments.get(&param) {
-                        None => Err(Error::ArgumentNotFound(param.clone())),
-                        Some(argument) => Ok(argument.r#type.clone()),
-                    }?;
-                    let exp = match native_query.arguments.get(&param) {
-                        None => Err(Error::ArgumentNotFound(param.clone())),
-                        Some(argument) => match argument {
-                            models::Argument::Literal { value } => {
+            .columns
+            .values()
+            .map(|column| (column.name.clone(), type_name(&column.r#type)))
+            .collect();
+        let sql = translate_sql(
+            env,
+            native_query.info,
+            &native_query.arguments,
+            &variables_table,
+        )?;
+        let cte = sql::ast::CommonTableExpression {
+            alias: native_query.alias.clone(),
+            column_names: None,
+            select: sql::ast::CTExpr::RawSql(sql),
+        };
+
+        if env.options().strict_native_query_column_types {
+            let mut query = sql::helpers::star_select(sql::ast::From::Table {
+                reference: sql::ast::TableReference::AliasedTable(native_query.alias.clone()),
+                alias: native_query.alias,
+                only: false,
+                sample: None,
+            });
+            query.with = sql::ast::With {
+                common_table_expressions: vec![cte.clone()],
+            };
+            column_types.push(sql::execution_plan::NativeQueryColumnTypes {
+                name: native_query.name,
+                query,
+                columns,
+            });
+        }
+
+        // add a cte
+        ctes.push(cte);
+    }
+
+    Ok((ctes, column_types))
+}
+
+/// The name of a type as the database knows it, such as 'int4[]'.
+fn type_name(r#type: &metadata::Type) -> String {
+    match r#type {
+        metadata::Type::ScalarType(scalar_type) => scalar_type.0.clone(),
+        metadata::Type::CompositeType(type_name) => type_name.clone(),
+        metadata::Type::ArrayType(element_type) => format!("{}[]", type_name(element_type)),
+    }
+}
+
+/// Convert the metadata representation of a native query call to its SQL, with its arguments
+/// substituted in.
+pub fn translate_sql(
+    env: &Env,
+    info: metadata::NativeQueryInfo,
+    arguments: &BTreeMap<String, models::Argument>,
+    variables_table: &Result<sql::ast::TableReference, Error>,
+) -> Result<Vec<sql::ast::RawSql>, Error> {
+    info.sql
+        .0
+        .into_iter()
+        .map(|part| match part {
+            metadata::NativeQueryPart::Text(text) => Ok(sql::ast::RawSql::RawText(text)),
+            metadata::NativeQueryPart::Parameter(param) => {
+                let case_insensitive = env.options().case_insensitive_native_query_arguments;
+                let typ = match lookup_argument(&info.arguments, &param, case_insensitive)? {
+                    None => Err(Error::ArgumentNotFound(param.clone())),
+                    Some(argument) => Ok(argument.r#type.clone()),
+                }?;
+                let exp = match lookup_argument(arguments, &param, case_insensitive)? {
+                    None => Err(Error::ArgumentNotFound(param.clone())),
+                    Some(argument) => match argument {
+                        models::Argument::Literal { value } => {
+                            if values::is_compatible_json_value(value, &typ) {
                                 values::translate_json_value(value, &typ)
+                            } else {
+                                Err(Error::ArgumentTypeMismatch {
+                                    argument_name: param.clone(),
+                                    value: value.clone(),
+                                    r#type: typ.clone(),
+                                })
                             }
-                            models::Argument::Variable { name } => match &variables_table {
-                                Err(err) => Err(err.clone()),
-                                Ok(variables_table) => Ok(values::translate_variable(
-                                    variables_table.clone(),
-                                    name.clone(),
-                                    &typ,
-                                )),
-                            },
+                        }
+                        models::Argument::Variable { name } => match variables_table {
+                            Err(err) => Err(err.clone()),
+                            Ok(variables_table) => Ok(values::translate_variable(
+                                variables_table.clone(),
+                                name.clone(),
+                                &typ,
+                            )),
                         },
-                    }?;
-                    Ok(sql::ast::RawSql::Expression(exp))
-                }
-            })
-            .collect::<Result<Vec<sql::ast::RawSql>, Error>>()?;
+                    },
+                }?;
+                Ok(sql::ast::RawSql::Expression(exp))
+            }
+        })
+        .collect::<Result<Vec<sql::ast::RawSql>, Error>>()
+}
 
-        // add a cte
-        ctes.push(sql::ast::CommonTableExpression {
-            alias: native_query.alias,
-            column_names: None,
-            select: sql::ast::CTExpr::RawSql(sql),
-        });
+/// Find the argument with the given name. When matching is case-insensitive and no argument has
+/// exactly that name, we look for the argument whose name differs from it in case only, which is
+/// ambiguous when there are several of those.
+fn lookup_argument<'a, T>(
+    arguments: &'a BTreeMap<String, T>,
+    name: &str,
+    case_insensitive: bool,
+) -> Result<Option<&'a T>, Error> {
+    if let Some(argument) = arguments.get(name) {
+        return Ok(Some(argument));
+    }
+    if !case_insensitive {
+        return Ok(None);
+    }
+    let matches: Vec<(&String, &T)> = arguments
+        .iter()
+        .filter(|(argument_name, _)| argument_name.to_lowercase() == name.to_lowercase())
+        .collect();
+    match matches.as_slice() {
+        [] => Ok(None),
+        [(_, argument)] => Ok(Some(argument)),
+        _ => Err(Error::AmbiguousArgument {
+            argument_name: name.to_string(),
+            candidates: matches
+                .into_iter()
+                .map(|(argument_name, _)| argument_name.clone())
+                .collect(),
+        }),
     }
-
-    Ok(ctes)
 }
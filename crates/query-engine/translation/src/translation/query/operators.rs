@@ -3,23 +3,180 @@ use ndc_sdk::models;
 use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
-/// Maps a binary comparison operator to their appropriate PostgreSQL name and arguments type.
+/// Maps a binary comparison operator to their appropriate PostgreSQL name, arguments type, and
+/// the collation to compare with, if it is not the column's.
 pub fn translate_comparison_operator(
     env: &Env,
     left_type: &metadata::ScalarType,
     operator: &models::BinaryComparisonOperator,
-) -> Result<(sql::ast::BinaryOperator, metadata::ScalarType), Error> {
+) -> Result<
+    (
+        sql::ast::BinaryOperator,
+        metadata::ScalarType,
+        Option<String>,
+    ),
+    Error,
+> {
     match operator {
-        models::BinaryComparisonOperator::Equal => {
-            Ok((sql::ast::BinaryOperator("=".to_string()), left_type.clone()))
-        }
+        models::BinaryComparisonOperator::Equal => Ok((
+            sql::ast::BinaryOperator("=".to_string()),
+            left_type.clone(),
+            None,
+        )),
         models::BinaryComparisonOperator::Other { name } => {
             let op = env.lookup_comparison_operator(left_type, name)?;
 
             Ok((
                 sql::ast::BinaryOperator(op.operator_name.clone()),
                 op.argument_type.clone(),
+                op.collation.clone(),
             ))
         }
     }
 }
+
+/// Maps a quantified comparison operator, such as '_gt_all', to the PostgreSQL name of the
+/// operator it quantifies, the type of the elements of its array argument, the collation to
+/// compare with, if it is not the column's, and its quantifier. Returns nothing for other
+/// operators.
+pub fn translate_quantified_comparison_operator(
+    env: &Env,
+    left_type: &metadata::ScalarType,
+    operator: &models::BinaryComparisonOperator,
+) -> Option<(
+    sql::ast::BinaryOperator,
+    metadata::ScalarType,
+    Option<String>,
+    metadata::Quantifier,
+)> {
+    match operator {
+        models::BinaryComparisonOperator::Equal => None,
+        models::BinaryComparisonOperator::Other { name } => env
+            .lookup_quantified_comparison_operator(left_type, name)
+            .map(|(quantifier, op)| {
+                (
+                    sql::ast::BinaryOperator(op.operator_name.clone()),
+                    op.argument_type.clone(),
+                    op.collation.clone(),
+                    quantifier,
+                )
+            }),
+    }
+}
+
+/// Quantify an array expression, for it to be compared with by a quantified comparison. The
+/// comparison is then false for an empty array with `ANY`, and true with `ALL`.
+pub fn quantify(
+    quantifier: metadata::Quantifier,
+    array: sql::ast::Expression,
+) -> sql::ast::Expression {
+    let function = match quantifier {
+        metadata::Quantifier::Any => "ANY",
+        metadata::Quantifier::All => "ALL",
+    };
+    sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown(function.to_string()),
+        args: vec![array],
+    }
+}
+
+/// Compare an expression with the given collation, if any.
+pub fn collate(
+    expression: sql::ast::Expression,
+    collation: Option<String>,
+) -> sql::ast::Expression {
+    match collation {
+        None => expression,
+        Some(collation) => sql::ast::Expression::Collate {
+            expression: Box::new(expression),
+            collation,
+        },
+    }
+}
+
+/// Comparison operators on array columns. Postgres does not define these as operators on array
+/// types, so we have to translate them ourselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayComparisonOperator {
+    /// '_contains_element': the array contains the value.
+    ContainsElement,
+    /// '_array_length_eq': the array has the given number of elements.
+    LengthEquals,
+}
+
+/// Maps a binary comparison operator on an array column to its operation and argument type.
+pub fn translate_array_comparison_operator(
+    array_type: &metadata::Type,
+    operator: &models::BinaryComparisonOperator,
+) -> Result<(ArrayComparisonOperator, metadata::ScalarType), Error> {
+    let non_scalar_type_error = || Error::NonScalarTypeUsedInOperator {
+        r#type: array_type.clone(),
+    };
+    let element_type = match array_type {
+        metadata::Type::ArrayType(element_type) => match element_type.as_ref() {
+            metadata::Type::ScalarType(scalar_type) => Ok(scalar_type),
+            metadata::Type::ArrayType(_) | metadata::Type::CompositeType(_) => {
+                Err(non_scalar_type_error())
+            }
+        },
+        metadata::Type::ScalarType(_) | metadata::Type::CompositeType(_) => {
+            Err(non_scalar_type_error())
+        }
+    }?;
+
+    match operator {
+        models::BinaryComparisonOperator::Other { name } if name == "_contains_element" => Ok((
+            ArrayComparisonOperator::ContainsElement,
+            element_type.clone(),
+        )),
+        models::BinaryComparisonOperator::Other { name } if name == "_array_length_eq" => Ok((
+            ArrayComparisonOperator::LengthEquals,
+            metadata::ScalarType("int4".to_string()),
+        )),
+        _ => Err(non_scalar_type_error()),
+    }
+}
+
+impl ArrayComparisonOperator {
+    /// Build the comparison of an array expression with a value.
+    ///
+    /// Both operators are false for NULL arrays, as the comparison is then NULL.
+    pub fn apply(
+        self,
+        array: sql::ast::Expression,
+        value: sql::ast::Expression,
+    ) -> sql::ast::Expression {
+        match self {
+            ArrayComparisonOperator::ContainsElement => sql::ast::Expression::BinaryOperation {
+                left: Box::new(value),
+                operator: sql::ast::BinaryOperator("=".to_string()),
+                right: Box::new(sql::ast::Expression::FunctionCall {
+                    function: sql::ast::Function::Unknown("ANY".to_string()),
+                    args: vec![array],
+                }),
+            },
+            // 'array_length(array, 1)' is NULL rather than 0 for an empty array, so we fall back
+            // to 'cardinality', which is only NULL for a NULL array.
+            ArrayComparisonOperator::LengthEquals => sql::ast::Expression::BinaryOperation {
+                left: Box::new(sql::ast::Expression::FunctionCall {
+                    function: sql::ast::Function::Coalesce,
+                    args: vec![
+                        sql::ast::Expression::FunctionCall {
+                            function: sql::ast::Function::Unknown("array_length".to_string()),
+                            args: vec![
+                                array.clone(),
+                                sql::ast::Expression::Value(sql::ast::Value::Int8(1)),
+                            ],
+                        },
+                        sql::ast::Expression::FunctionCall {
+                            function: sql::ast::Function::Unknown("cardinality".to_string()),
+                            args: vec![array],
+                        },
+                    ],
+                }),
+                operator: sql::ast::BinaryOperator("=".to_string()),
+                right: Box::new(value),
+            },
+        }
+    }
+}
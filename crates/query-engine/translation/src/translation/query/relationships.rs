@@ -51,6 +51,7 @@ pub fn translate_joins(
                 &target_collection,
                 &from_clause,
                 join_field.query,
+                None,
             )?;
 
             // add join expressions to row / aggregate selects
@@ -114,6 +115,10 @@ pub fn translate_joins(
                         aggregate_select,
                     ))
                 }
+                // Only made for the top-level query.
+                sql::helpers::SelectSet::RowsAndTotalCount(_, _) => Err(Error::InternalError(
+                    "unexpected total count in a relationship".to_string(),
+                )),
             }?;
 
             // form a single JSON item shaped `{ rows: [], aggregates: {} }`
@@ -10,10 +10,14 @@ use super::aggregates;
 use super::filtering;
 use super::relationships;
 use super::sorting;
+use super::values;
+use super::SAMPLE_ARGUMENT;
 use crate::translation::error::Error;
 use crate::translation::helpers::{
-    CollectionInfo, Env, RootAndCurrentTables, State, TableNameAndReference,
+    BigNumberRepresentation, CollectionInfo, Env, IntervalRepresentation, RootAndCurrentTables,
+    State, TableNameAndReference, INLINE_ROWS_ARGUMENT,
 };
+use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
 /// Translate aggregates query to sql ast.
@@ -33,13 +37,21 @@ pub fn translate_aggregate_query(
         false => Ok(()),
     }?;
 
-    // create all aggregate columns
-    let aggregate_columns = aggregates::translate(&current_table.reference, aggregate_fields)?;
-
     // create the select clause and the joins, order by, where clauses.
     // We don't add the limit afterwards.
-    let mut select =
-        translate_query_part(env, state, current_table, query, aggregate_columns, vec![])?;
+    let mut select = translate_query_part(env, state, current_table, query, vec![], vec![])?;
+
+    // create all aggregate columns. Those which collect values in order use the order by.
+    let collection_info = env.lookup_collection(&current_table.name)?;
+    let aggregate_columns = aggregates::translate(
+        env,
+        &collection_info,
+        &current_table.reference,
+        aggregate_fields,
+        &select.order_by,
+    )?;
+    select.select_list = sql::ast::SelectList::SelectList(aggregate_columns);
+
     // we remove the order by part though because it is only relevant for group by clauses,
     // which we don't support at the moment.
     select.order_by = sql::helpers::empty_order_by();
@@ -49,13 +61,15 @@ pub fn translate_aggregate_query(
     Ok(select)
 }
 
-/// Translate rows part of query to sql ast.
+/// Translate rows part of query to sql ast, limited to the default limit given if the query
+/// gives no limit of its own.
 pub fn translate_rows_query(
     env: &Env,
     state: &mut State,
     current_table: &TableNameAndReference,
     from_clause: &sql::ast::From,
     query: &models::Query,
+    default_limit: Option<u32>,
 ) -> Result<sql::ast::Select, Error> {
     // find the table according to the metadata.
     let collection_info = env.lookup_collection(&current_table.name)?;
@@ -77,11 +91,32 @@ pub fn translate_rows_query(
         .into_iter()
         .map(|(alias, field)| match field {
             models::Field::Column { column, .. } => {
+                if let Some(computed_field) = collection_info.lookup_computed_field(&column) {
+                    return Ok((
+                        sql::helpers::make_column_alias(alias),
+                        to_json_representation(
+                            env,
+                            state,
+                            translate_computed_field(&current_table.reference, computed_field)?,
+                            &metadata::Type::ScalarType(computed_field.r#type.clone()),
+                        ),
+                    ));
+                }
+                if let Some(ranking_field) = collection_info.lookup_ranking_field(&column) {
+                    return Ok((
+                        sql::helpers::make_column_alias(alias),
+                        translate_ranking_field(&current_table.reference, ranking_field),
+                    ));
+                }
                 let column_info = collection_info.lookup_column(&column)?;
-                Ok(sql::helpers::make_column(
+                let (column_alias, column_expression) = sql::helpers::make_column(
                     current_table.reference.clone(),
                     column_info.name.clone(),
                     sql::helpers::make_column_alias(alias),
+                );
+                Ok((
+                    column_alias,
+                    to_json_representation(env, state, column_expression, &column_info.r#type),
                 ))
             }
             models::Field::Relationship {
@@ -116,14 +151,351 @@ pub fn translate_rows_query(
 
     select.from = Some(from_clause.clone());
 
-    // Add the limit.
+    // Add the limit, or the default one when the query gives none.
+    let with_ties = env.options().limit_with_ties && query.limit.is_some();
+    if with_ties && select.order_by.elements.is_empty() {
+        return Err(Error::LimitWithTiesWithoutOrdering);
+    }
     select.limit = sql::ast::Limit {
-        limit: query.limit,
+        limit: query.limit.or(default_limit),
         offset: query.offset,
+        with_ties,
     };
     Ok(select)
 }
 
+/// Translate the expression of a computed field, with the columns it refers to taken from the
+/// current table.
+fn translate_computed_field(
+    table: &sql::ast::TableReference,
+    computed_field: &metadata::ComputedField,
+) -> Result<sql::ast::Expression, Error> {
+    // The expressions of computed fields are checked when the configuration is validated.
+    let parts = computed_field.parts().map_err(Error::InternalError)?;
+    Ok(sql::ast::Expression::RawSql(
+        parts
+            .into_iter()
+            .map(|part| match part {
+                metadata::ComputedFieldPart::Text(text) => sql::ast::RawSql::RawText(text),
+                metadata::ComputedFieldPart::Column(name) => sql::ast::RawSql::Expression(
+                    sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
+                        table: table.clone(),
+                        name: sql::ast::ColumnName(name),
+                    }),
+                ),
+            })
+            .collect(),
+    ))
+}
+
+/// Translate a ranking field to its window function, over the rows of the current table.
+fn translate_ranking_field(
+    table: &sql::ast::TableReference,
+    ranking_field: &metadata::RankingField,
+) -> sql::ast::Expression {
+    let column = |name: &String| {
+        sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
+            table: table.clone(),
+            name: sql::ast::ColumnName(name.clone()),
+        })
+    };
+    sql::ast::Expression::WindowFunction {
+        function: Box::new(sql::ast::Expression::FunctionCall {
+            function: sql::ast::Function::Unknown(
+                ranking_field.function.function_name().to_string(),
+            ),
+            args: vec![],
+        }),
+        partition_by: ranking_field.partition_by.iter().map(column).collect(),
+        order_by: sql::ast::OrderBy {
+            elements: ranking_field
+                .order_by
+                .iter()
+                .map(|order_by| sql::ast::OrderByElement {
+                    target: column(&order_by.column),
+                    direction: match order_by.direction {
+                        metadata::RankingOrderDirection::Asc => sql::ast::OrderByDirection::Asc,
+                        metadata::RankingOrderDirection::Desc => sql::ast::OrderByDirection::Desc,
+                    },
+                    nulls: None,
+                })
+                .collect(),
+        },
+    }
+}
+
+/// Translate a search rank field to its ranking function, over the rows of the given table and
+/// the search given by its argument.
+fn translate_search_rank_field(
+    state: &State,
+    table: &sql::ast::TableReference,
+    search_rank_field: &metadata::SearchRankField,
+    arguments: &BTreeMap<String, models::Argument>,
+) -> Result<sql::ast::Expression, Error> {
+    let text = metadata::Type::ScalarType(metadata::ScalarType("text".to_string()));
+    let search = match arguments.get(&search_rank_field.argument) {
+        None => values::translate_json_value(&serde_json::Value::Null, &text)?,
+        Some(models::Argument::Literal { value }) => {
+            if !values::is_compatible_json_value(value, &text) {
+                return Err(Error::ArgumentTypeMismatch {
+                    argument_name: search_rank_field.argument.clone(),
+                    value: value.clone(),
+                    r#type: text,
+                });
+            }
+            values::translate_json_value(value, &text)?
+        }
+        Some(models::Argument::Variable { name }) => {
+            values::translate_variable(state.get_variables_table()?, name.clone(), &text)
+        }
+    };
+    let weights = search_rank_field
+        .weights
+        .map(|weights| sql::ast::Expression::Cast {
+            expression: Box::new(sql::ast::Expression::Value(sql::ast::Value::String(
+                weights.to_array_literal(),
+            ))),
+            r#type: sql::ast::ScalarType("float4[]".to_string()),
+        });
+    let vector = sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
+        table: table.clone(),
+        name: sql::ast::ColumnName(search_rank_field.column.clone()),
+    });
+    let query = sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("websearch_to_tsquery".to_string()),
+        args: vec![search],
+    };
+    let normalization = (search_rank_field.normalization != 0).then(|| {
+        sql::ast::Expression::Value(sql::ast::Value::Int8(
+            search_rank_field.normalization as i32,
+        ))
+    });
+    Ok(sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown(
+            search_rank_field.function.function_name().to_string(),
+        ),
+        args: weights
+            .into_iter()
+            .chain([vector, query])
+            .chain(normalization)
+            .collect(),
+    })
+}
+
+/// Convert a selected column to the representation we return for its type, for types whose
+/// default JSON representation is not useful to clients.
+///
+/// `bytea` values are returned as base64 strings rather than postgres' hex escape format. The
+/// database breaks base64 into lines of 76 characters, which we join up again. `interval` values
+/// and `int8` and `numeric` values are returned as configured. Arrays of these types have each of
+/// their elements converted.
+fn to_json_representation(
+    env: &Env,
+    state: &mut State,
+    expression: sql::ast::Expression,
+    r#type: &metadata::Type,
+) -> sql::ast::Expression {
+    match r#type {
+        metadata::Type::ArrayType(element_type) => {
+            array_json_representation(env, state, expression, element_type)
+        }
+        metadata::Type::ScalarType(scalar_type) if scalar_type.0 == "interval" => {
+            interval_representation(env.options().interval_representation, expression)
+        }
+        metadata::Type::ScalarType(scalar_type)
+            if matches!(scalar_type.0.as_str(), "int8" | "numeric")
+                && env.options().big_number_representation == BigNumberRepresentation::String =>
+        {
+            sql::ast::Expression::Cast {
+                expression: Box::new(expression),
+                r#type: sql::ast::ScalarType("text".to_string()),
+            }
+        }
+        metadata::Type::ScalarType(scalar_type) if scalar_type.0 == "bytea" => {
+            let encoded = sql::ast::Expression::FunctionCall {
+                function: sql::ast::Function::Unknown("encode".to_string()),
+                args: vec![
+                    expression,
+                    sql::ast::Expression::Value(sql::ast::Value::String("base64".to_string())),
+                ],
+            };
+            sql::ast::Expression::FunctionCall {
+                function: sql::ast::Function::Unknown("translate".to_string()),
+                args: vec![
+                    encoded,
+                    sql::ast::Expression::Value(sql::ast::Value::String("\n".to_string())),
+                    sql::ast::Expression::Value(sql::ast::Value::String(String::new())),
+                ],
+            }
+        }
+        _ => expression,
+    }
+}
+
+/// Convert each element of an array to the representation we return for its type, keeping their
+/// order:
+///
+/// `CASE WHEN <expr> IS NULL THEN null
+///  ELSE ARRAY((SELECT <repr(element)> FROM unnest(<expr>) WITH ORDINALITY AS ... ORDER BY ordinality))
+///  END`
+///
+/// Arrays whose elements are returned as they are stay untouched.
+fn array_json_representation(
+    env: &Env,
+    state: &mut State,
+    expression: sql::ast::Expression,
+    element_type: &metadata::Type,
+) -> sql::ast::Expression {
+    if !has_json_representation(env, element_type) {
+        return expression;
+    }
+    let alias = state.make_table_alias("elements".to_string());
+    let column = |name: &str| {
+        sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::AliasedColumn {
+            table: sql::ast::TableReference::AliasedTable(alias.clone()),
+            column: sql::helpers::make_column_alias(name.to_string()),
+        })
+    };
+    let converted = to_json_representation(env, state, column("element"), element_type);
+    let mut select = sql::helpers::simple_select(vec![(
+        sql::helpers::make_column_alias("element".to_string()),
+        converted,
+    )]);
+    select.from = Some(sql::ast::From::Unnest {
+        expression: expression.clone(),
+        alias: alias.clone(),
+        element: sql::helpers::make_column_alias("element".to_string()),
+        ordinality: sql::helpers::make_column_alias("ordinality".to_string()),
+    });
+    select.order_by = sql::ast::OrderBy {
+        elements: vec![sql::ast::OrderByElement {
+            target: column("ordinality"),
+            direction: sql::ast::OrderByDirection::Asc,
+            nulls: None,
+        }],
+    };
+    sql::ast::Expression::Case {
+        cases: vec![(
+            sql::ast::Expression::UnaryOperation {
+                expression: Box::new(expression),
+                operator: sql::ast::UnaryOperator::IsNull,
+            },
+            sql::ast::Expression::Value(sql::ast::Value::Null),
+        )],
+        otherwise: Box::new(sql::ast::Expression::FunctionCall {
+            function: sql::ast::Function::Unknown("ARRAY".to_string()),
+            args: vec![sql::ast::Expression::ScalarSelect(Box::new(select))],
+        }),
+    }
+}
+
+/// Whether `to_json_representation` converts values of this type.
+fn has_json_representation(env: &Env, r#type: &metadata::Type) -> bool {
+    match r#type {
+        metadata::Type::ArrayType(element_type) => has_json_representation(env, element_type),
+        metadata::Type::ScalarType(scalar_type) => match scalar_type.0.as_str() {
+            "interval" => {
+                env.options().interval_representation != IntervalRepresentation::PostgresText
+            }
+            "int8" | "numeric" => {
+                env.options().big_number_representation == BigNumberRepresentation::String
+            }
+            "bytea" => true,
+            _ => false,
+        },
+        metadata::Type::CompositeType(_) => false,
+    }
+}
+
+/// Convert an `interval` to the configured representation, from the fields the database
+/// breaks it into.
+fn interval_representation(
+    representation: IntervalRepresentation,
+    expression: sql::ast::Expression,
+) -> sql::ast::Expression {
+    let part = |field: &str| sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("date_part".to_string()),
+        args: vec![
+            sql::ast::Expression::Value(sql::ast::Value::String(field.to_string())),
+            expression.clone(),
+        ],
+    };
+    let binary = |left: sql::ast::Expression, operator: &str, right: sql::ast::Expression| {
+        sql::ast::Expression::BinaryOperation {
+            left: Box::new(left),
+            operator: sql::ast::BinaryOperator(operator.to_string()),
+            right: Box::new(right),
+        }
+    };
+    match representation {
+        IntervalRepresentation::PostgresText => expression,
+        // 'P' || years || 'Y' || months || 'M' || days || 'DT' || ... || seconds || 'S',
+        // which is null when the interval is.
+        IntervalRepresentation::Iso8601 => [
+            ("year", "Y"),
+            ("month", "M"),
+            ("day", "DT"),
+            ("hour", "H"),
+            ("minute", "M"),
+            ("second", "S"),
+        ]
+        .into_iter()
+        .fold(
+            sql::ast::Expression::Value(sql::ast::Value::String("P".to_string())),
+            |iso, (field, designator)| {
+                binary(
+                    binary(iso, "||", part(field)),
+                    "||",
+                    sql::ast::Expression::Value(sql::ast::Value::String(designator.to_string())),
+                )
+            },
+        ),
+        IntervalRepresentation::Object => {
+            let months = binary(
+                binary(
+                    part("year"),
+                    "*",
+                    sql::ast::Expression::Value(sql::ast::Value::Int8(12)),
+                ),
+                "+",
+                part("month"),
+            );
+            // the microseconds field includes the seconds.
+            let microseconds = binary(
+                binary(
+                    binary(
+                        part("hour"),
+                        "*",
+                        sql::ast::Expression::Value(sql::ast::Value::Float8(3_600_000_000.0)),
+                    ),
+                    "+",
+                    binary(
+                        part("minute"),
+                        "*",
+                        sql::ast::Expression::Value(sql::ast::Value::Float8(60_000_000.0)),
+                    ),
+                ),
+                "+",
+                part("microseconds"),
+            );
+            sql::ast::Expression::Case {
+                cases: vec![(
+                    sql::ast::Expression::UnaryOperation {
+                        expression: Box::new(expression.clone()),
+                        operator: sql::ast::UnaryOperator::IsNull,
+                    },
+                    sql::ast::Expression::Value(sql::ast::Value::Null),
+                )],
+                otherwise: Box::new(sql::ast::Expression::JsonBuildObject(BTreeMap::from([
+                    ("months".to_string(), Box::new(months)),
+                    ("days".to_string(), Box::new(part("day"))),
+                    ("microseconds".to_string(), Box::new(microseconds)),
+                ]))),
+            }
+        }
+    }
+}
+
 /// Translate the lion (or common) part of 'rows' or 'aggregates' part of a query.
 /// Specifically, from, joins, order bys, and where clauses.
 ///
@@ -156,8 +528,12 @@ fn translate_query_part(
         relationships::translate_joins(env, state, &root_and_current_tables, join_fields)?;
 
     // translate order_by
-    let (order_by, order_by_joins) =
-        sorting::translate_order_by(env, state, &root_and_current_tables, &query.order_by)?;
+    let (order_by, order_by_joins) = sorting::translate_order_by_or_default(
+        env,
+        state,
+        &root_and_current_tables,
+        &query.order_by,
+    )?;
 
     relationship_joins.extend(order_by_joins);
 
@@ -199,7 +575,14 @@ pub fn make_from_clause_and_reference(
 
     // find the table according to the metadata.
     let collection_info = env.lookup_collection(collection_name)?;
-    let from_clause = make_from_clause(state, &collection_alias, &collection_info, arguments)?;
+    let from_clause = make_from_clause(
+        env,
+        state,
+        &collection_alias,
+        collection_name,
+        &collection_info,
+        arguments,
+    )?;
 
     let current_table = TableNameAndReference {
         name: collection_name.to_string(),
@@ -210,9 +593,61 @@ pub fn make_from_clause_and_reference(
 
 /// Build a FROM clause from a collection info and an alias.
 /// Will add a Native Query to the 'State' if the collection is a native query.
+///
+/// The mandatory filter of the collection, if it has one, is applied in a subquery here, so that
+/// every query on the collection only sees the rows which pass it, including those through
+/// relationships and in predicates.
 fn make_from_clause(
+    env: &Env,
     state: &mut State,
     current_table_alias: &sql::ast::TableAlias,
+    collection_name: &str,
+    collection_info: &CollectionInfo,
+    arguments: &BTreeMap<String, models::Argument>,
+) -> Result<sql::ast::From, Error> {
+    let Some(filter) = env.options().mandatory_filters.get(collection_name) else {
+        return make_unfiltered_from_clause(
+            env,
+            state,
+            current_table_alias,
+            collection_name,
+            collection_info,
+            arguments,
+        );
+    };
+    let column = collection_info.lookup_column(&filter.column)?;
+    let table_alias = state.make_table_alias(collection_name.to_string());
+    let from = make_unfiltered_from_clause(
+        env,
+        state,
+        &table_alias,
+        collection_name,
+        collection_info,
+        arguments,
+    )?;
+    let mut select = sql::helpers::star_select(from);
+    select.where_ = sql::ast::Where(sql::ast::Expression::BinaryOperation {
+        left: Box::new(sql::ast::Expression::ColumnReference(
+            sql::ast::ColumnReference::TableColumn {
+                table: sql::ast::TableReference::AliasedTable(table_alias),
+                name: column.name,
+            },
+        )),
+        operator: sql::ast::BinaryOperator("=".to_string()),
+        right: Box::new(values::translate_setting(&filter.setting, &column.r#type)),
+    });
+    Ok(sql::ast::From::Select {
+        select: Box::new(select),
+        alias: current_table_alias.clone(),
+    })
+}
+
+/// Build a FROM clause from a collection info and an alias, without its mandatory filter.
+fn make_unfiltered_from_clause(
+    env: &Env,
+    state: &mut State,
+    current_table_alias: &sql::ast::TableAlias,
+    collection_name: &str,
     collection_info: &CollectionInfo,
     arguments: &BTreeMap<String, models::Argument>,
 ) -> Result<sql::ast::From, Error> {
@@ -223,8 +658,56 @@ fn make_from_clause(
                 table: sql::ast::TableName(info.table_name.clone()),
             };
 
-            Ok(sql::ast::From::Table {
+            let only = env
+                .options()
+                .exclude_inherited_rows
+                .contains(collection_name);
+
+            let sample = translate_table_sample(arguments)?;
+
+            if info.search_rank_fields.is_empty() {
+                state.add_index_hint(env, collection_name, current_table_alias);
+                return Ok(sql::ast::From::Table {
+                    reference: db_table,
+                    alias: current_table_alias.clone(),
+                    only,
+                    sample,
+                });
+            }
+
+            // Search rank fields depend on the arguments, so we compute them in a subquery which
+            // adds them to the columns of the table. They can then be selected, filtered and
+            // ordered by like columns.
+            let table_alias = state.make_table_alias(collection_name.to_string());
+            state.add_index_hint(env, collection_name, &table_alias);
+            let table_reference = sql::ast::TableReference::AliasedTable(table_alias.clone());
+            let columns = info.columns.values().map(|column| {
+                Ok::<_, Error>(sql::helpers::make_column(
+                    table_reference.clone(),
+                    sql::ast::ColumnName(column.name.clone()),
+                    sql::helpers::make_column_alias(column.name.clone()),
+                ))
+            });
+            let search_rank_fields = info.search_rank_fields.0.iter().map(|(field_name, field)| {
+                Ok::<_, Error>((
+                    sql::helpers::make_column_alias(field_name.clone()),
+                    translate_search_rank_field(state, &table_reference, field, arguments)?,
+                ))
+            });
+            let mut select =
+                sql::helpers::simple_select(columns.chain(search_rank_fields).collect::<Result<
+                    Vec<_>,
+                    Error,
+                >>(
+                )?);
+            select.from = Some(sql::ast::From::Table {
                 reference: db_table,
+                alias: table_alias,
+                only,
+                sample,
+            });
+            Ok(sql::ast::From::Select {
+                select: Box::new(select),
                 alias: current_table_alias.clone(),
             })
         }
@@ -235,7 +718,124 @@ fn make_from_clause(
             Ok(sql::ast::From::Table {
                 reference: aliased_table,
                 alias: current_table_alias.clone(),
+                only: false,
+                sample: None,
             })
         }
+
+        CollectionInfo::InlineRows { name, info } => {
+            make_inline_rows_from_clause(current_table_alias, name, info, arguments)
+        }
     }
 }
+
+/// Translate the 'sample' argument of a table, if it is given, to a sample of its rows, such as
+/// `{"method": "system", "percentage": 10}`. Only tables and materialized views can be sampled;
+/// the database rejects samples of views.
+fn translate_table_sample(
+    arguments: &BTreeMap<String, models::Argument>,
+) -> Result<Option<sql::ast::TableSample>, Error> {
+    let value = match arguments.get(SAMPLE_ARGUMENT) {
+        None => return Ok(None),
+        Some(models::Argument::Literal { value }) => value,
+        Some(models::Argument::Variable { .. }) => {
+            return Err(Error::NotImplementedYet(
+                "variables as the 'sample' argument".to_string(),
+            ))
+        }
+    };
+    let method = match value.get("method").and_then(serde_json::Value::as_str) {
+        Some("system") => Ok(sql::ast::TableSampleMethod::System),
+        Some("bernoulli") => Ok(sql::ast::TableSampleMethod::Bernoulli),
+        _ => Err(Error::InvalidTableSample(value.clone())),
+    }?;
+    let percentage = match value.get("percentage") {
+        Some(serde_json::Value::Number(percentage))
+            if percentage
+                .as_f64()
+                .is_some_and(|percentage| (0.0..=100.0).contains(&percentage)) =>
+        {
+            Ok(percentage)
+        }
+        _ => Err(Error::InvalidTableSample(value.clone())),
+    }?;
+    Ok(Some(sql::ast::TableSample {
+        method,
+        percentage: sql::ast::Expression::Cast {
+            expression: Box::new(sql::ast::Expression::Value(sql::ast::Value::String(
+                percentage.to_string(),
+            ))),
+            r#type: sql::ast::ScalarType("float4".to_string()),
+        },
+    }))
+}
+
+/// Build a `VALUES` list from the rows given as the argument of an inline rows collection. Its
+/// columns are those of the collection, holding the values of the rows converted to the types of
+/// the columns, and null where a row does not have the column.
+fn make_inline_rows_from_clause(
+    current_table_alias: &sql::ast::TableAlias,
+    collection_name: &str,
+    info: &metadata::InlineRowsInfo,
+    arguments: &BTreeMap<String, models::Argument>,
+) -> Result<sql::ast::From, Error> {
+    let value = match arguments.get(INLINE_ROWS_ARGUMENT) {
+        None => Err(Error::ArgumentNotFound(INLINE_ROWS_ARGUMENT.to_string())),
+        Some(models::Argument::Literal { value }) => Ok(value),
+        Some(models::Argument::Variable { .. }) => Err(Error::NotImplementedYet(
+            "variables as the rows of inline rows".to_string(),
+        )),
+    }?;
+    let invalid_rows = || Error::InvalidInlineRows(collection_name.to_string(), value.clone());
+    // A `VALUES` list cannot be empty.
+    let rows = match value {
+        serde_json::Value::Array(rows) if !rows.is_empty() => rows
+            .iter()
+            .map(|row| match row {
+                serde_json::Value::Object(row)
+                    if row.keys().all(|key| info.columns.contains_key(key)) =>
+                {
+                    Ok(row)
+                }
+                _ => Err(invalid_rows()),
+            })
+            .collect::<Result<Vec<_>, Error>>(),
+        _ => Err(invalid_rows()),
+    }?;
+
+    let rows = rows
+        .iter()
+        .map(|row| {
+            info.columns
+                .iter()
+                .map(|(column, column_info)| {
+                    let value = match row.get(column) {
+                        None | Some(serde_json::Value::Null) => sql::ast::Value::Null,
+                        Some(value) => {
+                            sql::ast::Value::String(serde_json::to_string(value).map_err(
+                                |err| Error::UnableToSerializeJsonValueToString(err.to_string()),
+                            )?)
+                        }
+                    };
+                    Ok(values::translate_jsonb_scalar(
+                        sql::ast::Expression::Cast {
+                            expression: Box::new(sql::ast::Expression::Value(value)),
+                            r#type: sql::ast::ScalarType("jsonb".to_string()),
+                        },
+                        &column_info.r#type,
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(sql::ast::From::Values {
+        rows,
+        alias: current_table_alias.clone(),
+        columns: info
+            .columns
+            .values()
+            .map(|column_info| sql::helpers::make_column_alias(column_info.name.clone()))
+            .collect(),
+    })
+}
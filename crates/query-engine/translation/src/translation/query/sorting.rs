@@ -1,6 +1,7 @@
 //! Translate Order By clauses.
 use multimap::MultiMap;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
 use std::hash::{Hash, Hasher};
 
 use ndc_sdk::models;
@@ -12,10 +13,127 @@ use crate::translation::error::Error;
 use crate::translation::helpers::{
     CollectionInfo, Env, RootAndCurrentTables, State, TableNameAndReference,
 };
+use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
+/// Translate the ordering of a query, or the default ordering of its collection when the query
+/// does not order its rows.
+///
+/// With deterministic ordering, the columns of a uniqueness constraint of the collection are
+/// ordered by last, so that no two rows sort equal.
+pub fn translate_order_by_or_default(
+    env: &Env,
+    state: &mut State,
+    root_and_current_tables: &RootAndCurrentTables,
+    order_by: &Option<models::OrderBy>,
+) -> Result<(sql::ast::OrderBy, Vec<sql::ast::Join>), Error> {
+    let default_columns = match order_by {
+        Some(order_by) if !order_by.elements.is_empty() => None,
+        _ => env
+            .options()
+            .default_order_by
+            .get(&root_and_current_tables.current_table.name),
+    };
+    let (mut elements, nulls) = match default_columns {
+        None => (
+            order_by
+                .as_ref()
+                .map_or(vec![], |order_by| order_by.elements.clone()),
+            vec![],
+        ),
+        Some(columns) => (
+            columns
+                .iter()
+                .map(|column| models::OrderByElement {
+                    order_direction: column.direction,
+                    target: models::OrderByTarget::Column {
+                        name: column.column.clone(),
+                        path: vec![],
+                    },
+                })
+                .collect(),
+            columns.iter().map(|column| column.nulls).collect(),
+        ),
+    };
+    if env.options().deterministic_ordering && !elements.is_empty() {
+        let collection = env.lookup_collection(&root_and_current_tables.current_table.name)?;
+        let tiebreakers = tiebreaker_elements(&collection, &elements);
+        elements.extend(tiebreakers);
+    }
+    let (mut order_by, joins) = translate_order_by(
+        env,
+        state,
+        root_and_current_tables,
+        &Some(models::OrderBy { elements }),
+    )?;
+    // NDC orderings have no say over where nulls go, so they go where the configuration puts
+    // them. Default orderings are by columns, which translate to one element per column, in order,
+    // so we can place the nulls of those which say where they go afterwards.
+    let null_ordering = env.options().null_ordering;
+    for element in &mut order_by.elements {
+        element.nulls = null_ordering;
+    }
+    for (element, nulls) in order_by.elements.iter_mut().zip(nulls) {
+        if nulls.is_some() {
+            element.nulls = nulls;
+        }
+    }
+    Ok((order_by, joins))
+}
+
+/// The columns to order the rows of a collection by after the given elements so that no two rows
+/// sort equal: those of its uniqueness constraint with the fewest columns. There are none when
+/// the elements order by all the columns of a uniqueness constraint already, or when the
+/// collection has no uniqueness constraints.
+fn tiebreaker_elements(
+    collection: &CollectionInfo,
+    elements: &[models::OrderByElement],
+) -> Vec<models::OrderByElement> {
+    let CollectionInfo::Table { info, .. } = collection else {
+        return vec![];
+    };
+    let ordered_columns: BTreeSet<&str> = elements
+        .iter()
+        .filter_map(|element| match &element.target {
+            models::OrderByTarget::Column { name, path } if path.is_empty() => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let constraints = info
+        .uniqueness_constraints
+        .0
+        .values()
+        .map(|metadata::UniquenessConstraint(columns)| columns);
+    if constraints.clone().any(|columns| {
+        columns
+            .iter()
+            .all(|column| ordered_columns.contains(column.as_str()))
+    }) {
+        return vec![];
+    }
+    constraints
+        .min_by_key(|columns| columns.len())
+        .map_or(vec![], |columns| {
+            columns
+                .iter()
+                .filter(|column| !ordered_columns.contains(column.as_str()))
+                .map(|column| models::OrderByElement {
+                    order_direction: models::OrderDirection::Asc,
+                    target: models::OrderByTarget::Column {
+                        name: column.clone(),
+                        path: vec![],
+                    },
+                })
+                .collect()
+        })
+}
+
 /// Convert the order by fields from a QueryRequest to a SQL ORDER BY clause and potentially
 /// JOINs when we order by relationship fields.
+///
+/// Relationship fields are joined with a `LEFT OUTER JOIN`, so rows without a related row are
+/// kept and sort as NULL, which Postgres places last in ascending order and first in descending
+/// order.
 pub fn translate_order_by(
     env: &Env,
     state: &mut State,
@@ -47,13 +165,25 @@ pub fn translate_order_by(
             let mut order_by_columns = order_by_parts.into_iter().flatten().collect::<Vec<_>>();
             order_by_columns.sort_by_key(|(index, _)| *index);
 
-            // Discard the indices, construct an order by clause, and accompanied joins.
+            // Discard the indices, sort the columns configured to with their collation, and
+            // construct an order by clause, and accompanied joins.
             Ok((
                 sql::ast::OrderBy {
                     elements: order_by_columns
                         .into_iter()
-                        .map(|(_, order_by_element)| order_by_element)
-                        .collect(),
+                        .zip(elements)
+                        .map(|((_, mut order_by_element), element)| {
+                            if let Some(collation) =
+                                order_by_collation(env, root_and_current_tables, element)?
+                            {
+                                order_by_element.target = sql::ast::Expression::Collate {
+                                    expression: Box::new(order_by_element.target),
+                                    collation,
+                                };
+                            }
+                            Ok::<_, Error>(order_by_element)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?,
                 },
                 joins,
             ))
@@ -61,6 +191,31 @@ pub fn translate_order_by(
     }
 }
 
+/// The collation configured to sort a column by, if the element orders by a column, of the
+/// current collection or of the last collection along its path.
+fn order_by_collation(
+    env: &Env,
+    root_and_current_tables: &RootAndCurrentTables,
+    element: &models::OrderByElement,
+) -> Result<Option<String>, Error> {
+    let models::OrderByTarget::Column { name, path } = &element.target else {
+        return Ok(None);
+    };
+    let collection = match path.last() {
+        None => &root_and_current_tables.current_table.name,
+        Some(path_element) => {
+            &env.lookup_relationship(&path_element.relationship)?
+                .target_collection
+        }
+    };
+    Ok(env
+        .options()
+        .order_by_collations
+        .get(collection)
+        .and_then(|columns| columns.get(name))
+        .cloned())
+}
+
 /// Group columns or aggregates with the same path element.
 /// Columns and aggregates need to be separated because they return
 /// different amount on rows.
@@ -239,6 +394,7 @@ fn translate_order_by_target_group(
                             models::OrderDirection::Asc => sql::ast::OrderByDirection::Asc,
                             models::OrderDirection::Desc => sql::ast::OrderByDirection::Desc,
                         },
+                        nulls: None,
                     },
                 )
             })
@@ -277,6 +433,7 @@ fn translate_order_by_target_group(
                                 models::OrderDirection::Asc => sql::ast::OrderByDirection::Asc,
                                 models::OrderDirection::Desc => sql::ast::OrderByDirection::Desc,
                             },
+                            nulls: None,
                         },
                     )
                 })
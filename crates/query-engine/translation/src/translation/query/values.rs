@@ -16,12 +16,35 @@ pub fn translate_json_value(
             r#type: type_to_ast_scalar_type(r#type),
         }),
         (serde_json::Value::Bool(b), _) => Ok(Expression::Value(Value::Bool(*b))),
+        // Integers a double cannot hold exactly are given as their digits, so that they keep
+        // their precision, as they do when given as strings.
+        (serde_json::Value::Number(n), database::Type::ScalarType(scalar_type))
+            if matches!(scalar_type.0.as_str(), "int8" | "numeric") && !is_exact_as_f64(n) =>
+        {
+            Ok(Expression::Cast {
+                expression: Box::new(Expression::Value(Value::String(n.to_string()))),
+                r#type: type_to_ast_scalar_type(r#type),
+            })
+        }
         (serde_json::Value::Number(n), _) => {
             let lit = n
                 .as_f64()
                 .ok_or(Error::UnableToDeserializeNumberAsF64(n.clone()))?;
             Ok(Expression::Value(Value::Float8(lit)))
         }
+        (serde_json::Value::Object(object), database::Type::ScalarType(range_type))
+            if range_element_type(range_type).is_some() =>
+        {
+            translate_range_bounds(object, range_type)
+        }
+        (serde_json::Value::String(str), database::Type::ScalarType(scalar_type))
+            if scalar_type.0 == "bytea" =>
+        {
+            if !is_base64(str) {
+                return Err(Error::InvalidBase64(str.clone()));
+            }
+            Ok(decode_base64(Expression::Value(Value::String(str.clone()))))
+        }
         (serde_json::Value::String(str), _) => Ok(Expression::Cast {
             expression: Box::new(Expression::Value(Value::String(str.clone()))),
             r#type: type_to_ast_scalar_type(r#type),
@@ -55,6 +78,152 @@ pub fn translate_json_value(
     }
 }
 
+/// Whether a number is either not an integer or an integer a double holds exactly, which is any
+/// integer of at most 2^53 in magnitude.
+fn is_exact_as_f64(n: &serde_json::Number) -> bool {
+    const MAX_EXACT_INTEGER: u64 = 1 << 53;
+    match (n.as_i64(), n.as_u64()) {
+        (Some(i), _) => i.unsigned_abs() <= MAX_EXACT_INTEGER,
+        (None, Some(u)) => u <= MAX_EXACT_INTEGER,
+        (None, None) => true,
+    }
+}
+
+/// The element type of a built-in range type, if the type is one.
+fn range_element_type(range_type: &database::ScalarType) -> Option<database::ScalarType> {
+    let element_type = match range_type.0.as_str() {
+        "int4range" => "int4",
+        "int8range" => "int8",
+        "numrange" => "numeric",
+        "tsrange" => "timestamp",
+        "tstzrange" => "timestamptz",
+        "daterange" => "date",
+        _ => return None,
+    };
+    Some(database::ScalarType(element_type.to_string()))
+}
+
+/// Build a range with its type's constructor function from an object giving its bounds, such as
+/// `{"lower": "2024-01-01T00:00:00Z", "upper": null, "bounds": "[)"}`.
+///
+/// A missing or null bound leaves that end of the range unbounded, which is also how infinite
+/// bounds are given for types with no 'infinity' value. The bounds default to including the lower
+/// bound and excluding the upper one, as with the constructor itself.
+fn translate_range_bounds(
+    object: &serde_json::Map<String, serde_json::Value>,
+    range_type: &database::ScalarType,
+) -> Result<sql::ast::Expression, Error> {
+    let invalid_bounds = || Error::InvalidRangeBounds {
+        value: serde_json::Value::Object(object.clone()),
+        range_type: range_type.clone(),
+    };
+    if !object
+        .keys()
+        .all(|key| matches!(key.as_str(), "lower" | "upper" | "bounds"))
+    {
+        return Err(invalid_bounds());
+    }
+    let bounds = match object.get("bounds") {
+        None => "[)".to_string(),
+        Some(serde_json::Value::String(bounds))
+            if matches!(bounds.as_str(), "[)" | "[]" | "(]" | "()") =>
+        {
+            bounds.clone()
+        }
+        Some(_) => return Err(invalid_bounds()),
+    };
+
+    let element_type =
+        database::Type::ScalarType(range_element_type(range_type).ok_or_else(invalid_bounds)?);
+    let translate_bound = |name: &str| match object.get(name) {
+        None => translate_json_value(&serde_json::Value::Null, &element_type),
+        // Numbers are translated as 'float8' values, for which there are no range constructors.
+        Some(value @ serde_json::Value::Number(_)) => Ok(Expression::Cast {
+            expression: Box::new(translate_json_value(value, &element_type)?),
+            r#type: type_to_ast_scalar_type(&element_type),
+        }),
+        Some(value) => translate_json_value(value, &element_type),
+    };
+
+    Ok(Expression::FunctionCall {
+        function: sql::ast::Function::Unknown(range_type.0.clone()),
+        args: vec![
+            translate_bound("lower")?,
+            translate_bound("upper")?,
+            Expression::Value(Value::String(bounds)),
+        ],
+    })
+}
+
+/// Whether a string is base64 encoded, with padding, as `decode(..., 'base64')` expects. Like the
+/// database, we ignore whitespace, such as the line breaks of wrapped base64.
+fn is_base64(string: &str) -> bool {
+    let bytes: Vec<u8> = string
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    let padding = bytes.iter().rev().take_while(|byte| **byte == b'=').count();
+    bytes.len() % 4 == 0
+        && padding <= 2
+        && bytes[..bytes.len() - padding]
+            .iter()
+            .all(|byte| byte.is_ascii_alphanumeric() || *byte == b'+' || *byte == b'/')
+}
+
+/// Decode a base64 string into binary data, as `bytea` values are given as base64.
+fn decode_base64(expression: Expression) -> Expression {
+    Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("decode".to_string()),
+        args: vec![
+            expression,
+            Expression::Value(Value::String("base64".to_string())),
+        ],
+    }
+}
+
+/// Check whether a JSON value can be used as a value of the given type.
+///
+/// This only rejects combinations that are certainly wrong, such as a boolean supplied for a
+/// numeric type. Strings are always accepted, as they are cast to the type in the database, as
+/// are values for types we know nothing about, which may define a cast from json.
+pub fn is_compatible_json_value(value: &serde_json::Value, r#type: &database::Type) -> bool {
+    match (value, r#type) {
+        (serde_json::Value::Null | serde_json::Value::String(_), _) => true,
+        (serde_json::Value::Array(arr), database::Type::ArrayType(element_type)) => arr
+            .iter()
+            .all(|element| is_compatible_json_value(element, element_type)),
+        (_, database::Type::ArrayType(_)) => false,
+        (value, database::Type::CompositeType(_)) => value.is_object(),
+        (value, database::Type::ScalarType(scalar_type)) => match scalar_type.0.as_str() {
+            "int2" | "int4" | "int8" | "float4" | "float8" | "numeric" => value.is_number(),
+            "bool" => value.is_boolean(),
+            "text" | "varchar" | "bpchar" | "char" | "name" | "bytea" => false,
+            _ => true,
+        },
+    }
+}
+
+/// Convert a `jsonb` scalar, such as `4` or `"abc"`, to a value of a type through its text.
+/// Values of `jsonb` are left as they are.
+pub fn translate_jsonb_scalar(expression: Expression, r#type: &database::Type) -> Expression {
+    let scalar_type = type_to_ast_scalar_type(r#type);
+    if scalar_type.0 == "jsonb" {
+        return expression;
+    }
+    // `jsonb_build_array(<expr>) ->> 0` is the text of the scalar, without the quotes of strings.
+    Expression::Cast {
+        expression: Box::new(Expression::BinaryOperation {
+            left: Box::new(Expression::FunctionCall {
+                function: sql::ast::Function::Unknown("jsonb_build_array".to_string()),
+                args: vec![expression],
+            }),
+            operator: sql::ast::BinaryOperator("->>".to_string()),
+            right: Box::new(Expression::Value(Value::Int8(0))),
+        }),
+        r#type: scalar_type,
+    }
+}
+
 /// Translate a NDC 'Type' to an SQL type name.
 fn type_to_ast_scalar_type(typ: &database::Type) -> sql::ast::ScalarType {
     match typ {
@@ -68,6 +237,9 @@ fn type_to_ast_scalar_type(typ: &database::Type) -> sql::ast::ScalarType {
             sql::ast::ScalarType(scalar_type + "[]")
         }
         query_engine_metadata::metadata::Type::ScalarType(t) => sql::ast::ScalarType(t.0.clone()),
+        query_engine_metadata::metadata::Type::CompositeType(_) => {
+            sql::ast::ScalarType("jsonb".to_string())
+        }
     }
 }
 
@@ -82,8 +254,23 @@ pub fn translate_variable(
         column: sql::helpers::make_column_alias(variable),
     });
 
+    match r#type {
+        database::Type::ScalarType(scalar_type) if scalar_type.0 == "bytea" => decode_base64(exp),
+        _ => sql::ast::Expression::Cast {
+            expression: Box::new(exp),
+            r#type: type_to_ast_scalar_type(r#type),
+        },
+    }
+}
+
+/// Read a setting of the database session, such as `app.tenant_id`, as a SQL value. Reading a
+/// setting which is not set fails.
+pub fn translate_setting(setting: &str, r#type: &database::Type) -> sql::ast::Expression {
     sql::ast::Expression::Cast {
-        expression: Box::new(exp),
+        expression: Box::new(Expression::FunctionCall {
+            function: sql::ast::Function::Unknown("current_setting".to_string()),
+            args: vec![Expression::Value(Value::String(setting.to_string()))],
+        }),
         r#type: type_to_ast_scalar_type(r#type),
     }
 }
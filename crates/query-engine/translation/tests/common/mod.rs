@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 
 use query_engine_sql::sql;
@@ -5,20 +6,15 @@ use query_engine_translation::translation;
 
 /// Translate a query to SQL and compare against the snapshot.
 pub fn test_translation(testname: &str) -> Result<String, translation::error::Error> {
-    let tables = serde_json::from_str(
-        fs::read_to_string(format!("tests/goldenfiles/{}/tables.json", testname))
-            .unwrap()
-            .as_str(),
-    )
-    .unwrap();
-    let request = serde_json::from_str(
-        fs::read_to_string(format!("tests/goldenfiles/{}/request.json", testname))
-            .unwrap()
-            .as_str(),
-    )
-    .unwrap();
+    test_translation_with_options(testname, translation::helpers::QueryOptions::default())
+}
 
-    let plan = translation::query::translate(&tables, request)?;
+/// Translate a query to SQL with the given options and compare against the snapshot.
+pub fn test_translation_with_options(
+    testname: &str,
+    options: translation::helpers::QueryOptions,
+) -> Result<String, translation::error::Error> {
+    let plan = translate_with_options(testname, options)?;
     let plan = plan.query;
     let query = plan.query_sql();
     let params: Vec<(usize, &sql::string::Param)> = query
@@ -37,6 +33,149 @@ pub fn test_translation(testname: &str) -> Result<String, translation::error::Er
     Ok(format!("{}\n\n{:?}", pretty, params))
 }
 
+/// Translate a query to an execution plan with the given options.
+pub fn translate_with_options(
+    testname: &str,
+    options: translation::helpers::QueryOptions,
+) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>, translation::error::Error>
+{
+    let tables = serde_json::from_str(
+        fs::read_to_string(format!("tests/goldenfiles/{}/tables.json", testname))
+            .unwrap()
+            .as_str(),
+    )
+    .unwrap();
+    let request = serde_json::from_str(
+        fs::read_to_string(format!("tests/goldenfiles/{}/request.json", testname))
+            .unwrap()
+            .as_str(),
+    )
+    .unwrap();
+
+    translation::query::translate(&tables, options, request)
+}
+
+/// Options which order the rows of a collection by a column when queries do not order them.
+pub fn default_order_by_options(
+    collection: &str,
+    column: translation::helpers::DefaultOrderByColumn,
+) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        default_order_by: BTreeMap::from([(collection.to_string(), vec![column])]),
+        ..Default::default()
+    }
+}
+
+/// Options which filter the rows of a collection in every query.
+pub fn mandatory_filter_options(
+    collection: &str,
+    filter: translation::helpers::MandatoryFilter,
+) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        mandatory_filters: BTreeMap::from([(collection.to_string(), filter)]),
+        ..Default::default()
+    }
+}
+
+/// Options which place the nulls of every ordering as given.
+pub fn null_ordering_options(
+    null_ordering: Option<sql::ast::NullsOrder>,
+) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        null_ordering,
+        ..Default::default()
+    }
+}
+
+/// Options which return intervals in the given representation.
+pub fn interval_representation_options(
+    interval_representation: translation::helpers::IntervalRepresentation,
+) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        interval_representation,
+        ..Default::default()
+    }
+}
+
+/// Options which return `int8` and `numeric` values in the given representation.
+pub fn big_number_representation_options(
+    big_number_representation: translation::helpers::BigNumberRepresentation,
+) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        big_number_representation,
+        ..Default::default()
+    }
+}
+
+/// Options which reject queries rendered to more than the given length of SQL.
+pub fn max_sql_length_options(max_sql_length: usize) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        max_sql_length: Some(max_sql_length),
+        ..Default::default()
+    }
+}
+
+/// Options which expose the labels of enum types in the given case.
+pub fn enum_value_case_options(
+    enum_value_case: translation::helpers::EnumValueCase,
+) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        enum_value_case: Some(enum_value_case),
+        ..Default::default()
+    }
+}
+
+/// Options which match native query arguments regardless of case.
+pub fn case_insensitive_native_query_argument_options() -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        case_insensitive_native_query_arguments: true,
+        ..Default::default()
+    }
+}
+
+/// Options of a database which can read data as of a point in time, as CockroachDB can.
+pub fn as_of_system_time_options() -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        as_of_system_time: true,
+        ..Default::default()
+    }
+}
+
+/// Options which compare the values of scalar types nothing is known about as text.
+pub fn unknown_scalar_types_as_text_options() -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        unknown_scalar_types_as_text: true,
+        ..Default::default()
+    }
+}
+
+/// Options which only quote the identifiers which need it.
+pub fn quote_identifiers_when_needed_options() -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        identifier_quoting: sql::string::IdentifierQuoting::WhenNeeded,
+        ..Default::default()
+    }
+}
+
+/// Options which hint the planner to scan a collection with the given indexes.
+pub fn index_hints_options(
+    collection: &str,
+    indexes: Vec<String>,
+) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        index_hints: BTreeMap::from([(collection.to_string(), indexes)]),
+        ..Default::default()
+    }
+}
+
+/// Options which limit the rows of queries which do not give a limit to the given number.
+pub fn default_limit_options(default_limit: u32) -> translation::helpers::QueryOptions {
+    translation::helpers::QueryOptions {
+        default_limit: Some(default_limit),
+        ..Default::default()
+    }
+}
+
 /// Translate a mutation to SQL and compare against the snapshot.
 pub fn test_mutation_translation(testname: &str) -> Result<String, translation::error::Error> {
     let tables = serde_json::from_str(
@@ -64,6 +203,7 @@ pub fn test_mutation_translation(testname: &str) -> Result<String, translation::
         .map(|operation| {
             translation::mutation::translate(
                 &tables,
+                translation::helpers::QueryOptions::default(),
                 operation,
                 request.collection_relationships.clone(),
             )
@@ -1,5 +1,96 @@
 mod common;
 
+use ndc_sdk::models;
+use query_engine_sql::sql;
+use query_engine_translation::translation::helpers::{DefaultOrderByColumn, MandatoryFilter};
+
+#[test]
+fn select_generated_column() {
+    let result = common::test_translation("select_generated_column").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn select_as_of_system_time() {
+    let result = common::test_translation_with_options(
+        "select_as_of_system_time",
+        common::as_of_system_time_options(),
+    )
+    .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn select_quote_identifiers_when_needed() {
+    let result = common::test_translation_with_options(
+        "select_quote_identifiers_when_needed",
+        common::quote_identifiers_when_needed_options(),
+    )
+    .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn select_with_index_hints() {
+    let result = common::test_translation_with_options(
+        "select_with_index_hints",
+        common::index_hints_options("Album", vec!["PK_Album".to_string()]),
+    )
+    .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn select_with_index_hints_leaves_out_hints_which_would_end_the_comment() {
+    let result = common::test_translation_with_options(
+        "select_with_index_hints",
+        common::index_hints_options(
+            "Album",
+            vec![
+                "PK_Album".to_string(),
+                r#"x */ DROP TABLE "Album"; /*"#.to_string(),
+            ],
+        ),
+    )
+    .unwrap();
+    assert!(
+        !result.contains("DROP TABLE"),
+        "unexpected SQL in:\n{result}"
+    );
+    assert!(!result.contains("/*+"), "expected no hints in:\n{result}");
+}
+
+#[test]
+fn select_without_pg_hint_plan_leaves_out_index_hints() {
+    // The database of this test does not have the extension which reads the hints.
+    let result = common::test_translation_with_options(
+        "select_with_limit",
+        common::index_hints_options("Album", vec!["PK_Album".to_string()]),
+    )
+    .unwrap();
+    assert!(!result.contains("/*+"), "expected no hints in:\n{result}");
+}
+
+#[test]
+fn select_where_unknown_scalar_type_as_text() {
+    let result = common::test_translation_with_options(
+        "select_where_unknown_scalar_type",
+        common::unknown_scalar_types_as_text_options(),
+    )
+    .unwrap()
+    .to_lowercase();
+    assert!(
+        result.contains(r#"cast("%0_shapes"."outline" as text) like"#),
+        "expected the column to be compared as text in:\n{result}"
+    );
+}
+
+#[test]
+fn select_where_unknown_scalar_type_has_no_operators() {
+    let result = common::test_translation("select_where_unknown_scalar_type");
+    assert!(result.is_err(), "expected no '_like' operator for the type");
+}
+
 #[test]
 fn select_array_column() {
     let result = common::test_translation("select_array_column").unwrap();
@@ -20,12 +111,121 @@ fn select_where_album_id_equals_self_nested_object_relationship() {
     insta::assert_snapshot!(result);
 }
 
+#[test]
+fn it_converts_select_with_mandatory_filter() {
+    // The query has no predicate of its own, but only sees the rows of the current tenant.
+    let options = common::mandatory_filter_options(
+        "Invoice",
+        MandatoryFilter {
+            column: "TenantId".to_string(),
+            setting: "app.tenant_id".to_string(),
+        },
+    );
+    let result =
+        common::test_translation_with_options("select_with_mandatory_filter", options).unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[test]
 fn it_converts_select_with_limit() {
     let result = common::test_translation("select_with_limit").unwrap();
     insta::assert_snapshot!(result);
 }
 
+#[test]
+fn select_with_default_limit() {
+    let result = common::test_translation_with_options(
+        "select_with_default_limit",
+        common::default_limit_options(10),
+    )
+    .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn select_with_limit_and_default_limit() {
+    // The query's own limit is kept, even though it is not the default one.
+    let result = common::test_translation_with_options(
+        "select_with_limit",
+        common::default_limit_options(10),
+    )
+    .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn nested_array_relationships_with_default_limit() {
+    // Only the root query is limited, not the arrays of related rows nested in it.
+    let plan = common::translate_with_options(
+        "nested_array_relationships",
+        common::default_limit_options(10),
+    )
+    .unwrap();
+    let sql = plan.query.query_sql().sql;
+    assert_eq!(sql.matches(" LIMIT ").count(), 1, "in:\n{sql}");
+    assert_eq!(sql.matches(" LIMIT 10").count(), 1, "in:\n{sql}");
+}
+
+#[test]
+fn it_converts_select_with_limit_zero() {
+    let result = common::test_translation("select_with_limit_zero").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_converts_select_with_default_order_by() {
+    let options = common::default_order_by_options(
+        "Album",
+        DefaultOrderByColumn {
+            column: "AlbumId".to_string(),
+            direction: models::OrderDirection::Asc,
+            nulls: None,
+        },
+    );
+    let result =
+        common::test_translation_with_options("select_with_default_order_by", options).unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_converts_select_with_descending_default_order_by() {
+    let options = common::default_order_by_options(
+        "Album",
+        DefaultOrderByColumn {
+            column: "AlbumId".to_string(),
+            direction: models::OrderDirection::Desc,
+            nulls: Some(sql::ast::NullsOrder::Last),
+        },
+    );
+    let result =
+        common::test_translation_with_options("select_with_default_order_by", options).unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_sorts_nulls_first_when_configured() {
+    let options = common::null_ordering_options(Some(sql::ast::NullsOrder::First));
+    let result =
+        common::test_translation_with_options("sorting_by_nullable_column", options).unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_sorts_nulls_last_when_configured() {
+    let options = common::null_ordering_options(Some(sql::ast::NullsOrder::Last));
+    let result =
+        common::test_translation_with_options("sorting_by_nullable_column", options).unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_sorts_nulls_where_postgres_puts_them_by_default() {
+    let options = common::null_ordering_options(None);
+    let result =
+        common::test_translation_with_options("sorting_by_nullable_column", options).unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[test]
 fn it_select_where_string() {
     let result = common::test_translation("select_where_string").unwrap();
@@ -38,6 +238,37 @@ fn it_select_where_not_null() {
     insta::assert_snapshot!(result);
 }
 
+#[test]
+fn it_select_where_array_contains_element() {
+    let result = common::test_translation("select_where_array_contains_element").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_select_where_varchar_array_contains_element() {
+    let result = common::test_translation("select_where_varchar_array_contains_element").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_select_where_quantified_comparison() {
+    let result = common::test_translation("select_where_quantified_comparison").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_select_where_quantified_comparison_with_empty_array() {
+    let result =
+        common::test_translation("select_where_quantified_comparison_with_empty_array").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn it_select_where_array_length() {
+    let result = common::test_translation("select_where_array_length").unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[test]
 fn it_select_where_unrelated_exists() {
     let result = common::test_translation("select_where_unrelated_exists").unwrap();
@@ -74,6 +305,12 @@ fn it_aggregate_function_albums() {
     insta::assert_snapshot!(result);
 }
 
+#[test]
+fn it_aggregate_array_agg_ordered_albums() {
+    let result = common::test_translation("aggregate_array_agg_ordered_albums").unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[test]
 fn it_simple_array_relationship() {
     let result = common::test_translation("simple_array_relationship").unwrap();
@@ -159,6 +396,24 @@ fn sorting_by_relationship_count_with_predicate() {
     insta::assert_snapshot!(result);
 }
 
+#[test]
+fn select_computed_field() {
+    let result = common::test_translation("select_computed_field").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn select_ranking_field() {
+    let result = common::test_translation("select_ranking_field").unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn select_order_by_search_rank() {
+    let result = common::test_translation("select_order_by_search_rank").unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[test]
 fn select_track_order_by_artist_id_and_album_title() {
     let result =
@@ -168,6 +423,8 @@ fn select_track_order_by_artist_id_and_album_title() {
 
 mod negative_tests {
     use crate::common;
+    use query_engine_sql::sql;
+    use query_engine_translation::translation;
 
     #[test]
     fn sorting_by_no_relationship_aggregate() {
@@ -175,6 +432,69 @@ mod negative_tests {
             .expect_err("Expected error");
         insta::assert_snapshot!(result.to_string());
     }
+
+    #[test]
+    fn inline_rows_without_rows() {
+        let result =
+            common::test_translation("inline_rows_without_rows").expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[test]
+    fn native_query_argument_type_mismatch() {
+        let result = common::test_translation("native_queries/argument_type_mismatch")
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[test]
+    fn ambiguous_native_query_argument() {
+        let result = common::test_translation_with_options(
+            "native_queries/ambiguous_argument",
+            common::case_insensitive_native_query_argument_options(),
+        )
+        .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[test]
+    fn invalid_range_bounds() {
+        let result =
+            common::test_translation("values/invalid_range_bounds").expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[test]
+    fn invalid_base64() {
+        let result = common::test_translation("values/invalid_base64").expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[test]
+    fn sql_longer_than_the_maximum() {
+        let plan =
+            common::translate_with_options("select_with_limit", common::max_sql_length_options(64))
+                .unwrap();
+        let result =
+            sql::execution_plan::check_sql_length(&plan.query.query_sql(), plan.max_sql_length);
+        assert!(matches!(
+            result,
+            Err(sql::execution_plan::SqlTooLong {
+                length,
+                max_length: 64,
+            }) if length > 64
+        ));
+    }
+
+    #[test]
+    fn as_of_system_time_without_cockroach() {
+        let result =
+            common::test_translation("select_as_of_system_time").expect_err("Expected error");
+        assert!(matches!(
+            result,
+            translation::error::Error::AsOfSystemTimeNotSupported
+        ));
+    }
 }
 
 mod native_queries {
@@ -198,6 +518,16 @@ mod native_queries {
         insta::assert_snapshot!(result);
     }
 
+    #[test]
+    fn select_artist_by_name_with_different_case() {
+        let result = common::test_translation_with_options(
+            "native_queries/select_artist_by_name_with_different_case",
+            common::case_insensitive_native_query_argument_options(),
+        )
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
     #[test]
     fn select_artist_with_album_by_title() {
         let result =
@@ -213,10 +543,19 @@ mod native_queries {
         .unwrap();
         insta::assert_snapshot!(result);
     }
+
+    #[test]
+    fn select_employee_hierarchy() {
+        let result = common::test_translation("native_queries/select_employee_hierarchy").unwrap();
+        insta::assert_snapshot!(result);
+    }
 }
 
 mod types {
     use crate::common;
+    use query_engine_translation::translation::helpers::{
+        BigNumberRepresentation, EnumValueCase, IntervalRepresentation,
+    };
 
     #[test]
     fn select_types_on_comparisons() {
@@ -229,6 +568,140 @@ mod types {
         let result = common::test_translation("values/select_types_on_native_queries").unwrap();
         insta::assert_snapshot!(result);
     }
+
+    #[test]
+    fn select_where_hstore() {
+        let result = common::test_translation("values/select_where_hstore").unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_where_similar() {
+        let result = common::test_translation("values/select_where_similar").unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_where_collated() {
+        let result = common::test_translation("values/select_where_collated").unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_where_tstzrange_overlaps() {
+        let result = common::test_translation("values/select_where_tstzrange_overlaps").unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_special_types() {
+        let result = common::test_translation("values/select_special_types").unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_bytea_through_relationship() {
+        let result = common::test_translation("values/select_bytea_through_relationship")
+            .unwrap()
+            .to_lowercase();
+        // Once for the related column, and once for each element of the related array.
+        assert_eq!(
+            result.matches("translate(encode(").count(),
+            2,
+            "expected base64 bytea values in:\n{result}"
+        );
+        assert!(
+            result.contains(r#"unnest("%2_attachments"."thumbnails")"#)
+                && result.contains("ordinality"),
+            "expected the array elements in order in:\n{result}"
+        );
+    }
+
+    #[test]
+    fn select_where_bytea_eq() {
+        let result = common::test_translation("values/select_where_bytea_eq").unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_where_enum_label_in_upper_case() {
+        let result = common::test_translation_with_options(
+            "values/select_where_enum_label",
+            common::enum_value_case_options(EnumValueCase::Upper),
+        )
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_interval_as_postgres_text() {
+        let result = common::test_translation_with_options(
+            "values/select_interval",
+            common::interval_representation_options(IntervalRepresentation::PostgresText),
+        )
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_interval_as_iso_8601() {
+        let result = common::test_translation_with_options(
+            "values/select_interval",
+            common::interval_representation_options(IntervalRepresentation::Iso8601),
+        )
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_interval_as_object() {
+        let result = common::test_translation_with_options(
+            "values/select_interval",
+            common::interval_representation_options(IntervalRepresentation::Object),
+        )
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_where_big_numbers_as_strings() {
+        let result = common::test_translation_with_options(
+            "values/select_where_big_numbers",
+            common::big_number_representation_options(BigNumberRepresentation::String),
+        )
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[test]
+    fn select_big_number_aggregates_as_strings() {
+        let plan = common::translate_with_options(
+            "values/select_big_number_aggregates",
+            common::big_number_representation_options(BigNumberRepresentation::String),
+        )
+        .unwrap();
+        let sql = plan.query.query_sql().sql;
+        // The sum of an int8 is a numeric, and the average of an int4 is too.
+        assert!(sql.contains(r#" as text) AS "sum_big""#), "in:\n{sql}");
+        assert!(sql.contains(r#" as text) AS "avg_id""#), "in:\n{sql}");
+        // The maximum of an int4 is an int4, and counts are left as numbers.
+        assert!(!sql.contains(r#" as text) AS "max_id""#), "in:\n{sql}");
+        assert!(!sql.contains(r#" as text) AS "how_many""#), "in:\n{sql}");
+    }
+
+    #[test]
+    fn select_standard_big_number_aggregates_as_strings() {
+        let plan = common::translate_with_options(
+            "values/select_standard_big_number_aggregates",
+            common::big_number_representation_options(BigNumberRepresentation::String),
+        )
+        .unwrap();
+        let sql = plan.query.query_sql().sql;
+        // Introspection found no aggregates, so the schema offers the standard ones, and the sum
+        // of an int4 is an int8.
+        assert!(sql.contains(r#" as text) AS "sum_id""#), "in:\n{sql}");
+        assert!(!sql.contains(r#" as text) AS "max_id""#), "in:\n{sql}");
+    }
 }
 
 mod mutations {
@@ -11,6 +11,13 @@ mod explain {
         insta::assert_snapshot!(result.details.query);
     }
 
+    #[tokio::test]
+    async fn select_by_pk_as_of_system_time() {
+        let result = run_explain(create_router().await, "select_by_pk_as_of_system_time").await;
+        is_contained_in_lines(vec!["scan", "35"], result.details.plan);
+        is_contained_in_lines(vec!["AS OF SYSTEM TIME $1"], result.details.query);
+    }
+
     #[tokio::test]
     async fn select_where_variable() {
         let result = run_explain(create_router().await, "select_where_variable").await;
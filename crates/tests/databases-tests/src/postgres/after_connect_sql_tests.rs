@@ -0,0 +1,96 @@
+//! Tests that the statements in `afterConnectSql` are run on the connections of the pool.
+
+#[cfg(test)]
+mod after_connect_sql {
+    use std::fs;
+
+    use sqlx::{Executor, Row};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::configuration::version2;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    fn runtime_configuration(after_connect_sql: &[&str]) -> configuration::RuntimeConfiguration {
+        let raw_configuration = match serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            configuration::RawConfiguration::Version2(v2) => {
+                configuration::RawConfiguration::Version2(version2::RawConfiguration {
+                    after_connect_sql: after_connect_sql
+                        .iter()
+                        .map(|statement| statement.to_string())
+                        .collect(),
+                    ..v2
+                })
+            }
+            configuration::RawConfiguration::Version1(_) => panic!("expected a v2 configuration"),
+        };
+        configuration::as_runtime_configuration(&configuration::Configuration {
+            config: configuration::set_connection_uri(
+                raw_configuration,
+                common::CONNECTION_STRING.to_string(),
+            ),
+        })
+    }
+
+    #[tokio::test]
+    async fn connections_are_set_up_with_the_statements() {
+        let runtime_configuration = runtime_configuration(&[
+            "SET jit = off",
+            "SELECT set_config('work_mem', '12MB', false)",
+        ]);
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+
+        let mut connection = state.pool.acquire(&state.metrics).await.unwrap();
+        let session = connection
+            .as_mut()
+            .fetch_one("SELECT current_setting('jit'), current_setting('work_mem')")
+            .await
+            .unwrap();
+        let jit: String = session.get(0);
+        let work_mem: String = session.get(1);
+        drop(connection);
+        state.pool.close().await;
+
+        assert_eq!(jit, "off");
+        assert_eq!(work_mem, "12MB");
+    }
+
+    #[tokio::test]
+    async fn the_statements_cannot_write_to_the_database() {
+        let runtime_configuration =
+            runtime_configuration(&[r#"SELECT * INTO "AlbumCopy" FROM "Album""#]);
+
+        let result = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await;
+
+        match result {
+            Err(err) => assert!(
+                err.to_string().contains("read-only transaction"),
+                "unexpected error: {err}"
+            ),
+            Ok(_) => panic!("expected the connection to be refused"),
+        }
+    }
+}
@@ -10,8 +10,17 @@
 
 #[cfg(test)]
 mod configuration_tests {
+    use std::collections::BTreeMap;
+
+    use sqlx::{Connection, Executor, PgConnection};
+
+    use ndc_postgres::configuration::{self, version1, version2};
+
     use super::super::common;
     use tests_common::common_tests;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+    use tests_common::deployment::{clean_up_deployment, create_fresh_deployment};
+    use tests_common::request::run_query_json;
 
     #[tokio::test]
     async fn get_configuration_schema() {
@@ -48,6 +57,735 @@ mod configuration_tests {
         insta::assert_json_snapshot!(default_configuration);
     }
 
+    #[tokio::test]
+    async fn configure_v2_introspects_column_collations() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(r#"ALTER TABLE "Artist" ALTER COLUMN "Name" TYPE varchar(120) COLLATE "C""#)
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(configuration.metadata.tables.0["Artist"]);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_row_level_security() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                ALTER TABLE "Genre" ENABLE ROW LEVEL SECURITY;
+                CREATE POLICY "genre_readers" ON "Genre" FOR SELECT USING (true);
+                CREATE POLICY "genre_editors" ON "Genre" USING (current_user = 'postgres');
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(configuration.metadata.tables.0["Genre"]);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_triggers() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE FUNCTION "genre_audit"() RETURNS trigger LANGUAGE plpgsql
+                  AS $$ BEGIN RETURN NEW; END $$;
+                CREATE TRIGGER "genre_inserted" AFTER INSERT ON "Genre"
+                  FOR EACH ROW EXECUTE FUNCTION "genre_audit"();
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(configuration.metadata.tables.0["Genre"]);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_foreign_tables() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE EXTENSION IF NOT EXISTS postgres_fdw;
+                CREATE SERVER "billing" FOREIGN DATA WRAPPER postgres_fdw
+                  OPTIONS (dbname 'billing');
+                CREATE FOREIGN TABLE "remote_invoices" (
+                  "InvoiceId" int4 NOT NULL,
+                  "BillingCity" text
+                ) SERVER "billing" OPTIONS (table_name 'Invoice');
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(configuration.metadata.tables.0["remote_invoices"]);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_keeps_same_named_tables_in_unqualified_schemas() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE SCHEMA "archive";
+                CREATE TABLE "archive"."Album" ("AlbumId" int4 PRIMARY KEY, "ArchivedAt" date);
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            configure_options: version1::ConfigureOptions {
+                unqualified_schemas: vec!["public".to_string(), "archive".to_string()],
+                ..version1::ConfigureOptions::default()
+            },
+            ..version2::RawConfiguration::empty()
+        };
+        let configuration = version2::configure(args).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let tables = configuration.unwrap().metadata.tables.0;
+        assert!(!tables.contains_key("Album"));
+        assert_eq!(tables["public_Album"].schema_name, "public");
+        assert_eq!(tables["archive_Album"].schema_name, "archive");
+        assert!(tables["archive_Album"].columns.contains_key("ArchivedAt"));
+        assert_eq!(tables["Artist"].schema_name, "public");
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_column_defaults() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE TABLE "orders" (
+                  "id" int8 GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                  "reference" int8 GENERATED BY DEFAULT AS IDENTITY,
+                  "placed_at" timestamptz NOT NULL DEFAULT now(),
+                  "total" numeric NOT NULL,
+                  "total_with_tax" numeric GENERATED ALWAYS AS ("total" * 1.2) STORED
+                );
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(configuration.metadata.tables.0["orders"]);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_allowed_values_of_check_constraints() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE TABLE "shipments" (
+                  "id" int4 PRIMARY KEY,
+                  "status" text NOT NULL CHECK ("status" IN ('pending', 'shipped', 'it''s lost')),
+                  "carrier" varchar(20) CHECK ("carrier" IN ('post', 'courier')),
+                  "weight" int4 CHECK ("weight" > 0)
+                );
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            ..version2::RawConfiguration::empty()
+        };
+        let configuration = version2::configure(args).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let columns = &configuration.unwrap().metadata.tables.0["shipments"].columns;
+        assert_eq!(
+            columns["status"].allowed_values,
+            Some(vec![
+                "pending".to_string(),
+                "shipped".to_string(),
+                "it's lost".to_string()
+            ])
+        );
+        assert_eq!(
+            columns["carrier"].allowed_values,
+            Some(vec!["post".to_string(), "courier".to_string()])
+        );
+        assert_eq!(columns["weight"].allowed_values, None);
+        assert_eq!(columns["id"].allowed_values, None);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_table_access_methods() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(r#"CREATE VIEW "AlbumTitles" AS SELECT "Title" FROM "Album""#)
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            ..version2::RawConfiguration::empty()
+        };
+        let configuration = version2::configure(args).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let tables = configuration.unwrap().metadata.tables.0;
+        assert_eq!(tables["Album"].access_method.as_deref(), Some("heap"));
+        assert_eq!(tables["AlbumTitles"].access_method, None);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_partial_index_predicates() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE TABLE "accounts" ("id" int4 PRIMARY KEY, "email" text, "deleted_at" timestamptz);
+                CREATE UNIQUE INDEX "accounts_live_email" ON "accounts" ("email") WHERE "deleted_at" IS NULL;
+                CREATE INDEX "accounts_email" ON "accounts" ("email");
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            ..version2::RawConfiguration::empty()
+        };
+        let configuration = version2::configure(args).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let indexes = &configuration.unwrap().metadata.tables.0["accounts"]
+            .indexes
+            .0;
+        assert_eq!(
+            indexes.keys().collect::<Vec<_>>(),
+            vec!["accounts_live_email"]
+        );
+        assert_eq!(
+            indexes["accounts_live_email"].predicate,
+            "(deleted_at IS NULL)"
+        );
+    }
+
+    #[tokio::test]
+    async fn configure_v2_introspects_equality_on_special_types() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE TABLE "special_types" (
+                    "price" money,
+                    "address" inet,
+                    "network" cidr,
+                    "mac" macaddr,
+                    "data" bytea,
+                    "duration" interval
+                );
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            ..version2::RawConfiguration::empty()
+        };
+        let configuration = version2::configure(args).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let metadata = configuration.unwrap().metadata;
+        for scalar_type in ["money", "inet", "cidr", "macaddr", "bytea", "interval"] {
+            let operators = metadata
+                .comparison_operators
+                .0
+                .iter()
+                .find(|(name, _)| name.0 == scalar_type)
+                .map(|(_, operators)| operators);
+            let equal = operators.and_then(|operators| operators.get("_eq"));
+            assert!(
+                equal.is_some_and(|operator| operator.operator_name == "="
+                    && operator.argument_type.0 == scalar_type),
+                "missing equality on {scalar_type}"
+            );
+            assert!(
+                operators.is_some_and(|operators| operators.contains_key("_neq")),
+                "missing inequality on {scalar_type}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn configure_v2_marks_deprecated_columns() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                COMMENT ON COLUMN "Genre"."Name" IS E'The name of the genre.\n@deprecated: use "Title"';
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(configuration.metadata.tables.0["Genre"]);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_names_foreign_keys_after_their_comments() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                COMMENT ON CONSTRAINT "FK_TrackAlbumId" ON "Track" IS 'album';
+                COMMENT ON CONSTRAINT "FK_TrackGenreId" ON "Track" IS ' genre ';
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(configuration.metadata.tables.0["Track"].foreign_relations);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_records_installed_extensions() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection.execute("CREATE EXTENSION hstore").await.unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let extensions = configuration.metadata.extensions;
+        assert!(extensions.contains("plpgsql"));
+        assert_eq!(extensions.0["hstore"].schema_name, "public");
+    }
+
+    #[tokio::test]
+    async fn configure_v2_maps_trigram_similarity_when_pg_trgm_is_installed() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute("CREATE EXTENSION pg_trgm")
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let configuration =
+            common_tests::configuration_v2_tests::configure_initial_configuration_is_unchanged(
+                &connection_string,
+            )
+            .await;
+        let word_similar = configuration
+            .metadata
+            .comparison_operators
+            .0
+            .iter()
+            .find(|(scalar_type, _)| scalar_type.0 == "varchar")
+            .and_then(|(_, operators)| operators.get("_word_similar"))
+            .cloned();
+
+        // Query the fresh database with the configuration introspected from it.
+        std::fs::write(
+            get_path_from_project_root(&deployment.deployment_path),
+            serde_json::to_string(&configuration::RawConfiguration::Version2(configuration))
+                .unwrap(),
+        )
+        .unwrap();
+        let router =
+            tests_common::router::create_router_from_deployment(&deployment.deployment_path).await;
+        let response = run_query_json(router, "select_where_name_word_similar").await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        assert_eq!(
+            word_similar.map(|operator| operator.operator_name),
+            Some("%".to_string())
+        );
+        let rows = response[0]["rows"].as_array().unwrap();
+        assert!(
+            rows.contains(&serde_json::json!({"Name": "Led Zeppelin"})),
+            "expected the misspelt name to match in {response}"
+        );
+    }
+
+    #[tokio::test]
+    async fn configure_v2_records_sequence_last_values() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE TABLE "Ticket" (
+                    "TicketId" int GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                    "Number" serial,
+                    "Title" text
+                );
+                INSERT INTO "Ticket" ("Title") VALUES ('first'), ('second'), ('third');
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            configure_options: version1::ConfigureOptions {
+                introspect_sequence_values: true,
+                ..version1::ConfigureOptions::default()
+            },
+            ..version2::RawConfiguration::empty()
+        };
+        let configuration = version2::configure(args).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let columns = &configuration.unwrap().metadata.tables.0["Ticket"].columns;
+        assert_eq!(columns["TicketId"].sequence_last_value, Some(3));
+        assert_eq!(columns["Number"].sequence_last_value, Some(3));
+        assert_eq!(columns["Title"].sequence_last_value, None);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_leaves_out_tables_beyond_the_maximum() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                DO $$
+                BEGIN
+                  FOR n IN 1..200 LOOP
+                    EXECUTE format('CREATE TABLE %I ("id" int4 PRIMARY KEY)', 'zz_table_' || n);
+                  END LOOP;
+                END
+                $$;
+                ALTER TABLE "Album" ADD COLUMN "ZzId" int4 REFERENCES "zz_table_1" ("id");
+                "#,
+            )
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            configure_options: version1::ConfigureOptions {
+                max_tables: Some(5),
+                ..version1::ConfigureOptions::default()
+            },
+            ..version2::RawConfiguration::empty()
+        };
+        let configuration = version2::configure(args).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let configuration = configuration.unwrap();
+        let tables = &configuration.metadata.tables.0;
+        assert_eq!(tables.len(), 5);
+        assert!(!tables.contains_key("zz_table_1"));
+        let album_foreign_tables = tables["Album"]
+            .foreign_relations
+            .0
+            .values()
+            .map(|relation| relation.foreign_table.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(album_foreign_tables, vec!["Artist"]);
+        let left_out = configuration
+            .introspection_warnings
+            .iter()
+            .find_map(|warning| match warning {
+                version2::IntrospectionWarning::TablesLeftOut {
+                    max_tables,
+                    left_out,
+                } => Some((*max_tables, *left_out)),
+                version2::IntrospectionWarning::TableSkipped { .. } => None,
+            })
+            .expect("expected the tables beyond the maximum to be reported");
+        assert_eq!(left_out.0, 5);
+        assert!(left_out.1 >= 200);
+    }
+
+    #[tokio::test]
+    async fn configure_v2_fails_when_the_excluded_schemas_leave_out_every_table() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+
+        let configure_options = version1::ConfigureOptions::default();
+        let configure_options = version1::ConfigureOptions {
+            excluded_schemas: configure_options
+                .excluded_schemas
+                .iter()
+                .cloned()
+                .chain(["public".to_string(), "topology".to_string()])
+                .collect(),
+            ..configure_options
+        };
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            configure_options: configure_options.clone(),
+            ..version2::RawConfiguration::empty()
+        };
+        let failure = version2::configure(args.clone()).await;
+        let allowed = version2::configure(version2::RawConfiguration {
+            configure_options: version1::ConfigureOptions {
+                error_on_empty_schema: false,
+                ..configure_options
+            },
+            ..args
+        })
+        .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let error = failure
+            .expect_err("expected configuring without any tables to fail")
+            .to_string();
+        assert!(
+            error.contains("Introspection found no tables"),
+            "unexpected error: {error}"
+        );
+        assert!(allowed.unwrap().metadata.tables.0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn configure_v2_keeps_edited_descriptions() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+
+        let args = version2::RawConfiguration {
+            connection_uri: version2::ConnectionUri::Uri(version2::ResolvedSecret(
+                connection_string.clone(),
+            )),
+            ..version2::RawConfiguration::empty()
+        };
+        let mut configuration = version2::configure(args).await.unwrap();
+        let table_annotations: BTreeMap<String, version2::TableAnnotations> =
+            serde_json::from_value(serde_json::json!({
+                "Album": {
+                    "description": "The albums we sell.",
+                    "columns": {
+                        "Title": { "description": "The title, as printed on the cover." }
+                    }
+                }
+            }))
+            .unwrap();
+        configuration.table_annotations = table_annotations.clone();
+
+        let configuration = version2::configure(configuration).await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        let configuration = configuration.unwrap();
+        assert_eq!(configuration.table_annotations, table_annotations);
+        let configuration = version2::validate_raw_configuration(configuration)
+            .await
+            .unwrap();
+        let album = &configuration.metadata.tables.0["Album"];
+        assert_eq!(album.description.as_deref(), Some("The albums we sell."));
+        assert_eq!(
+            album.columns["Title"].description.as_deref(),
+            Some("The title, as printed on the cover.")
+        );
+    }
+
     #[tokio::test]
     async fn get_rawconfiguration_v2_schema() {
         let schema = schemars::schema_for!(ndc_postgres::configuration::version2::RawConfiguration);
@@ -0,0 +1,132 @@
+//! Tests that the `connectionCheckInterval` pool setting closes idle connections which no longer
+//! work, and that the `checkConnectionOnStartup` pool setting stops the connector from starting
+//! when the database cannot run queries.
+
+#[cfg(test)]
+mod connection_check {
+    use std::fs;
+    use std::time::Duration;
+
+    use sqlx::{Connection, Executor, PgConnection, Row};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+    use tests_common::deployment::{clean_up_deployment, create_fresh_deployment};
+
+    use super::super::common;
+
+    #[tokio::test]
+    async fn an_idle_connection_killed_out_of_band_is_closed_by_the_background_check() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+
+        let raw_configuration: configuration::RawConfiguration = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let mut runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    connection_string.clone(),
+                ),
+            });
+        runtime_configuration
+            .pool_settings
+            .connection_check_interval = Some(1);
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+
+        let mut pooled = state.pool.acquire(&state.metrics).await.unwrap();
+        let backend_pid: i32 = pooled
+            .as_mut()
+            .fetch_one("SELECT pg_backend_pid()")
+            .await
+            .unwrap()
+            .get(0);
+        drop(pooled);
+        let idle_before = state.pool.num_idle();
+
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        sqlx::query("SELECT pg_terminate_backend($1)")
+            .bind(backend_pid)
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        let idle_after = state.pool.num_idle();
+        let size_after = state.pool.size();
+
+        state.pool.close().await;
+        clean_up_deployment(deployment).await.unwrap();
+
+        assert_eq!(idle_before, 1);
+        assert_eq!(idle_after, 0);
+        assert_eq!(size_after, 0);
+    }
+
+    #[tokio::test]
+    async fn startup_fails_when_the_database_cannot_run_the_validation_query() {
+        let pool_settings = configuration::PoolSettings {
+            check_connection_on_startup: true,
+            validation_query: Some(r#"SELECT 1 FROM "NoSuchTable""#.to_string()),
+            ..configuration::PoolSettings::default()
+        };
+
+        let result = state::create_state(
+            common::CONNECTION_STRING,
+            &pool_settings,
+            &[],
+            &mut prometheus::Registry::new(),
+        )
+        .await;
+
+        let err = result.expect_err("expected startup to fail");
+        assert!(
+            matches!(err, state::InitializationError::ConnectionCheckFailed(_)),
+            "{err:?}"
+        );
+        assert!(
+            err.to_string()
+                .contains(r#"relation "NoSuchTable" does not exist"#),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn startup_succeeds_when_the_database_can_run_the_validation_query() {
+        let pool_settings = configuration::PoolSettings {
+            check_connection_on_startup: true,
+            ..configuration::PoolSettings::default()
+        };
+
+        let state = state::create_state(
+            common::CONNECTION_STRING,
+            &pool_settings,
+            &[],
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+
+        state.pool.close().await;
+    }
+}
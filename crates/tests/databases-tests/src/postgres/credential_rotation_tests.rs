@@ -0,0 +1,95 @@
+//! Tests that the connector recovers when the credentials it connects with are rotated.
+
+#[cfg(test)]
+mod credential_rotation {
+    use std::fs;
+
+    use sqlx::{Connection, Executor, PgConnection};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    #[tokio::test]
+    async fn queries_recover_after_the_credentials_are_rotated() {
+        let old_role = format!("rotation_old_{}", std::process::id());
+        let new_role = format!("rotation_new_{}", std::process::id());
+        let mut admin = PgConnection::connect(common::CONNECTION_STRING)
+            .await
+            .unwrap();
+        for role in [&old_role, &new_role] {
+            admin
+                .execute(
+                    format!(
+                        "CREATE ROLE {role} LOGIN PASSWORD 'password';
+                         GRANT SELECT ON public.\"Album\" TO {role};"
+                    )
+                    .as_str(),
+                )
+                .await
+                .unwrap();
+        }
+
+        // The connector reads the connection URI from this variable, which the rotation updates.
+        let variable = format!("ROTATION_CONNECTION_URI_{}", std::process::id());
+        let connection_uri_for =
+            |role: &str| common::CONNECTION_STRING.replacen("postgres:", &format!("{role}:"), 1);
+        let mut raw_configuration: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        raw_configuration["connectionUriVariable"] = serde_json::json!(variable);
+        let configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: serde_json::from_value(raw_configuration).unwrap(),
+            });
+
+        std::env::set_var(&variable, connection_uri_for(&old_role));
+        let state =
+            state::create_state_for_region(&configuration, &mut prometheus::Registry::new())
+                .await
+                .unwrap();
+        let request = || {
+            serde_json::from_str(
+                &fs::read_to_string(get_path_from_project_root(
+                    "crates/tests/tests-common/goldenfiles/select_5.json",
+                ))
+                .unwrap(),
+            )
+            .unwrap()
+        };
+        let before = ndc_postgres::query::query(&configuration, &state, request()).await;
+
+        // Rotate the credentials: the old role can no longer log in, and its connections are
+        // gone. The variable now holds the new credentials.
+        admin
+            .execute(
+                format!(
+                    "ALTER ROLE {old_role} NOLOGIN;
+                     SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE usename = '{old_role}';"
+                )
+                .as_str(),
+            )
+            .await
+            .unwrap();
+        std::env::set_var(&variable, connection_uri_for(&new_role));
+        let after = ndc_postgres::query::query(&configuration, &state, request()).await;
+
+        state.pool.close().await;
+        std::env::remove_var(&variable);
+        for role in [&old_role, &new_role] {
+            admin
+                .execute(format!("DROP OWNED BY {role}; DROP ROLE {role};").as_str())
+                .await
+                .unwrap();
+        }
+
+        assert!(before.is_ok(), "query failed before the rotation");
+        assert!(after.is_ok(), "query failed after the rotation");
+    }
+}
@@ -0,0 +1,132 @@
+//! Tests that the errors of running requests are returned to clients as verbosely as configured.
+
+#[cfg(test)]
+mod error_verbosity {
+    use std::fs;
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::configuration::version2;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    /// Run a query with the given error verbosity, after changing the chinook configuration as
+    /// given, and return its error.
+    async fn query_error(
+        error_verbosity: version2::ErrorVerbosity,
+        request_path: &str,
+        change_configuration: impl FnOnce(&mut serde_json::Value),
+    ) -> String {
+        let mut raw_configuration: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        change_configuration(&mut raw_configuration);
+        let raw_configuration = match serde_json::from_value(raw_configuration).unwrap() {
+            configuration::RawConfiguration::Version2(v2) => {
+                configuration::RawConfiguration::Version2(version2::RawConfiguration {
+                    error_verbosity: Some(error_verbosity),
+                    ..v2
+                })
+            }
+            configuration::RawConfiguration::Version1(_) => panic!("expected a v2 configuration"),
+        };
+        let runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    common::CONNECTION_STRING.to_string(),
+                ),
+            });
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+        let request = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(request_path)).unwrap(),
+        )
+        .unwrap();
+        let result = ndc_postgres::query::query(&runtime_configuration, &state, request).await;
+        state.pool.close().await;
+        result
+            .map(|_| ())
+            .expect_err("expected the query to fail")
+            .to_string()
+    }
+
+    /// Point the 'Album' collection at a table which does not exist, so that the database
+    /// rejects queries of it.
+    fn album_table_missing(raw_configuration: &mut serde_json::Value) {
+        raw_configuration["metadata"]["tables"]["Album"]["tableName"] =
+            serde_json::json!("salaries");
+    }
+
+    /// Declare the 'Name' column of the 'artist' native query with a type it does not return,
+    /// in strict mode, so that the connector rejects queries of it before running them.
+    fn artist_name_mistyped(raw_configuration: &mut serde_json::Value) {
+        raw_configuration["strictNativeQueryColumnTypes"] = serde_json::json!(true);
+        raw_configuration["metadata"]["nativeQueries"]["artist"]["columns"]["Name"]["type"] =
+            serde_json::json!({ "scalarType": "text" });
+    }
+
+    #[tokio::test]
+    async fn database_errors_are_detailed() {
+        let error = query_error(
+            version2::ErrorVerbosity::Detailed,
+            "crates/tests/tests-common/goldenfiles/select_5.json",
+            album_table_missing,
+        )
+        .await;
+        assert!(
+            error.contains(r#"relation "public.salaries" does not exist"#),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn database_errors_are_sanitized() {
+        let error = query_error(
+            version2::ErrorVerbosity::Sanitized,
+            "crates/tests/tests-common/goldenfiles/select_5.json",
+            album_table_missing,
+        )
+        .await;
+        assert!(!error.contains("salaries"), "{error}");
+        assert!(error.contains("correlation id: "), "{error}");
+    }
+
+    #[tokio::test]
+    async fn connector_errors_are_detailed() {
+        let error = query_error(
+            version2::ErrorVerbosity::Detailed,
+            "crates/tests/tests-common/goldenfiles/native_queries/select_artist.json",
+            artist_name_mistyped,
+        )
+        .await;
+        assert!(
+            error.contains("Column 'Name' of native query 'artist'"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn connector_errors_are_sanitized() {
+        let error = query_error(
+            version2::ErrorVerbosity::Sanitized,
+            "crates/tests/tests-common/goldenfiles/native_queries/select_artist.json",
+            artist_name_mistyped,
+        )
+        .await;
+        assert!(!error.contains("artist"), "{error}");
+        assert!(error.contains("correlation id: "), "{error}");
+    }
+}
@@ -1,5 +1,7 @@
 #[cfg(test)]
 mod explain {
+    use std::collections::BTreeSet;
+
     use super::super::common::create_router;
     use tests_common::assert::is_contained_in_lines;
     use tests_common::request::run_explain;
@@ -31,4 +33,359 @@ mod explain {
         is_contained_in_lines(keywords, result.details.plan);
         insta::assert_snapshot!(result.details.query);
     }
+
+    #[tokio::test]
+    async fn select_order_by_artist_name() {
+        let result = run_explain(create_router().await, "select_order_by_artist_name").await;
+        let keywords = vec!["Limit", "Sort", "Sort Key", "Left Join", "Artist"];
+        is_contained_in_lines(keywords, result.details.plan);
+        insta::assert_snapshot!(result.details.query);
+    }
+
+    #[tokio::test]
+    async fn select_albums_with_total_count() {
+        let result = run_explain(create_router().await, "select_albums_with_total_count").await;
+        let keywords = vec!["Aggregate", "Limit", "WindowAgg", "Index Scan"];
+        is_contained_in_lines(keywords, result.details.plan);
+        insta::assert_snapshot!(result.details.query);
+    }
+
+    #[tokio::test]
+    async fn select_employees_by_manager_with_manager() {
+        let result = run_explain(
+            create_router().await,
+            "select_employees_by_manager_with_manager",
+        )
+        .await;
+        // The table is joined to itself, both to filter by the manager and to select the manager,
+        // under an alias of its own each time.
+        let aliases: BTreeSet<&str> = result
+            .details
+            .query
+            .split(r#""public"."Employee" AS "#)
+            .skip(1)
+            .filter_map(|rest| rest.split_whitespace().next())
+            .map(|alias| alias.trim_end_matches(')'))
+            .collect();
+        assert!(
+            aliases.len() >= 2,
+            "expected the table to be selected from under distinct aliases in:\n{}",
+            result.details.query
+        );
+        assert!(
+            aliases.len()
+                == result
+                    .details
+                    .query
+                    .matches(r#""public"."Employee" AS "#)
+                    .count(),
+            "expected no alias to be used twice in:\n{}",
+            result.details.query
+        );
+    }
+
+    #[tokio::test]
+    async fn aggregate_array_agg_album_titles() {
+        let result = run_explain(create_router().await, "aggregate_array_agg_album_titles").await;
+        is_contained_in_lines(vec!["Aggregate", "Seq Scan", "Album"], result.details.plan);
+        insta::assert_snapshot!(result.details.query);
+    }
+
+    #[tokio::test]
+    async fn select_albums_by_inline_rows() {
+        let result = run_explain(create_router().await, "select_albums_by_inline_rows").await;
+        is_contained_in_lines(vec!["Values Scan", "Album"], result.details.plan);
+        // The query is pretty-printed, which may break the clauses over several lines.
+        let query = result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            query.contains("(VALUES") && query.contains(r#"_album_labels"("id", "label")"#),
+            "expected the inline rows to be selected from a VALUES list in:\n{}",
+            result.details.query
+        );
+        assert!(
+            query.contains("as int4") && query.contains("as text"),
+            "expected the values of the inline rows to be converted to the types of their columns in:\n{}",
+            result.details.query
+        );
+    }
+
+    #[tokio::test]
+    async fn select_albums_sample() {
+        let result = run_explain(create_router().await, "select_albums_sample").await;
+        is_contained_in_lines(vec!["Sample Scan", "Album", "system"], result.details.plan);
+        // The query is pretty-printed, which may break the clauses over several lines.
+        let query = result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            query.contains("TABLESAMPLE SYSTEM"),
+            "expected the table to be sampled in:\n{}",
+            result.details.query
+        );
+    }
+
+    #[tokio::test]
+    async fn select_album_count_by_artist() {
+        let result = run_explain(
+            create_router().await,
+            "native_queries/select_album_count_by_artist",
+        )
+        .await;
+        let keywords = vec!["Aggregate", "Group Key", "Sort Key", "Filter", "Album"];
+        is_contained_in_lines(keywords, result.details.plan);
+        insta::assert_snapshot!(result.details.query);
+    }
+}
+
+#[cfg(test)]
+mod json_explain {
+    use super::super::common;
+    use tests_common::request::run_explain;
+
+    #[tokio::test]
+    async fn select_by_pk() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::JSON_EXPLAIN_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_explain(router, "select_by_pk").await;
+        let plans: serde_json::Value = serde_json::from_str(&result.details.plan).unwrap();
+        let plan = &plans[0];
+        assert_eq!(plan["nodeType"], "Aggregate");
+        assert!(plan["startupCost"].is_number());
+        assert!(plan["totalCost"].is_number());
+        assert!(plan["planRows"].is_number());
+        assert!(!plan["plans"].as_array().unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod limit_with_ties {
+    use super::super::common;
+    use tests_common::assert::is_contained_in_lines;
+    use tests_common::request::run_explain;
+
+    #[tokio::test]
+    async fn select_album_ids_by_artist_limit_3() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::LIMIT_WITH_TIES_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_explain(router, "select_album_ids_by_artist_limit_3").await;
+        is_contained_in_lines(vec!["Limit", "Sort", "Sort Key"], result.details.plan);
+        // The query is pretty-printed, which may break the clause over several lines.
+        let query = result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            query.contains("FETCH FIRST 3 ROWS WITH TIES"),
+            "expected a WITH TIES clause in:\n{}",
+            result.details.query
+        );
+    }
+}
+
+#[cfg(test)]
+mod deterministic_ordering {
+    use super::super::common;
+    use tests_common::request::run_explain;
+
+    #[tokio::test]
+    async fn select_album_ids_by_artist_limit_3() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::DETERMINISTIC_ORDERING_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_explain(router, "select_album_ids_by_artist_limit_3").await;
+        let query = result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            query.contains(r#"ORDER BY "%0_Album"."ArtistId" ASC, "%0_Album"."AlbumId" ASC"#),
+            "expected the primary key to break ties in:\n{}",
+            result.details.query
+        );
+    }
+}
+
+#[cfg(test)]
+mod order_by_collations {
+    use super::super::common;
+    use tests_common::request::run_explain;
+
+    #[tokio::test]
+    async fn select_order_by_name() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::ORDER_BY_COLLATIONS_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_explain(router, "select_order_by_name").await;
+        let query = result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            query.contains(
+                r#"ORDER BY "%0_Album"."Title" COLLATE "C" ASC, "%0_Album"."AlbumId" DESC"#
+            ),
+            "expected only the title to be sorted with the configured collation in:\n{}",
+            result.details.query
+        );
+    }
+}
+
+#[cfg(test)]
+mod exclude_inherited_rows {
+    use super::super::common;
+    use tests_common::request::run_explain;
+
+    #[tokio::test]
+    async fn select_by_pk() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::EXCLUDE_INHERITED_ROWS_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_explain(router, "select_by_pk").await;
+        let query = result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            query.contains(r#"FROM ONLY "public"."Album""#),
+            "expected a FROM ONLY clause in:\n{}",
+            result.details.query
+        );
+    }
+}
+
+#[cfg(test)]
+mod ranking_fields {
+    use super::super::common;
+    use tests_common::assert::is_contained_in_lines;
+    use tests_common::request::run_explain;
+
+    #[tokio::test]
+    async fn select_album_positions_in_artist() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::RANKING_FIELDS_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_explain(router, "select_album_positions_in_artist").await;
+        is_contained_in_lines(vec!["WindowAgg", "Sort Key"], result.details.plan);
+        // The query is pretty-printed, which may break the window clause over several lines.
+        let query = result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            query.contains(r#"row_number() OVER ( PARTITION BY "%0_Album"."ArtistId""#),
+            "expected a window function partitioned by the artist in:\n{}",
+            result.details.query
+        );
+    }
+}
+
+#[cfg(test)]
+mod identifier_quoting {
+    use super::super::common;
+    use tests_common::assert::is_contained_in_lines;
+    use tests_common::request::run_explain;
+
+    /// Explain a request against a deployment, returning the query on a single line.
+    async fn explain_query(deployment_path: &str, request: &str) -> String {
+        let router = tests_common::router::create_router_from_deployment(deployment_path).await;
+        let result = run_explain(router, request).await;
+        is_contained_in_lines(vec!["Aggregate", "Scan"], result.details.plan);
+        result
+            .details
+            .query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[tokio::test]
+    async fn always_quote_mixed_case_table() {
+        let query = explain_query(
+            common::ALWAYS_QUOTE_IDENTIFIERS_DEPLOYMENT_PATH,
+            "select_by_pk",
+        )
+        .await;
+        assert!(
+            query.contains(r#"FROM "public"."Album" AS "%0_Album""#),
+            "expected quoted identifiers in:\n{query}"
+        );
+    }
+
+    #[tokio::test]
+    async fn always_quote_lowercase_table() {
+        let query = explain_query(
+            common::ALWAYS_QUOTE_IDENTIFIERS_DEPLOYMENT_PATH,
+            "select_spatial_ref_sys_by_srid",
+        )
+        .await;
+        assert!(
+            query.contains(r#"FROM "public"."spatial_ref_sys" AS "%0_spatial_ref_sys""#),
+            "expected quoted identifiers in:\n{query}"
+        );
+        assert!(
+            query.contains(r#""%0_spatial_ref_sys"."srid" = "#),
+            "expected a quoted column in:\n{query}"
+        );
+    }
+
+    #[tokio::test]
+    async fn quote_when_needed_mixed_case_table() {
+        let query = explain_query(
+            common::QUOTE_IDENTIFIERS_WHEN_NEEDED_DEPLOYMENT_PATH,
+            "select_by_pk",
+        )
+        .await;
+        // The mixed case names would be folded to lowercase unquoted.
+        assert!(
+            query.contains(r#"FROM public."Album" AS "%0_Album""#),
+            "expected only the mixed case identifiers to be quoted in:\n{query}"
+        );
+        assert!(
+            query.contains(r#""%0_Album"."AlbumId" = "#),
+            "expected a quoted column in:\n{query}"
+        );
+    }
+
+    #[tokio::test]
+    async fn quote_when_needed_lowercase_table() {
+        let query = explain_query(
+            common::QUOTE_IDENTIFIERS_WHEN_NEEDED_DEPLOYMENT_PATH,
+            "select_spatial_ref_sys_by_srid",
+        )
+        .await;
+        assert!(
+            query.contains(r#"FROM public.spatial_ref_sys AS "%0_spatial_ref_sys""#),
+            "expected unquoted lowercase identifiers in:\n{query}"
+        );
+        assert!(
+            query.contains(r#""%0_spatial_ref_sys".srid = "#),
+            "expected an unquoted column in:\n{query}"
+        );
+    }
 }
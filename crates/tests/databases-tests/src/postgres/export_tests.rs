@@ -0,0 +1,105 @@
+//! Tests that the rows of a collection are exported with `COPY`.
+
+#[cfg(test)]
+mod export {
+    use std::fs;
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::export::ExportFormat;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    /// Export the albums, with their id and title, filtered by the given predicate if there is
+    /// one, and return the whole output.
+    async fn export_albums(predicate: Option<serde_json::Value>, format: ExportFormat) -> String {
+        let raw_configuration: configuration::RawConfiguration = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    common::CONNECTION_STRING.to_string(),
+                ),
+            });
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+
+        let mut query = serde_json::json!({
+            "fields": {
+                "AlbumId": {"type": "column", "column": "AlbumId", "arguments": {}},
+                "Title": {"type": "column", "column": "Title", "arguments": {}}
+            },
+            "order_by": {
+                "elements": [{
+                    "order_direction": "asc",
+                    "target": {"type": "column", "name": "AlbumId", "path": []}
+                }]
+            }
+        });
+        if let Some(predicate) = predicate {
+            query["where"] = predicate;
+        }
+        let request = serde_json::from_value(serde_json::json!({
+            "collection": "Album",
+            "query": query,
+            "arguments": {},
+            "collection_relationships": {}
+        }))
+        .unwrap();
+
+        let mut chunks =
+            ndc_postgres::export::export(&runtime_configuration, &state, request, format)
+                .await
+                .unwrap();
+        let mut output = vec![];
+        while let Some(chunk) = chunks.recv().await {
+            output.extend_from_slice(&chunk.unwrap());
+        }
+        state.pool.close().await;
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn every_album_is_exported_to_csv() {
+        let output = export_albums(None, ExportFormat::Csv).await;
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1 + 347, "{output}");
+        assert_eq!(lines[0], "AlbumId,Title");
+        assert_eq!(lines[1], "1,For Those About To Rock We Salute You");
+    }
+
+    #[tokio::test]
+    async fn filtered_albums_are_exported_to_json_lines() {
+        let predicate = serde_json::json!({
+            "type": "binary_comparison_operator",
+            "column": {"type": "column", "name": "Title", "path": []},
+            "operator": {"type": "equal"},
+            "value": {"type": "scalar", "value": "Let There Be Rock"}
+        });
+
+        let output = export_albums(Some(predicate), ExportFormat::JsonLines).await;
+
+        let rows: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![serde_json::json!({"AlbumId": 4, "Title": "Let There Be Rock"})]
+        );
+    }
+}
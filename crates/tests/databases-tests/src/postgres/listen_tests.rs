@@ -0,0 +1,71 @@
+//! Tests that notifications are forwarded to the subscribers of a channel.
+
+#[cfg(test)]
+mod listen {
+    use std::time::Duration;
+
+    use sqlx::{Connection, Executor, PgConnection};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::state;
+
+    use super::super::common;
+
+    #[tokio::test]
+    async fn notifications_from_another_connection_are_received() {
+        let channel = format!("listen_test_{}", std::process::id());
+        let state = state::create_state(
+            common::CONNECTION_STRING,
+            &configuration::PoolSettings::default(),
+            &[],
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+        let mut notifications = state.subscribe(&channel).await.unwrap();
+
+        let mut other = PgConnection::connect(common::CONNECTION_STRING)
+            .await
+            .unwrap();
+        other
+            .execute(format!("SELECT pg_notify('{channel}', 'first')").as_str())
+            .await
+            .unwrap();
+        let notification = tokio::time::timeout(Duration::from_secs(10), notifications.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(notification.channel, channel);
+        assert_eq!(notification.payload, "first");
+
+        // Drop the listening connection, after which a new one listens in its place. The
+        // notifications sent in between are lost, so we keep sending until one gets through.
+        other
+            .execute(
+                format!(
+                    "SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+                     WHERE query LIKE 'LISTEN %{channel}%' AND pid <> pg_backend_pid()"
+                )
+                .as_str(),
+            )
+            .await
+            .unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                other
+                    .execute(format!("SELECT pg_notify('{channel}', 'second')").as_str())
+                    .await
+                    .unwrap();
+                if let Ok(notification) =
+                    tokio::time::timeout(Duration::from_millis(100), notifications.recv()).await
+                {
+                    return notification;
+                }
+            }
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(received.payload, "second");
+    }
+}
@@ -1,7 +1,19 @@
+pub mod after_connect_sql_tests;
 pub mod common;
 pub mod configuration_tests;
+pub mod connection_check_tests;
+pub mod credential_rotation_tests;
+pub mod error_verbosity_tests;
 pub mod explain_tests;
+pub mod export_tests;
+pub mod listen_tests;
 pub mod mutation_tests;
+pub mod native_query_column_types_tests;
 pub mod ndc_tests;
+pub mod pgbouncer_tests;
 pub mod query_tests;
+pub mod region_tests;
+pub mod replication_lag_tests;
 pub mod schema_tests;
+pub mod statement_cache_tests;
+pub mod validation_query_tests;
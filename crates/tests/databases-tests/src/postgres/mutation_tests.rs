@@ -42,4 +42,23 @@ mod basic {
         clean_up_deployment(deployment).await.unwrap();
         insta::assert_json_snapshot!(result)
     }
+
+    #[tokio::test]
+    async fn update_reports_affected_rows() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+
+        let result = run_mutation(
+            tests_common::router::create_router_from_deployment(&deployment.deployment_path).await,
+            "update_album_track_composers",
+        )
+        .await;
+
+        clean_up_deployment(deployment).await.unwrap();
+        insta::assert_json_snapshot!(result)
+    }
 }
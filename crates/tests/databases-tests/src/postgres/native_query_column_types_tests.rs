@@ -0,0 +1,82 @@
+//! Tests that the columns of native queries are checked against their declared types in strict
+//! mode.
+
+#[cfg(test)]
+mod native_query_column_types {
+    use std::fs;
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::configuration::version2;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    /// Run the query selecting from the 'artist' native query in strict mode, with the type of its
+    /// 'Name' column declared as given.
+    async fn select_artist_with_name_declared_as(name_type: &str) -> Result<(), String> {
+        let mut raw_configuration: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        raw_configuration["metadata"]["nativeQueries"]["artist"]["columns"]["Name"]["type"] =
+            serde_json::json!({ "scalarType": name_type });
+        let raw_configuration = match serde_json::from_value(raw_configuration).unwrap() {
+            configuration::RawConfiguration::Version2(v2) => {
+                configuration::RawConfiguration::Version2(version2::RawConfiguration {
+                    strict_native_query_column_types: true,
+                    ..v2
+                })
+            }
+            configuration::RawConfiguration::Version1(_) => panic!("expected a v2 configuration"),
+        };
+        let runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    common::CONNECTION_STRING.to_string(),
+                ),
+            });
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+        let request = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                "crates/tests/tests-common/goldenfiles/native_queries/select_artist.json",
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let result = ndc_postgres::query::query(&runtime_configuration, &state, request)
+            .await
+            .map(|_| ())
+            .map_err(|err| err.to_string());
+        state.pool.close().await;
+        result
+    }
+
+    #[tokio::test]
+    async fn native_queries_returning_their_declared_types_are_run() {
+        assert!(select_artist_with_name_declared_as("varchar").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn native_queries_returning_other_types_are_rejected() {
+        let error = select_artist_with_name_declared_as("text")
+            .await
+            .expect_err("expected the query to fail");
+        assert!(
+            error.contains("Column 'Name' of native query 'artist' is declared with type 'text'"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Tests that queries leave no state behind on their connection in PgBouncer compatibility mode.
+
+#[cfg(test)]
+mod pgbouncer_compat {
+    use std::fs;
+
+    use sqlx::{Executor, Row};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::configuration::version2;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    #[tokio::test]
+    async fn queries_do_not_change_the_session() {
+        let raw_configuration = match serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            configuration::RawConfiguration::Version2(v2) => {
+                configuration::RawConfiguration::Version2(version2::RawConfiguration {
+                    default_isolation_level: Some(version2::IsolationLevel::Serializable),
+                    pgbouncer_compat: true,
+                    ..v2
+                })
+            }
+            configuration::RawConfiguration::Version1(_) => panic!("expected a v2 configuration"),
+        };
+        let mut runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    common::CONNECTION_STRING.to_string(),
+                ),
+            });
+        runtime_configuration.pool_settings.max_connections = 1;
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+        let request = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                "crates/tests/tests-common/goldenfiles/select_5.json",
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let result = ndc_postgres::query::query(&runtime_configuration, &state, request).await;
+
+        // Look at the session the query ran in, which is the only connection in the pool.
+        let mut connection = state.pool.acquire(&state.metrics).await.unwrap();
+        let session = connection
+            .as_mut()
+            .fetch_one(
+                "SELECT current_setting('transaction_isolation'),
+                        (SELECT count(*) FROM pg_prepared_statements)",
+            )
+            .await
+            .unwrap();
+        let isolation_level: String = session.get(0);
+        let prepared_statements: i64 = session.get(1);
+        drop(connection);
+        state.pool.close().await;
+
+        assert!(result.is_ok(), "query failed");
+        assert_eq!(isolation_level, "read committed");
+        assert_eq!(prepared_statements, 0);
+    }
+}
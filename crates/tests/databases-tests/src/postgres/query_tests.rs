@@ -15,6 +15,12 @@ mod basic {
         insta::assert_json_snapshot!(result);
     }
 
+    #[tokio::test]
+    async fn select_limit_zero() {
+        let result = run_query(create_router().await, "select_limit_zero").await;
+        insta::assert_json_snapshot!(result);
+    }
+
     #[tokio::test]
     async fn select_array_column() {
         let result = run_query(create_router().await, "select_array_column").await;
@@ -51,6 +57,18 @@ mod predicates {
         insta::assert_json_snapshot!(result);
     }
 
+    #[tokio::test]
+    async fn select_where_composer_neq() {
+        let result = run_query(create_router().await, "select_where_composer_neq").await;
+        insta::assert_json_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn select_where_composer_neq_or_null() {
+        let result = run_query(create_router().await, "select_where_composer_neq_or_null").await;
+        insta::assert_json_snapshot!(result);
+    }
+
     #[tokio::test]
     async fn select_where_name_like() {
         let result = run_query(create_router().await, "select_where_name_like").await;
@@ -315,6 +333,22 @@ mod aggregation {
         insta::assert_json_snapshot!(result);
     }
 
+    #[tokio::test]
+    async fn select_albums_with_total_count() {
+        let result = run_query(create_router().await, "select_albums_with_total_count").await;
+        insta::assert_json_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn select_albums_past_the_end_with_total_count() {
+        let result = run_query(
+            create_router().await,
+            "select_albums_past_the_end_with_total_count",
+        )
+        .await;
+        insta::assert_json_snapshot!(result);
+    }
+
     #[tokio::test]
     async fn aggregate_count_artist_albums() {
         let result = run_query(create_router().await, "aggregate_count_artist_albums").await;
@@ -330,6 +364,62 @@ mod aggregation {
         .await;
         insta::assert_json_snapshot!(result);
     }
+
+    // The 'Composer' column contains both duplicates and nulls. A distinct count ignores the
+    // nulls, just like a regular column count does.
+    #[tokio::test]
+    async fn aggregate_count_distinct_track_composers() {
+        let result = run_query(
+            create_router().await,
+            "aggregate_count_distinct_track_composers",
+        )
+        .await;
+        insta::assert_json_snapshot!(result);
+    }
+}
+
+#[cfg(test)]
+mod bool_aggregates {
+    use super::super::common;
+    use tests_common::request::run_query;
+
+    #[tokio::test]
+    async fn aggregate_bool_and_or_flags() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::BOOLEAN_FLAGS_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_query(router, "aggregate_bool_and_or_flags").await;
+
+        // Nulls are ignored, so the aggregates of a column which is always null are null.
+        let aggregates = result.0[0].aggregates.as_ref().unwrap();
+        assert_eq!(aggregates["all_flags"], serde_json::json!(false));
+        assert_eq!(aggregates["any_flag"], serde_json::json!(true));
+        assert_eq!(aggregates["all_unset"], serde_json::Value::Null);
+        assert_eq!(aggregates["any_unset"], serde_json::Value::Null);
+    }
+}
+
+#[cfg(test)]
+mod big_numbers {
+    use super::super::common;
+    use tests_common::request::run_query;
+
+    #[tokio::test]
+    async fn select_where_big_number() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::BIG_NUMBERS_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_query(router, "select_where_big_number").await;
+
+        // As a double, the value of the filter would also match its neighbour 2^53.
+        let rows = result.0[0].rows.as_ref().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["id"].0, 1);
+        assert_eq!(rows[0]["big"].0, "9007199254740993");
+        assert_eq!(rows[0]["exact"].0, "12345678901234567890.123456789");
+    }
 }
 
 #[cfg(test)]
@@ -347,6 +437,18 @@ mod relationships {
         insta::assert_json_snapshot!(result);
     }
 
+    #[tokio::test]
+    async fn select_album_and_artist_with_same_named_fields() {
+        // The album and its artist both have fields named 'ArtistId' and 'Name', which are
+        // returned apart, the artist's nested under the relationship field.
+        let result = run_query(
+            create_router().await,
+            "select_album_and_artist_with_same_named_fields",
+        )
+        .await;
+        insta::assert_json_snapshot!(result);
+    }
+
     #[tokio::test]
     async fn select_artist_array_relationship_to_album() {
         let result = run_query(
@@ -380,6 +482,22 @@ mod relationships {
         let result = run_query(create_router().await, "very_nested_recursive_relationship").await;
         insta::assert_json_snapshot!(result);
     }
+
+    #[tokio::test]
+    async fn select_employees_by_manager_with_manager() {
+        let result = run_query(
+            create_router().await,
+            "select_employees_by_manager_with_manager",
+        )
+        .await;
+        insta::assert_json_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn select_albums_by_inline_rows() {
+        let result = run_query(create_router().await, "select_albums_by_inline_rows").await;
+        insta::assert_json_snapshot!(result);
+    }
 }
 
 #[cfg(test)]
@@ -442,6 +560,26 @@ mod native_queries {
         .await;
         insta::assert_json_snapshot!(result);
     }
+
+    #[tokio::test]
+    async fn select_album_count_by_artist() {
+        let result = run_query(
+            create_router().await,
+            "native_queries/select_album_count_by_artist",
+        )
+        .await;
+        insta::assert_json_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn select_artists_with_albums() {
+        let result = run_query(
+            create_router().await,
+            "native_queries/select_artists_with_albums",
+        )
+        .await;
+        insta::assert_json_snapshot!(result);
+    }
 }
 
 #[cfg(test)]
@@ -455,3 +593,76 @@ mod types {
         insta::assert_json_snapshot!(result);
     }
 }
+
+#[cfg(test)]
+mod transactions {
+    use super::super::common;
+    use tests_common::request::run_query;
+
+    #[tokio::test]
+    async fn select_runs_with_the_configured_isolation_level() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::ISOLATION_LEVEL_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_query(router, "select_transaction_isolation").await;
+        insta::assert_json_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn select_runs_as_the_role_of_the_collection() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::COLLECTION_ROLES_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_query(router, "select_current_role").await;
+        let rows = result.0[0].rows.as_ref().unwrap();
+        assert_eq!(rows[0]["role"].0, "pg_monitor");
+    }
+}
+
+#[cfg(test)]
+mod system_columns {
+    use super::super::common;
+    use tests_common::request::run_query;
+
+    #[tokio::test]
+    async fn select_xmin_as_a_row_version() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::SYSTEM_COLUMNS_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_query(router, "select_album_xmin").await;
+
+        // The transaction id which last wrote the row depends on how the database was set up,
+        // so only its shape is checked.
+        let rows = result.0[0].rows.as_ref().unwrap();
+        assert_eq!(rows.len(), 1);
+        let xmin = &rows[0]["xmin"].0;
+        assert!(
+            xmin.as_str()
+                .is_some_and(|xmin| xmin.parse::<u32>().is_ok()),
+            "expected a transaction id, got {xmin}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod limit_with_ties {
+    use super::super::common;
+    use tests_common::request::run_query;
+
+    #[tokio::test]
+    async fn select_keeps_the_rows_tied_with_the_last_one() {
+        let router = tests_common::router::create_router_from_deployment(
+            common::LIMIT_WITH_TIES_DEPLOYMENT_PATH,
+        )
+        .await;
+        let result = run_query(router, "select_album_ids_by_artist_limit_3").await;
+
+        // The third album is by the second artist, who has a fourth album as well.
+        let rows = result.0[0].rows.as_ref().unwrap();
+        let artist_ids: Vec<_> = rows.iter().map(|row| row["ArtistId"].0.clone()).collect();
+        assert_eq!(artist_ids, vec![1, 1, 2, 2]);
+    }
+}
@@ -0,0 +1,84 @@
+//! Tests that the connector prefers the connection URIs in its own region.
+
+#[cfg(test)]
+mod region {
+    use std::fs;
+
+    use sqlx::{Executor, Row};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::configuration::version2;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    /// A URI of the database in the given region, which names the region as the application.
+    fn connection_uri_in(region: &str) -> version2::RegionalConnectionUri {
+        version2::RegionalConnectionUri {
+            connection_uri: format!("{}?application_name={region}", common::CONNECTION_STRING)
+                .into(),
+            region: Some(region.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn reachable_connection_uris_in_the_same_region_are_preferred() {
+        let raw_configuration = match serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            configuration::RawConfiguration::Version2(v2) => {
+                configuration::RawConfiguration::Version2(version2::RawConfiguration {
+                    pool_settings: version2::PoolSettings {
+                        pool_timeout: 1,
+                        ..v2.pool_settings.clone()
+                    },
+                    connection_uris: vec![
+                        connection_uri_in("us-east-1"),
+                        version2::RegionalConnectionUri {
+                            connection_uri: "postgresql://nobody@127.0.0.1:1/nothing".into(),
+                            region: Some("eu-west-1".to_string()),
+                        },
+                        connection_uri_in("eu-west-1"),
+                    ],
+                    region: Some("eu-west-1".to_string()),
+                    ..v2
+                })
+            }
+            configuration::RawConfiguration::Version1(_) => panic!("expected a v2 configuration"),
+        };
+        let runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    common::CONNECTION_STRING.to_string(),
+                ),
+            });
+
+        // The unreachable URI in the same region is skipped for the reachable one, which is
+        // preferred over the one in another region.
+        let state = state::create_state_for_region(
+            &runtime_configuration,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+
+        let mut connection = state.pool.acquire(&state.metrics).await.unwrap();
+        let application_name: String = connection
+            .as_mut()
+            .fetch_one("SELECT current_setting('application_name')")
+            .await
+            .unwrap()
+            .get(0);
+        drop(connection);
+        state.pool.close().await;
+
+        assert_eq!(application_name, "eu-west-1");
+    }
+}
@@ -0,0 +1,49 @@
+//! Tests that the health check reads the replication lag of the database.
+
+#[cfg(test)]
+mod replication_lag {
+    use std::time::Duration;
+
+    use sqlx::{Connection, PgConnection};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::health;
+    use ndc_postgres::state;
+
+    use super::super::common;
+
+    #[tokio::test]
+    async fn a_primary_has_no_replication_lag() {
+        let mut connection = PgConnection::connect(common::CONNECTION_STRING)
+            .await
+            .unwrap();
+
+        let lag = health::replication_lag(&mut connection).await;
+        connection.close().await.unwrap();
+
+        assert_eq!(lag.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn a_primary_is_healthy_whatever_the_maximum_replication_lag() {
+        let state = state::create_state(
+            common::CONNECTION_STRING,
+            &configuration::PoolSettings::default(),
+            &[],
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+
+        let health = health::health_check(
+            &state.pool,
+            &state.metrics,
+            Some(Duration::from_secs(0)),
+            None,
+        )
+        .await;
+        state.pool.close().await;
+
+        assert!(health.is_ok(), "{health:?}");
+    }
+}
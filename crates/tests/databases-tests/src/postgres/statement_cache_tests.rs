@@ -0,0 +1,81 @@
+//! Tests that the prepared statement cache follows the pool settings.
+
+#[cfg(test)]
+mod statement_cache {
+    use std::fs;
+
+    use sqlx::{Executor, Row};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+
+    use super::super::common;
+
+    /// Run a query on a single connection with the given statement cache capacity, and return the
+    /// number of prepared statements left on that connection.
+    async fn prepared_statements_after_a_query(statement_cache_capacity: Option<usize>) -> i64 {
+        let raw_configuration: configuration::RawConfiguration = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let mut runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    common::CONNECTION_STRING.to_string(),
+                ),
+            });
+        runtime_configuration.pool_settings.max_connections = 1;
+        runtime_configuration.pool_settings.statement_cache_capacity = statement_cache_capacity;
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+        let request = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                "crates/tests/tests-common/goldenfiles/select_5.json",
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let result = ndc_postgres::query::query(&runtime_configuration, &state, request).await;
+        assert!(result.is_ok(), "query failed");
+
+        // A query without parameters is sent with the simple query protocol, so it is not
+        // prepared itself.
+        let mut connection = state.pool.acquire(&state.metrics).await.unwrap();
+        let count: i64 = connection
+            .as_mut()
+            .fetch_one("SELECT count(*) FROM pg_prepared_statements")
+            .await
+            .unwrap()
+            .get(0);
+        drop(connection);
+        state.pool.close().await;
+        count
+    }
+
+    #[tokio::test]
+    async fn queries_are_kept_as_prepared_statements_by_default() {
+        assert!(prepared_statements_after_a_query(None).await > 0);
+    }
+
+    #[tokio::test]
+    async fn queries_are_kept_as_prepared_statements_with_a_cache_capacity() {
+        assert!(prepared_statements_after_a_query(Some(1)).await > 0);
+    }
+
+    #[tokio::test]
+    async fn queries_are_not_kept_as_prepared_statements_without_a_cache() {
+        assert_eq!(prepared_statements_after_a_query(Some(0)).await, 0);
+    }
+}
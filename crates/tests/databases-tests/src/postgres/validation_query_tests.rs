@@ -0,0 +1,152 @@
+//! Tests that the `validationQuery` pool setting decides whether connections are healthy.
+
+#[cfg(test)]
+mod validation_query {
+    use std::fs;
+
+    use sqlx::{Connection, Executor, PgConnection};
+
+    use ndc_postgres::configuration;
+    use ndc_postgres::health;
+    use ndc_postgres::state;
+    use tests_common::deployment::helpers::get_path_from_project_root;
+    use tests_common::deployment::{clean_up_deployment, create_fresh_deployment};
+
+    use super::super::common;
+
+    #[tokio::test]
+    async fn a_failing_validation_query_makes_the_connector_unhealthy() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(r#"CREATE SCHEMA "app"; CREATE TABLE "app"."settings" ()"#)
+            .await
+            .unwrap();
+
+        let raw_configuration: configuration::RawConfiguration = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let mut runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    connection_string.clone(),
+                ),
+            });
+        runtime_configuration.pool_settings.validation_query =
+            Some(r#"SELECT 1 FROM "app"."settings" LIMIT 1"#.to_string());
+        let validation_query = runtime_configuration
+            .pool_settings
+            .validation_query
+            .as_deref();
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+        let healthy_before =
+            health::health_check(&state.pool, &state.metrics, None, validation_query).await;
+
+        connection
+            .execute(r#"DROP SCHEMA "app" CASCADE"#)
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+        let healthy_after =
+            health::health_check(&state.pool, &state.metrics, None, validation_query).await;
+
+        state.pool.close().await;
+        clean_up_deployment(deployment).await.unwrap();
+
+        assert!(healthy_before.is_ok(), "{healthy_before:?}");
+        let err = healthy_after.unwrap_err().to_string();
+        assert!(err.contains("app"), "unexpected error: {err}");
+    }
+
+    #[tokio::test]
+    async fn the_validation_query_cannot_write_to_the_database() {
+        let deployment = create_fresh_deployment(
+            common::CONNECTION_STRING,
+            common::CHINOOK_DEPLOYMENT_PATH_V2,
+        )
+        .await
+        .unwrap();
+        let connection_string = format!("{}/{}", common::CONNECTION_STRING, deployment.db_name);
+        let mut connection = PgConnection::connect(&connection_string).await.unwrap();
+        connection
+            .execute(
+                r#"
+                CREATE TABLE "checks" ("checked_at" timestamptz);
+                CREATE FUNCTION "record_check"() RETURNS int4 LANGUAGE sql AS
+                  'INSERT INTO "checks" VALUES (now()) RETURNING 1';
+                "#,
+            )
+            .await
+            .unwrap();
+
+        let raw_configuration: configuration::RawConfiguration = serde_json::from_str(
+            &fs::read_to_string(get_path_from_project_root(
+                common::CHINOOK_DEPLOYMENT_PATH_V2,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        let mut runtime_configuration =
+            configuration::as_runtime_configuration(&configuration::Configuration {
+                config: configuration::set_connection_uri(
+                    raw_configuration,
+                    connection_string.clone(),
+                ),
+            });
+        runtime_configuration.pool_settings.validation_query =
+            Some(r#"SELECT "record_check"()"#.to_string());
+
+        let state = state::create_state(
+            &runtime_configuration.connection_uri.0,
+            &runtime_configuration.pool_settings,
+            &runtime_configuration.after_connect_sql,
+            &mut prometheus::Registry::new(),
+        )
+        .await
+        .unwrap();
+        let health = health::health_check(
+            &state.pool,
+            &state.metrics,
+            None,
+            runtime_configuration
+                .pool_settings
+                .validation_query
+                .as_deref(),
+        )
+        .await;
+        let checks: i64 = sqlx::query_scalar(r#"SELECT count(*) FROM "checks""#)
+            .fetch_one(&mut connection)
+            .await
+            .unwrap();
+
+        connection.close().await.unwrap();
+        state.pool.close().await;
+        clean_up_deployment(deployment).await.unwrap();
+
+        let err = health.unwrap_err().to_string();
+        assert!(
+            err.contains("read-only transaction"),
+            "unexpected error: {err}"
+        );
+        assert_eq!(checks, 0);
+    }
+}
@@ -10,6 +10,12 @@ pub async fn run_query(router: axum::Router, testname: &str) -> ndc_sdk::models:
     run_against_server(router, "query", testname).await
 }
 
+/// Run a query against the server, and get the response as JSON, including anything which is not
+/// part of the specification.
+pub async fn run_query_json(router: axum::Router, testname: &str) -> serde_json::Value {
+    run_against_server(router, "query", testname).await
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize)]
 pub struct ExactExplainResponse {
     pub details: ExplainDetails,